@@ -0,0 +1,15 @@
+// 对内存输入的抽帧入口做模糊测试
+//
+// 运行方式: cargo +nightly fuzz run extract_frame_from_memory
+// 输入的第一个字节用于选择时间点，其余字节作为视频数据。
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use video_capture_wasm::video_processor;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((&time_byte, video_data)) = data.split_first() {
+        // 任何输入都只能返回 Ok 或 Err，不能崩溃
+        let _ = video_processor::extract_frame_from_memory(video_data, f64::from(time_byte) / 10.0);
+    }
+});
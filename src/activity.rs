@@ -0,0 +1,167 @@
+// activity.rs
+// 视觉活跃度时间线
+//
+// 为进度条热度图提供数据：把整个视频按秒分桶，每秒的活跃度由画面运动量
+// 和亮度方差组成。运动量反映镜头内的变化，亮度方差区分内容丰富的画面和
+// 黑场、纯色画面。结果归一化到 0.0 - 1.0，可以直接使用，也可以渲染成图像。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::video_processor;
+
+// 分析用的亮度缩略图尺寸
+const THUMB_WIDTH: u32 = 64;
+const THUMB_HEIGHT: u32 = 36;
+
+// 每秒取样的次数
+const SAMPLES_PER_SECOND: f64 = 4.0;
+
+// 运动量和亮度方差在活跃度中所占的权重
+const MOTION_WEIGHT: f64 = 0.7;
+const VARIANCE_WEIGHT: f64 = 0.3;
+
+// 热度图中最低和最高活跃度对应的颜色
+const COLD: [f32; 3] = [40.0, 60.0, 160.0];
+const HOT: [f32; 3] = [240.0, 60.0, 40.0];
+
+/// 计算每秒的视觉活跃度
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<Vec<f32>, VideoError>` - 成功时返回每秒一个的活跃度（0.0 - 1.0），
+///   第 `i` 项对应视频开始后的第 `i` 秒，按整个视频中的最大值归一化
+pub fn activity_timeline<P: AsRef<Path>>(input_path: P) -> Result<Vec<f32>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let options = config::default_options();
+    let mut timeline = Timeline::default();
+    video_processor::for_each_video_frame(input_path.as_ref(), &options, 0.0, |frame, time| {
+        if timeline.due(time) {
+            let luma =
+                video_processor::convert_to_luma(frame, THUMB_WIDTH, THUMB_HEIGHT, &options)?;
+            timeline.push(time, luma);
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    Ok(timeline.finish())
+}
+
+/// 把活跃度渲染成热度图
+///
+/// 每列对应一段时间，柱高和颜色随活跃度增加，从底部向上填充，其余部分为黑色。
+///
+/// # 参数
+/// * `values` - `activity_timeline` 返回的活跃度
+/// * `width` - 图像宽度（像素）
+/// * `height` - 图像高度（像素）
+///
+/// # 返回
+/// * `Vec<u8>` - RGB24格式的图像数据，逐行紧密排列
+pub fn render_activity_heatmap(values: &[f32], width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut image = vec![0u8; width * height * 3];
+    if values.is_empty() || width == 0 || height == 0 {
+        return image;
+    }
+
+    for x in 0..width {
+        // 列中心对应的活跃度，相邻两秒之间线性插值
+        let position = (x as f32 + 0.5) / width as f32 * values.len() as f32 - 0.5;
+        let position = position.clamp(0.0, (values.len() - 1) as f32);
+        let index = position.floor() as usize;
+        let next = (index + 1).min(values.len() - 1);
+        let fraction = position - index as f32;
+        let value = (values[index] * (1.0 - fraction) + values[next] * fraction).clamp(0.0, 1.0);
+
+        let color: [u8; 3] = std::array::from_fn(|c| (COLD[c] + (HOT[c] - COLD[c]) * value) as u8);
+        let filled = (value * height as f32).round() as usize;
+        for y in height - filled..height {
+            let offset = (y * width + x) * 3;
+            image[offset..offset + 3].copy_from_slice(&color);
+        }
+    }
+
+    image
+}
+
+// 按秒累计的活跃度
+#[derive(Default)]
+struct Timeline {
+    start: Option<f64>,
+    next_sample: f64,
+    previous: Option<Vec<u8>>,
+    // 每秒的活跃度总和与样本数
+    buckets: Vec<(f64, u32)>,
+}
+
+impl Timeline {
+    // 是否到了下一次取样的时间
+    fn due(&mut self, time: f64) -> bool {
+        if self.start.is_some() && time < self.next_sample {
+            return false;
+        }
+        self.next_sample = time + 1.0 / SAMPLES_PER_SECOND;
+        true
+    }
+
+    fn push(&mut self, time: f64, luma: Vec<u8>) {
+        let start = *self.start.get_or_insert(time);
+        let pixels = luma.len().max(1) as f64;
+
+        let motion = self.previous.as_ref().map_or(0.0, |previous| {
+            let diff: u64 = luma
+                .iter()
+                .zip(previous)
+                .map(|(&a, &b)| u64::from(a.abs_diff(b)))
+                .sum();
+            diff as f64 / (pixels * 255.0)
+        });
+
+        let mean = luma.iter().map(|&v| f64::from(v)).sum::<f64>() / pixels;
+        let variance = luma
+            .iter()
+            .map(|&v| (f64::from(v) - mean).powi(2))
+            .sum::<f64>()
+            / pixels;
+        // 标准差最大约为128，归一化到 0.0 - 1.0
+        let spread = (variance.sqrt() / 128.0).min(1.0);
+
+        // 时间戳回退的帧归入第一秒
+        let second = (time - start).max(0.0) as usize;
+        if self.buckets.len() <= second {
+            self.buckets.resize(second + 1, (0.0, 0));
+        }
+        let bucket = &mut self.buckets[second];
+        bucket.0 += MOTION_WEIGHT * motion + VARIANCE_WEIGHT * spread;
+        bucket.1 += 1;
+
+        self.previous = Some(luma);
+    }
+
+    fn finish(self) -> Vec<f32> {
+        // 没有样本的秒（例如帧率很低的片段）沿用前一秒的值
+        let mut values = Vec::with_capacity(self.buckets.len());
+        let mut last = 0.0;
+        for (sum, count) in self.buckets {
+            if count > 0 {
+                last = sum / f64::from(count);
+            }
+            values.push(last);
+        }
+
+        let max = values.iter().copied().fold(0.0, f64::max);
+        if max > 0.0 {
+            values.iter().map(|v| (v / max) as f32).collect()
+        } else {
+            vec![0.0; values.len()]
+        }
+    }
+}
@@ -0,0 +1,492 @@
+// audio.rs
+// 解码音频片段为PCM/WAV
+//
+// 页面上试听一小段音频时，浏览器不一定能解码容器里的音频编码（AC-3、部分AAC变体等），
+// 也不方便只取其中一段。这里用FFmpeg解码主音频流的指定区间，重采样为需要的采样率和声道数，
+// 输出交错排列的PCM，可以直接填入Web Audio的 AudioBuffer，也可以加上WAV头交给
+// `decodeAudioData` 或 `<audio>` 播放。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::encode;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::memory_watch;
+use crate::validation;
+use crate::video_processor;
+
+use ffmpeg::format::{sample, Sample};
+use ffmpeg::software::resampling;
+use ffmpeg::{util::frame::audio::Audio, ChannelLayout};
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+// 支持的输出采样率范围
+const MIN_SAMPLE_RATE: u32 = 8000;
+const MAX_SAMPLE_RATE: u32 = 192_000;
+
+// 输出声道数上限，多于两个声道时按FFmpeg的默认布局排列（如6声道为5.1）
+const MAX_CHANNELS: u32 = 8;
+
+// 一次最多解码的时长（秒），片段的PCM数据全部保存在内存中
+const MAX_DURATION: f64 = 600.0;
+
+// 片段PCM数据的字节数上限；最高采样率、最多声道时600秒的浮点数据约3.7GB，超出WASM的寻址范围
+const MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+// WAV格式块中的格式标签
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+// WAVE_FORMAT_EXTENSIBLE 子格式GUID中格式标签之后的固定部分（KSDATAFORMAT_SUBTYPE_*）
+const SUBFORMAT_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// PCM采样格式
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PcmFormat {
+    /// 32位浮点，范围 -1.0 - 1.0，与Web Audio的 AudioBuffer 一致
+    #[default]
+    F32,
+    /// 16位有符号整数
+    I16,
+}
+
+impl PcmFormat {
+    /// 每个采样的字节数
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            PcmFormat::F32 => 4,
+            PcmFormat::I16 => 2,
+        }
+    }
+
+    fn sample(self) -> Sample {
+        match self {
+            PcmFormat::F32 => Sample::F32(sample::Type::Packed),
+            PcmFormat::I16 => Sample::I16(sample::Type::Packed),
+        }
+    }
+}
+
+/// 解码出的音频片段
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioClip {
+    /// 交错排列的PCM数据，本机字节序（WebAssembly和常见平台上为小端，与WAV一致）
+    pub data: Vec<u8>,
+    /// 采样率（Hz）
+    pub sample_rate: u32,
+    /// 声道数
+    pub channels: u32,
+    /// 采样格式
+    pub format: PcmFormat,
+    /// 第一个采样的时间（秒），文件在该时间点之后才有音频时晚于请求的起点
+    pub start: f64,
+}
+
+#[wasm_bindgen]
+impl AudioClip {
+    /// 每个声道的采样数
+    #[wasm_bindgen(js_name = sampleFrames)]
+    pub fn sample_frames(&self) -> usize {
+        self.data.len() / (self.channels as usize * self.format.bytes_per_sample()).max(1)
+    }
+
+    /// 片段的时长（秒）
+    pub fn duration(&self) -> f64 {
+        self.sample_frames() as f64 / f64::from(self.sample_rate.max(1))
+    }
+
+    /// 加上RIFF/WAVE头，得到完整的WAV文件
+    ///
+    /// 多于两个声道时使用 `WAVE_FORMAT_EXTENSIBLE`，声道掩码与提取时使用的FFmpeg默认布局一致；
+    /// 格式标签不是整数PCM时（浮点或扩展格式）附带 `fact` 块。
+    #[wasm_bindgen(js_name = toWav)]
+    pub fn to_wav(&self) -> Vec<u8> {
+        let bytes_per_sample = self.format.bytes_per_sample() as u32;
+        let block_align = self.channels * bytes_per_sample;
+        let bits = bytes_per_sample as u16 * 8;
+        let format_tag = match self.format {
+            PcmFormat::F32 => WAVE_FORMAT_IEEE_FLOAT,
+            PcmFormat::I16 => WAVE_FORMAT_PCM,
+        };
+        let extensible = self.channels > 2;
+
+        let mut fmt = Vec::with_capacity(40);
+        let tag = if extensible {
+            WAVE_FORMAT_EXTENSIBLE
+        } else {
+            format_tag
+        };
+        fmt.extend_from_slice(&tag.to_le_bytes());
+        fmt.extend_from_slice(&(self.channels as u16).to_le_bytes());
+        fmt.extend_from_slice(&self.sample_rate.to_le_bytes());
+        fmt.extend_from_slice(&(self.sample_rate * block_align).to_le_bytes());
+        fmt.extend_from_slice(&(block_align as u16).to_le_bytes());
+        fmt.extend_from_slice(&bits.to_le_bytes());
+        if extensible {
+            // 扩展部分：有效位数、声道掩码（与FFmpeg的声道位定义相同）和子格式GUID
+            let mask = ChannelLayout::default(self.channels as i32).bits() as u32;
+            fmt.extend_from_slice(&22u16.to_le_bytes());
+            fmt.extend_from_slice(&bits.to_le_bytes());
+            fmt.extend_from_slice(&mask.to_le_bytes());
+            fmt.extend_from_slice(&format_tag.to_le_bytes());
+            fmt.extend_from_slice(&SUBFORMAT_GUID_TAIL);
+        } else if format_tag != WAVE_FORMAT_PCM {
+            // 非整数PCM的格式块带有扩展长度字段
+            fmt.extend_from_slice(&0u16.to_le_bytes());
+        }
+
+        let mut wav = Vec::with_capacity(80 + self.data.len());
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&0u32.to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        encode::push_chunk(&mut wav, b"fmt ", &fmt);
+        // 非整数PCM的文件需要 `fact` 块记录每个声道的采样数
+        if tag != WAVE_FORMAT_PCM {
+            let frames = self.sample_frames() as u32;
+            encode::push_chunk(&mut wav, b"fact", &frames.to_le_bytes());
+        }
+        encode::push_chunk(&mut wav, b"data", &self.data);
+        let riff_len = wav.len() as u32 - 8;
+        wav[4..8].copy_from_slice(&riff_len.to_le_bytes());
+        wav
+    }
+}
+
+/// 解码主音频流的一段为PCM
+///
+/// 从 `start_sec` 之前的关键帧开始解码，重采样后按时间裁剪出请求的区间。
+/// 文件在区间结束前就没有音频时，返回的片段相应较短。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `start_sec` - 片段的开始时间（秒）
+/// * `duration` - 片段的时长（秒），不超过600秒，PCM数据不超过512MB
+/// * `sample_rate` - 输出采样率（8000 - 192000 Hz），Web Audio常用44100或48000
+/// * `channels` - 输出声道数（1 - 8），与源声道数不同时由FFmpeg混音或扩展
+/// * `format` - 输出采样格式
+///
+/// # 返回
+/// * `Result<AudioClip, VideoError>` - 成功时返回音频片段，没有音频流时返回错误
+pub fn extract_audio<P: AsRef<Path>>(
+    input_path: P,
+    start_sec: f64,
+    duration: f64,
+    sample_rate: u32,
+    channels: u32,
+    format: PcmFormat,
+) -> Result<AudioClip, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    validation::validate_time(start_sec)?;
+    if !(duration.is_finite() && duration > 0.0 && duration <= MAX_DURATION) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "片段时长必须在 0 - {} 秒之间: {}",
+                MAX_DURATION, duration
+            )),
+        ));
+    }
+    if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "采样率必须在 {} - {} Hz 之间: {}",
+                MIN_SAMPLE_RATE, MAX_SAMPLE_RATE, sample_rate
+            )),
+        ));
+    }
+    if !(1..=MAX_CHANNELS).contains(&channels) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "声道数必须在 1 - {} 之间: {}",
+                MAX_CHANNELS, channels
+            )),
+        ));
+    }
+
+    let wanted = (duration * f64::from(sample_rate)).round() as u64;
+    let bytes = wanted * u64::from(channels) * format.bytes_per_sample() as u64;
+    if bytes > MAX_BYTES {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "片段的PCM数据约 {} 字节，超出上限 {} 字节，请缩短时长或降低采样率、声道数",
+                bytes, MAX_BYTES
+            )),
+        ));
+    }
+
+    let layout = ChannelLayout::default(channels as i32);
+    let mut collector = PcmCollector {
+        start: start_sec,
+        wanted: wanted as usize,
+        frame_bytes: channels as usize * format.bytes_per_sample(),
+        rate: f64::from(sample_rate),
+        next_time: None,
+        first_time: None,
+        data: Vec::new(),
+    };
+    memory_watch::reserve(bytes as usize)?;
+    let mut resampler: Option<resampling::Context> = None;
+    let mut done = false;
+
+    // 从开始时间之前的关键帧开始，重采样每一帧并收集区间内的采样，取够后停止
+    video_processor::for_each_audio_frame(
+        input_path.as_ref(),
+        &config::default_options(),
+        start_sec,
+        |frame, time| {
+            if collector.next_time.is_none() {
+                collector.next_time = Some(time.unwrap_or(0.0));
+            }
+
+            // 个别文件中途改变采样率或声道布局，此时重新创建重采样器
+            let input_layout = source_layout(frame);
+            let stale = resampler.as_ref().is_none_or(|context| {
+                let input = context.input();
+                input.format != frame.format()
+                    || input.rate != frame.rate()
+                    || input.channel_layout != input_layout
+            });
+            let context = match resampler.take() {
+                Some(context) if !stale => resampler.insert(context),
+                previous => {
+                    // 丢弃旧的重采样器之前取出其中缓存的采样
+                    if let Some(mut previous) = previous {
+                        if flush_resampler(&mut previous, &mut collector, format, layout) {
+                            done = true;
+                            return Ok(ControlFlow::Break(()));
+                        }
+                    }
+                    resampler.insert(
+                        resampling::Context::get(
+                            frame.format(),
+                            input_layout,
+                            frame.rate(),
+                            format.sample(),
+                            layout,
+                            sample_rate,
+                        )
+                        .map_err(|e| {
+                            VideoError::new(
+                                VideoErrorCode::FFmpegError,
+                                Some(format!("无法创建音频重采样器: {}", e)),
+                            )
+                        })?,
+                    )
+                }
+            };
+
+            let mut output = Audio::new(
+                format.sample(),
+                output_capacity(context, frame.samples()),
+                layout,
+            );
+            context.run(frame, &mut output).map_err(|e| {
+                VideoError::new(
+                    VideoErrorCode::FFmpegError,
+                    Some(format!("音频重采样失败: {}", e)),
+                )
+            })?;
+            done = collector.push(&output);
+            Ok(if done {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            })
+        },
+    )?;
+
+    // 取出重采样器内部缓存的最后一部分采样
+    if let Some(context) = resampler.as_mut().filter(|_| !done) {
+        flush_resampler(context, &mut collector, format, layout);
+    }
+
+    Ok(AudioClip {
+        start: collector.first_time.unwrap_or(start_sec),
+        data: collector.data,
+        sample_rate,
+        channels,
+        format,
+    })
+}
+
+// 帧的声道布局；部分解码器只给出声道数，此时使用该声道数的默认布局
+pub(crate) fn source_layout(frame: &Audio) -> ChannelLayout {
+    let layout = frame.channel_layout();
+    if layout.is_empty() {
+        ChannelLayout::default(i32::from(frame.channels()))
+    } else {
+        layout
+    }
+}
+
+// 输出帧的容量按重采样器给出的上限分配，容量不足时多出的采样会一直积压在重采样器内部
+fn output_capacity(context: &mut resampling::Context, input_samples: usize) -> usize {
+    // 安全性：重采样器已经初始化，只查询输出采样数
+    let samples =
+        unsafe { ffmpeg::ffi::swr_get_out_samples(context.as_mut_ptr(), input_samples as i32) };
+    samples.max(1) as usize
+}
+
+// 取出重采样器内部缓存的采样交给 `collector`，返回是否已经取够
+fn flush_resampler(
+    context: &mut resampling::Context,
+    collector: &mut PcmCollector,
+    format: PcmFormat,
+    layout: ChannelLayout,
+) -> bool {
+    let mut output = Audio::new(format.sample(), output_capacity(context, 0), layout);
+    context.flush(&mut output).is_ok() && collector.push(&output)
+}
+
+// 按时间裁剪并收集重采样后的交错PCM
+struct PcmCollector {
+    start: f64,
+    // 需要的采样帧数（每个声道的采样数）
+    wanted: usize,
+    // 一个采样帧（所有声道）的字节数
+    frame_bytes: usize,
+    rate: f64,
+    // 下一个输出采样的时间（秒），由第一帧的时间戳开始顺延
+    next_time: Option<f64>,
+    // 收集到的第一个采样的时间
+    first_time: Option<f64>,
+    data: Vec<u8>,
+}
+
+impl PcmCollector {
+    // 收集一帧输出中落在区间内的采样，返回是否已经取够
+    fn push(&mut self, output: &Audio) -> bool {
+        let samples = output.samples();
+        let time = self.next_time.unwrap_or(0.0);
+        self.next_time = Some(time + samples as f64 / self.rate);
+        if samples == 0 {
+            return self.is_full();
+        }
+
+        // 开始时间之前的采样丢弃
+        let skip = (((self.start - time) * self.rate).round().max(0.0) as usize).min(samples);
+        let remaining = self.wanted - self.data.len() / self.frame_bytes;
+        let count = (samples - skip).min(remaining);
+        if count > 0 {
+            self.first_time
+                .get_or_insert(time + skip as f64 / self.rate);
+            let data = output.data(0);
+            self.data.extend_from_slice(
+                &data[skip * self.frame_bytes..(skip + count) * self.frame_bytes],
+            );
+        }
+        self.is_full()
+    }
+
+    fn is_full(&self) -> bool {
+        self.data.len() >= self.wanted * self.frame_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(channels: u32, format: PcmFormat, frames: usize) -> AudioClip {
+        AudioClip {
+            data: vec![0; frames * channels as usize * format.bytes_per_sample()],
+            sample_rate: 48000,
+            channels,
+            format,
+            start: 0.0,
+        }
+    }
+
+    fn u16_at(wav: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([wav[offset], wav[offset + 1]])
+    }
+
+    fn u32_at(wav: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(wav[offset..offset + 4].try_into().unwrap())
+    }
+
+    // 测试WAV头：整数PCM只有16字节的格式块，浮点带扩展长度字段和fact块，
+    // 多声道使用 WAVE_FORMAT_EXTENSIBLE 和FFmpeg默认布局的声道掩码
+    #[test]
+    fn test_to_wav() {
+        let wav = clip(2, PcmFormat::I16, 4).to_wav();
+        assert_eq!(wav.len(), 12 + 8 + 16 + 8 + 16);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32_at(&wav, 4) as usize, wav.len() - 8);
+        assert_eq!(&wav[12..16], b"fmt ");
+        assert_eq!(u32_at(&wav, 16), 16);
+        assert_eq!(u16_at(&wav, 20), WAVE_FORMAT_PCM);
+        assert_eq!(u32_at(&wav, 28), 48000 * 4);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(&wav, 40), 16);
+
+        let wav = clip(1, PcmFormat::F32, 2).to_wav();
+        assert_eq!(u32_at(&wav, 16), 18);
+        assert_eq!(u16_at(&wav, 20), WAVE_FORMAT_IEEE_FLOAT);
+        assert_eq!(u16_at(&wav, 36), 0);
+        assert_eq!(&wav[38..42], b"fact");
+        assert_eq!(u32_at(&wav, 46), 2);
+        assert_eq!(&wav[50..54], b"data");
+        assert_eq!(u32_at(&wav, 4) as usize, wav.len() - 8);
+
+        let wav = clip(6, PcmFormat::F32, 3).to_wav();
+        assert_eq!(u32_at(&wav, 16), 40);
+        assert_eq!(u16_at(&wav, 20), WAVE_FORMAT_EXTENSIBLE);
+        assert_eq!(u16_at(&wav, 22), 6);
+        assert_eq!(u16_at(&wav, 36), 22);
+        assert_eq!(u16_at(&wav, 38), 32);
+        let mask = u32_at(&wav, 40);
+        assert_eq!(mask, ChannelLayout::default(6).bits() as u32);
+        assert_eq!(mask.count_ones(), 6);
+        assert_eq!(u16_at(&wav, 44), WAVE_FORMAT_IEEE_FLOAT);
+        assert_eq!(wav[46..60], SUBFORMAT_GUID_TAIL);
+        assert_eq!(&wav[60..64], b"fact");
+        assert_eq!(u32_at(&wav, 68), 3);
+    }
+
+    // 测试收集PCM：丢弃开始时间之前的采样，取够后停止并记录第一个采样的时间
+    #[test]
+    fn test_pcm_collector() {
+        let frame = |base: i16| {
+            let mut frame = Audio::new(PcmFormat::I16.sample(), 8, ChannelLayout::MONO);
+            for (i, sample) in frame.data_mut(0).chunks_exact_mut(2).take(8).enumerate() {
+                sample.copy_from_slice(&(base + i as i16).to_le_bytes());
+            }
+            frame
+        };
+        let mut collector = PcmCollector {
+            start: 1.0,
+            wanted: 5,
+            frame_bytes: 2,
+            rate: 10.0,
+            next_time: Some(0.5),
+            first_time: None,
+            data: Vec::new(),
+        };
+
+        // 第一帧从0.5秒开始，前5个采样在开始时间之前
+        assert!(!collector.push(&frame(0)));
+        assert_eq!(collector.first_time, Some(1.0));
+        // 第二帧只需要前2个采样
+        assert!(collector.push(&frame(100)));
+        let samples: Vec<i16> = collector
+            .data
+            .chunks_exact(2)
+            .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+            .collect();
+        assert_eq!(samples, [5, 6, 7, 100, 101]);
+    }
+}
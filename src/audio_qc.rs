@@ -0,0 +1,267 @@
+// audio_qc.rs
+// 音频质检：数字静音断音和削波
+//
+// 传输或剪辑出错时，音轨中会出现一段采样全部为0的数字静音，听起来就是声音突然断掉；
+// 录音电平过高时采样会连续顶在满幅上，形成削波失真。
+// 这里逐个采样检查主音频流的所有声道，返回出现这两类问题的时间区间。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::onset;
+use crate::video_processor;
+
+use ffmpeg::{format::Sample, util::frame::audio::Audio};
+use ffmpeg_next as ffmpeg;
+
+// 所有声道的幅度都不超过该值时视为数字静音，小于16位采样的一个量化级
+const SILENCE_LEVEL: f32 = 1.0 / 32768.0;
+
+// 数字静音至少持续多长时间（秒）才算断音，过滤掉过零点附近的零值
+const MIN_DROPOUT: f64 = 0.1;
+
+// 幅度达到该值的采样视为满幅
+const CLIP_LEVEL: f32 = 0.999;
+
+// 同一声道至少连续这么多个满幅采样才算一次削波，单个峰值采样可能是正常的
+const MIN_CLIP_RUN: usize = 3;
+
+// 间隔不超过该值（秒）的削波合并为一个区间
+const CLIP_MERGE_GAP: f64 = 0.5;
+
+/// 一段数字静音断音
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioDropout {
+    /// 开始时间（秒）
+    pub start: f64,
+    /// 结束时间（秒）
+    pub end: f64,
+}
+
+/// 一段出现削波的区间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClippingSpan {
+    /// 第一次削波的时间（秒）
+    pub start: f64,
+    /// 最后一次削波的时间（秒）
+    pub end: f64,
+    /// 区间内削波（同一声道连续满幅采样）的次数
+    pub count: usize,
+}
+
+/// 音频质检结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioQc {
+    /// 按时间排序的数字静音断音
+    pub dropouts: Vec<AudioDropout>,
+    /// 按时间排序的削波区间
+    pub clipping: Vec<ClippingSpan>,
+}
+
+/// 检查主音频流中的数字静音断音和削波
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<AudioQc, VideoError>` - 成功时返回检查结果，没有音频流时返回错误
+pub fn analyze_audio<P: AsRef<Path>>(input_path: P) -> Result<AudioQc, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let mut checker = AudioChecker::default();
+    video_processor::for_each_audio_frame(
+        input_path.as_ref(),
+        &config::default_options(),
+        0.0,
+        |frame, time| {
+            checker.push_frame(frame, time);
+            Ok(ControlFlow::Continue(()))
+        },
+    )?;
+    Ok(checker.finish())
+}
+
+// 逐个采样累计断音和削波
+#[derive(Default)]
+pub(crate) struct AudioChecker {
+    // 下一个采样的时间（秒），帧没有时间戳时顺延
+    next_time: f64,
+    // 当前数字静音开始的时间
+    silence_start: Option<f64>,
+    // 每个声道当前连续满幅采样的数量
+    runs: Vec<usize>,
+    // 尚未结束的削波区间
+    clip: Option<ClippingSpan>,
+    result: AudioQc,
+}
+
+impl AudioChecker {
+    // `time` 为帧的开始时间（秒），帧没有时间戳时为 `None`，接着上一帧计算
+    pub(crate) fn push_frame(&mut self, frame: &Audio, time: Option<f64>) {
+        let format = frame.format();
+        let channels = frame.channels() as usize;
+        let samples = frame.samples();
+        let bytes = format.bytes();
+        let rate = f64::from(frame.rate());
+        if channels == 0 || bytes == 0 || rate <= 0.0 || format == Sample::None {
+            return;
+        }
+
+        let start = time.unwrap_or(self.next_time);
+        self.runs.resize(channels, 0);
+
+        // 安全性：解码器保证每个声道都有 `samples` 个该格式的采样；
+        // 平面格式只有第一个平面的 linesize 有效，因此直接按 extended_data 读取
+        unsafe {
+            let data = (*frame.as_ptr()).extended_data;
+            for i in 0..samples {
+                let levels = (0..channels).map(|ch| {
+                    let (plane, index) = if format.is_planar() {
+                        (ch, i)
+                    } else {
+                        (0, i * channels + ch)
+                    };
+                    onset::read_sample(format, (*data.add(plane)).add(index * bytes))
+                });
+                self.push_sample(start + i as f64 / rate, levels);
+            }
+        }
+
+        self.next_time = start + samples as f64 / rate;
+    }
+
+    // 加入一个采样帧（每个声道一个采样），声道数与 `runs` 的长度一致
+    fn push_sample(&mut self, time: f64, levels: impl IntoIterator<Item = f32>) {
+        let mut silent = true;
+        for (ch, level) in levels.into_iter().enumerate() {
+            let level = level.abs();
+            silent &= level <= SILENCE_LEVEL;
+            if level >= CLIP_LEVEL {
+                self.runs[ch] += 1;
+                if self.runs[ch] == MIN_CLIP_RUN {
+                    self.push_clip(time);
+                }
+            } else {
+                self.runs[ch] = 0;
+            }
+        }
+
+        if silent {
+            self.silence_start.get_or_insert(time);
+        } else if let Some(silence_start) = self.silence_start.take() {
+            self.push_dropout(silence_start, time);
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> AudioQc {
+        if let Some(silence_start) = self.silence_start.take() {
+            self.push_dropout(silence_start, self.next_time);
+        }
+        if let Some(clip) = self.clip.take() {
+            self.result.clipping.push(clip);
+        }
+        self.result
+    }
+
+    fn push_dropout(&mut self, start: f64, end: f64) {
+        if end - start >= MIN_DROPOUT {
+            self.result.dropouts.push(AudioDropout { start, end });
+        }
+    }
+
+    // 记录一次削波，与上一次相隔不远时并入同一区间
+    fn push_clip(&mut self, time: f64) {
+        match &mut self.clip {
+            Some(clip) if time - clip.end <= CLIP_MERGE_GAP => {
+                clip.end = time;
+                clip.count += 1;
+            }
+            _ => {
+                let next = ClippingSpan {
+                    start: time,
+                    end: time,
+                    count: 1,
+                };
+                if let Some(clip) = self.clip.replace(next) {
+                    self.result.clipping.push(clip);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按1kHz采样率逐个送入单声道采样
+    fn check(samples: &[f32]) -> AudioQc {
+        let mut checker = AudioChecker {
+            runs: vec![0],
+            ..Default::default()
+        };
+        for (i, &sample) in samples.iter().enumerate() {
+            checker.push_sample(i as f64 / 1000.0, [sample]);
+        }
+        checker.next_time = samples.len() as f64 / 1000.0;
+        checker.finish()
+    }
+
+    // 测试断音和削波的判定
+    #[test]
+    fn test_audio_checker() {
+        // 200ms的数字静音是一次断音，50ms的不算
+        let mut samples = vec![0.5f32; 500];
+        samples.extend([0.0; 200]);
+        samples.extend([0.5; 300]);
+        samples.extend([0.0; 50]);
+        samples.extend([-0.5; 100]);
+        let qc = check(&samples);
+        assert_eq!(
+            qc.dropouts,
+            [AudioDropout {
+                start: 0.5,
+                end: 0.7
+            }]
+        );
+        assert!(qc.clipping.is_empty());
+
+        // 持续到结尾的静音在结束时计入
+        let qc = check(&[0.5; 100].into_iter().chain([0.0; 200]).collect::<Vec<_>>());
+        assert_eq!(
+            qc.dropouts,
+            [AudioDropout {
+                start: 0.1,
+                end: 0.3
+            }]
+        );
+
+        // 连续3个满幅采样算一次削波，相隔不超过0.5秒的合并为一个区间；单个满幅采样不算
+        let mut samples = vec![0.5f32; 2000];
+        samples[100..103].fill(1.0);
+        samples[300..305].fill(-1.0);
+        samples[1500..1503].fill(1.0);
+        samples[1800] = 1.0;
+        let qc = check(&samples);
+        assert_eq!(
+            qc.clipping,
+            [
+                ClippingSpan {
+                    start: 0.102,
+                    end: 0.302,
+                    count: 2
+                },
+                ClippingSpan {
+                    start: 1.502,
+                    end: 1.502,
+                    count: 1
+                },
+            ]
+        );
+        assert!(qc.dropouts.is_empty());
+    }
+}
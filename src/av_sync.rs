@@ -0,0 +1,184 @@
+// av_sync.rs
+// 音视频同步检查
+//
+// 只读取数据包时间戳，不解码。比较音频流和视频流的起始时间，
+// 并按固定间隔记录两者时间戳的差值，发现随时间累积的漂移。
+
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::video_processor;
+
+use ffmpeg::format::stream::Stream;
+use ffmpeg::media::Type;
+use ffmpeg::Packet;
+use ffmpeg_next as ffmpeg;
+
+// 两次采样之间的视频时间间隔（秒）
+const SAMPLE_INTERVAL: f64 = 1.0;
+
+// 超过该偏差（秒）即视为不同步，约为人眼可察觉的口型偏差
+const SYNC_TOLERANCE: f64 = 0.1;
+
+/// 某一时刻的音视频偏差
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvSyncSample {
+    /// 视频时间（秒，相对于视频流起点）
+    pub time: f64,
+    /// 音频时间减去视频时间（秒），正值表示音频滞后
+    pub offset: f64,
+}
+
+/// 音视频同步检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvSyncReport {
+    /// 音频流起始时间减去视频流起始时间（秒）
+    pub start_offset: f64,
+    /// 按时间顺序的偏差采样
+    pub samples: Vec<AvSyncSample>,
+    /// 相对于起始偏差的最大漂移（秒，取绝对值）
+    pub max_drift: f64,
+    /// 起始偏差和漂移是否都在容差范围内
+    pub in_sync: bool,
+}
+
+/// 检查视频文件的音视频是否同步
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<AvSyncReport, VideoError>` - 成功时返回检查结果，缺少音频或视频流时返回错误
+pub fn check_av_sync<P: AsRef<Path>>(input_path: P) -> Result<AvSyncReport, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let mut ictx = video_processor::open_input(input_path.as_ref(), &config::default_options())?;
+
+    let mut tracker = {
+        let video = ictx
+            .streams()
+            .best(Type::Video)
+            .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+        let audio = ictx.streams().best(Type::Audio).ok_or(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("未找到音频流".to_string()),
+        ))?;
+        SyncTracker::new(&video, &audio)
+    };
+
+    for (index, packet) in video_processor::read_packets(&mut ictx) {
+        tracker.push(index, &packet);
+    }
+
+    tracker.finish()
+}
+
+// 按数据包时间戳累计音视频偏差
+pub(crate) struct SyncTracker {
+    video_index: usize,
+    video_tb: f64,
+    audio_index: usize,
+    audio_tb: f64,
+    video_origin: Option<f64>,
+    audio_origin: Option<f64>,
+    video_end: Option<f64>,
+    audio_end: Option<f64>,
+    next_sample: f64,
+    samples: Vec<AvSyncSample>,
+}
+
+impl SyncTracker {
+    pub(crate) fn new(video: &Stream, audio: &Stream) -> Self {
+        let video_tb = f64::from(video.time_base());
+        let audio_tb = f64::from(audio.time_base());
+        Self {
+            video_index: video.index(),
+            video_tb,
+            audio_index: audio.index(),
+            audio_tb,
+            // 流没有声明起始时间时，以各自第一个数据包的时间戳为准
+            video_origin: declared_start(video.start_time(), video_tb),
+            audio_origin: declared_start(audio.start_time(), audio_tb),
+            video_end: None,
+            audio_end: None,
+            next_sample: 0.0,
+            samples: Vec::new(),
+        }
+    }
+
+    // 加入一个数据包，其他流的数据包被忽略
+    pub(crate) fn push(&mut self, index: usize, packet: &Packet) {
+        let pts = match packet.pts() {
+            Some(pts) => pts,
+            None => return,
+        };
+
+        if index == self.audio_index {
+            let start = pts as f64 * self.audio_tb;
+            let end = (pts + packet.duration().max(0)) as f64 * self.audio_tb;
+            self.audio_origin.get_or_insert(start);
+            self.audio_end = Some(self.audio_end.map_or(end, |e| e.max(end)));
+        } else if index == self.video_index {
+            let start = pts as f64 * self.video_tb;
+            let end = (pts + packet.duration().max(0)) as f64 * self.video_tb;
+            self.video_origin.get_or_insert(start);
+            self.video_end = Some(self.video_end.map_or(end, |e| e.max(end)));
+        } else {
+            return;
+        }
+
+        // 两路都有数据之后，按视频时间定期采样
+        if let (Some(v_origin), Some(v_end), Some(a_end)) =
+            (self.video_origin, self.video_end, self.audio_end)
+        {
+            let time = v_end - v_origin;
+            if time >= self.next_sample {
+                self.samples.push(AvSyncSample {
+                    time,
+                    offset: a_end - v_end,
+                });
+                self.next_sample = time + SAMPLE_INTERVAL;
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<AvSyncReport, VideoError> {
+        let (video_origin, audio_origin) = match (self.video_origin, self.audio_origin) {
+            (Some(v), Some(a)) => (v, a),
+            _ => {
+                return Err(VideoError::new(
+                    VideoErrorCode::InvalidInput,
+                    Some("音频或视频流没有可用的时间戳".to_string()),
+                ))
+            }
+        };
+
+        let samples = self.samples;
+        let start_offset = audio_origin - video_origin;
+        // 交错写入带来的固定差值体现在第一次采样中，漂移相对它计算
+        let baseline = samples.first().map_or(0.0, |s| s.offset);
+        let max_drift = samples
+            .iter()
+            .map(|s| (s.offset - baseline).abs())
+            .fold(0.0, f64::max);
+
+        Ok(AvSyncReport {
+            start_offset,
+            in_sync: start_offset.abs() <= SYNC_TOLERANCE && max_drift <= SYNC_TOLERANCE,
+            samples,
+            max_drift,
+        })
+    }
+}
+
+// 流声明的起始时间（秒）
+fn declared_start(start_time: i64, time_base: f64) -> Option<f64> {
+    if start_time == ffmpeg::ffi::AV_NOPTS_VALUE {
+        None
+    } else {
+        Some(start_time as f64 * time_base)
+    }
+}
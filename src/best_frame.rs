@@ -0,0 +1,278 @@
+// best_frame.rs
+// 自动挑选封面帧
+//
+// 固定在第1秒抽帧经常取到淡入的黑场、转场中的模糊画面或者低对比度的片头。
+// 这里在目标时间点附近的窗口内均匀取若干候选帧，按曝光、对比度和清晰度打分，
+// 只把得分最高的一帧按抽帧选项完整转换输出。生成封面时顺便用打分的缩略图
+// 分析画面的繁杂区域，告诉界面叠加文字放在哪里更容易看清。
+
+use std::mem;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::busy_regions::{self, BusyRegions};
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::options::ExtractOptions;
+use crate::validation;
+use crate::video_processor::{self, RgbFrame};
+
+use ffmpeg::util::frame::video::Video;
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+// 窗口内的候选帧数量
+const CANDIDATES: usize = 9;
+
+// 打分用的亮度缩略图尺寸
+const THUMB_WIDTH: u32 = 128;
+const THUMB_HEIGHT: u32 = 72;
+
+// 亮度在该范围内的像素视为曝光正常，范围外为欠曝（黑场）或过曝
+const EXPOSED_MIN: u8 = 32;
+const EXPOSED_MAX: u8 = 235;
+
+// 亮度标准差达到该值时对比度得分为1
+const FULL_CONTRAST: f64 = 64.0;
+
+// 平均拉普拉斯响应达到该值时清晰度得分为1
+const FULL_SHARPNESS: f64 = 24.0;
+
+// 曝光、对比度和清晰度在总分中所占的权重
+const EXPOSURE_WEIGHT: f64 = 0.4;
+const CONTRAST_WEIGHT: f64 = 0.3;
+const SHARPNESS_WEIGHT: f64 = 0.3;
+
+/// 候选帧的得分，各项均为 0.0 - 1.0，越大越好
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameScore {
+    /// 曝光正常的像素比例，黑场和过曝画面接近0
+    pub exposure: f64,
+    /// 亮度的离散程度，纯色和雾蒙蒙的画面接近0
+    pub contrast: f64,
+    /// 边缘的强度，运动模糊和失焦的画面接近0
+    pub sharpness: f64,
+    /// 加权后的总分
+    pub total: f64,
+}
+
+/// 挑选出的封面帧
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestFrame {
+    /// 实际帧的时间（秒）
+    pub time: f64,
+    /// 这一帧的得分
+    pub score: FrameScore,
+    /// 按抽帧选项输出的帧
+    pub frame: RgbFrame,
+    /// 画面各区域的繁杂程度，只有 `select_poster_frame` 会分析
+    pub busy_regions: Option<BusyRegions>,
+}
+
+/// 封面帧及其繁杂区域分析，供JavaScript使用
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PosterFrame {
+    /// 帧数据，格式和编码取决于抽帧选项
+    pub image: Vec<u8>,
+    /// 宽度（像素）
+    pub width: u32,
+    /// 高度（像素）
+    pub height: u32,
+    /// 实际帧的时间（秒）
+    pub time: f64,
+    /// 画面各区域的繁杂程度
+    pub busy_regions: BusyRegions,
+}
+
+/// 在时间点附近挑选最适合做封面的一帧，使用全局默认选项
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `around_sec` - 窗口中心的时间点（秒），与 `extract_frame` 的时间点含义相同
+/// * `window` - 窗口的长度（秒），为0时只取一个候选帧
+///
+/// # 返回
+/// * `Result<BestFrame, VideoError>` - 成功时返回得分最高的一帧
+pub fn select_best_frame<P: AsRef<Path>>(
+    input_path: P,
+    around_sec: f64,
+    window: f64,
+) -> Result<BestFrame, VideoError> {
+    select_best_frame_with_options(input_path, around_sec, window, &config::default_options())
+}
+
+/// 使用自定义选项在时间点附近挑选最适合做封面的一帧
+///
+/// 窗口超出视频开头时从0开始，超出结尾时只在剩下的帧中挑选。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `around_sec` - 窗口中心的时间点（秒）
+/// * `window` - 窗口的长度（秒）
+/// * `options` - 抽帧选项，只应用于最终输出的一帧
+///
+/// # 返回
+/// * `Result<BestFrame, VideoError>` - 成功时返回得分最高的一帧，窗口内没有帧时返回错误
+pub fn select_best_frame_with_options<P: AsRef<Path>>(
+    input_path: P,
+    around_sec: f64,
+    window: f64,
+    options: &ExtractOptions,
+) -> Result<BestFrame, VideoError> {
+    select(input_path.as_ref(), around_sec, window, options, false)
+}
+
+/// 挑选封面帧，同时分析画面的繁杂区域
+///
+/// 与 `select_best_frame_with_options` 相同，另外在返回的 `busy_regions` 中给出
+/// 各区域的边缘密度和最适合叠加文字的边缘带。分析使用打分时已经缩小的亮度图，几乎没有额外开销。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `around_sec` - 窗口中心的时间点（秒）
+/// * `window` - 窗口的长度（秒）
+/// * `options` - 抽帧选项，只应用于最终输出的一帧
+///
+/// # 返回
+/// * `Result<BestFrame, VideoError>` - 成功时返回得分最高的一帧及其繁杂区域分析
+pub fn select_poster_frame<P: AsRef<Path>>(
+    input_path: P,
+    around_sec: f64,
+    window: f64,
+    options: &ExtractOptions,
+) -> Result<BestFrame, VideoError> {
+    select(input_path.as_ref(), around_sec, window, options, true)
+}
+
+fn select(
+    input_path: &Path,
+    around_sec: f64,
+    window: f64,
+    options: &ExtractOptions,
+    analyze_regions: bool,
+) -> Result<BestFrame, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    validation::validate_time(around_sec)?;
+    if !(window.is_finite() && window >= 0.0) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的窗口长度: {}", window)),
+        ));
+    }
+
+    let first = (around_sec - window / 2.0).max(0.0);
+    let last = around_sec + window / 2.0;
+    let step = (last - first) / (CANDIDATES - 1) as f64;
+
+    let mut best: Option<Candidate> = None;
+    let mut index = 0usize;
+
+    // 帧到达下一个候选时间点时打分，比当前最好的更好时留下解码帧，取完后才完整转换
+    video_processor::for_each_video_frame(input_path, options, first, |frame, time| {
+        if time < first + index as f64 * step {
+            return Ok(ControlFlow::Continue(()));
+        }
+        // 窗口为0时所有候选时间点相同，这一帧覆盖了全部
+        while index < CANDIDATES && first + index as f64 * step <= time {
+            index += 1;
+        }
+
+        let luma = video_processor::convert_to_luma(frame, THUMB_WIDTH, THUMB_HEIGHT, options)?;
+        let score = score_frame(&luma, THUMB_WIDTH as usize);
+        if best
+            .as_ref()
+            .is_none_or(|best| score.total > best.score.total)
+        {
+            // 直接取走解码帧，解码器下次写入新的空帧，不复制像素
+            best = Some(Candidate {
+                time,
+                score,
+                frame: mem::replace(frame, Video::empty()),
+                luma,
+            });
+        }
+        Ok(if index >= CANDIDATES {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        })
+    })?;
+
+    let best = best.ok_or(VideoError::new(
+        VideoErrorCode::FrameNotFound,
+        Some(format!("{} 秒附近没有可用的帧", around_sec)),
+    ))?;
+    Ok(BestFrame {
+        time: best.time,
+        score: best.score,
+        frame: video_processor::to_rgb_frame(&best.frame, options)?,
+        busy_regions: analyze_regions.then(|| {
+            // 打分的缩略图保持存储方向，转到与输出帧相同的方向后再分析，区域才对应用户看到的画面
+            let transform = options
+                .transform
+                .after_rotation(video_processor::frame_rotation(&best.frame, options));
+            let (luma, width, height) = video_processor::apply_transform(
+                best.luma,
+                THUMB_WIDTH,
+                THUMB_HEIGHT,
+                1,
+                &transform,
+            );
+            busy_regions::analyze_busy_regions(&luma, width, height)
+        }),
+    })
+}
+
+// 目前得分最高的候选帧及其打分用的亮度缩略图
+struct Candidate {
+    time: f64,
+    score: FrameScore,
+    frame: Video,
+    luma: Vec<u8>,
+}
+
+// 按曝光、对比度和清晰度给亮度缩略图打分
+fn score_frame(luma: &[u8], width: usize) -> FrameScore {
+    let count = luma.len().max(1) as f64;
+
+    let exposed = luma
+        .iter()
+        .filter(|&&level| (EXPOSED_MIN..=EXPOSED_MAX).contains(&level))
+        .count();
+    let exposure = exposed as f64 / count;
+
+    let mean = luma.iter().map(|&level| f64::from(level)).sum::<f64>() / count;
+    let variance = luma
+        .iter()
+        .map(|&level| (f64::from(level) - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let contrast = (variance.sqrt() / FULL_CONTRAST).min(1.0);
+
+    // 四邻域拉普拉斯算子的平均绝对响应，不计边缘一圈像素
+    let height = luma.len() / width.max(1);
+    let mut laplacian = 0.0;
+    let mut samples = 0usize;
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width - 1 {
+            let at = |x: usize, y: usize| i32::from(luma[y * width + x]);
+            let response = 4 * at(x, y) - at(x - 1, y) - at(x + 1, y) - at(x, y - 1) - at(x, y + 1);
+            laplacian += f64::from(response.abs());
+            samples += 1;
+        }
+    }
+    let sharpness = (laplacian / samples.max(1) as f64 / FULL_SHARPNESS).min(1.0);
+
+    FrameScore {
+        exposure,
+        contrast,
+        sharpness,
+        total: exposure * EXPOSURE_WEIGHT
+            + contrast * CONTRAST_WEIGHT
+            + sharpness * SHARPNESS_WEIGHT,
+    }
+}
@@ -0,0 +1,7 @@
+// video_capture_sandbox.rs
+// 沙箱模式的子进程入口，实际逻辑见 `sandbox::helper_main`
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(video_capture_wasm::sandbox::helper_main(&args));
+}
@@ -0,0 +1,196 @@
+// blurhash.rs
+// BlurHash占位图
+//
+// 前端只需要在图片加载前显示一个模糊的占位色块时，把整帧RGB数据传回JavaScript
+// 再计算哈希太浪费。这里在WASM内部把帧缩小后直接编码为几十个字符的BlurHash字符串。
+// 编码算法与 https://github.com/woltapp/blurhash 的参考实现一致。
+
+use std::f64::consts::PI;
+use std::path::Path;
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::options::{ExtractOptions, OutputFormat, Position};
+use crate::video_processor;
+
+// 计算哈希前把帧缩小到的宽度；BlurHash只保留最低频的几个分量，更大的尺寸没有意义
+const SAMPLE_WIDTH: u32 = 32;
+
+// 每个方向上的分量数量上限
+const MAX_COMPONENTS: u32 = 9;
+
+const BASE83: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// 计算指定位置帧的BlurHash
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 帧的位置，可以是秒数或百分比
+/// * `components_x` - 水平方向的分量数量（1 - 9），常用4
+/// * `components_y` - 垂直方向的分量数量（1 - 9），常用3
+/// * `options` - 抽帧选项，输出尺寸、格式和编码由本函数决定
+///
+/// # 返回
+/// * `Result<String, VideoError>` - 成功时返回BlurHash字符串
+pub fn compute_blurhash<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    components_x: u32,
+    components_y: u32,
+    options: &ExtractOptions,
+) -> Result<String, VideoError> {
+    validate_components(components_x, components_y)?;
+
+    let mut frame_options = options.for_analysis(OutputFormat::Rgb24);
+    frame_options.output_width = Some(SAMPLE_WIDTH);
+    frame_options.output_height = None;
+    let frame = video_processor::extract_frame_with_options(input_path, position, &frame_options)?;
+
+    encode(
+        &frame.data,
+        frame.width,
+        frame.height,
+        components_x,
+        components_y,
+    )
+}
+
+/// 把RGB24图像编码为BlurHash
+///
+/// # 参数
+/// * `data` - 紧密排列的RGB24像素数据
+/// * `width` - 图像宽度
+/// * `height` - 图像高度
+/// * `components_x` - 水平方向的分量数量（1 - 9）
+/// * `components_y` - 垂直方向的分量数量（1 - 9）
+///
+/// # 返回
+/// * `Result<String, VideoError>` - 成功时返回BlurHash字符串
+pub fn encode(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    components_x: u32,
+    components_y: u32,
+) -> Result<String, VideoError> {
+    validate_components(components_x, components_y)?;
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || data.len() != w * h * 3 {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "图像数据长度 {} 与尺寸 {}x{} 不匹配",
+                data.len(),
+                width,
+                height
+            )),
+        ));
+    }
+
+    // 余弦基函数的取值，按分量和坐标预先计算
+    let cos_x = cosines(components_x as usize, w);
+    let cos_y = cosines(components_y as usize, h);
+    let linear: Vec<f64> = (0..=255u8).map(srgb_to_linear).collect();
+
+    let mut factors = Vec::with_capacity((components_x * components_y) as usize);
+    for j in 0..components_y as usize {
+        for i in 0..components_x as usize {
+            let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let mut sum = [0.0f64; 3];
+            for y in 0..h {
+                let row = &data[y * w * 3..(y + 1) * w * 3];
+                for (x, pixel) in row.chunks_exact(3).enumerate() {
+                    let basis = cos_x[i * w + x] * cos_y[j * h + y];
+                    for (total, &value) in sum.iter_mut().zip(pixel) {
+                        *total += basis * linear[usize::from(value)];
+                    }
+                }
+            }
+            let scale = normalisation / (w * h) as f64;
+            factors.push(sum.map(|total| total * scale));
+        }
+    }
+
+    let mut hash = String::with_capacity(4 + 2 * factors.len());
+    push_base83(&mut hash, (components_x - 1) + (components_y - 1) * 9, 1);
+
+    let (dc, ac) = factors.split_at(1);
+    let maximum = if ac.is_empty() {
+        push_base83(&mut hash, 0, 1);
+        1.0
+    } else {
+        let actual = ac
+            .iter()
+            .flat_map(|factor| factor.iter())
+            .fold(0.0f64, |max, &value| max.max(value.abs()));
+        let quantised = (actual * 166.0 - 0.5).floor().clamp(0.0, 82.0);
+        push_base83(&mut hash, quantised as u32, 1);
+        (quantised + 1.0) / 166.0
+    };
+
+    let [r, g, b] = dc[0].map(linear_to_srgb);
+    push_base83(&mut hash, (r << 16) + (g << 8) + b, 4);
+
+    for factor in ac {
+        let [r, g, b] = factor.map(|value| {
+            let quantised = (sign_pow(value / maximum, 0.5) * 9.0 + 9.5).floor();
+            quantised.clamp(0.0, 18.0) as u32
+        });
+        push_base83(&mut hash, r * 19 * 19 + g * 19 + b, 2);
+    }
+
+    Ok(hash)
+}
+
+fn validate_components(components_x: u32, components_y: u32) -> Result<(), VideoError> {
+    let valid = 1..=MAX_COMPONENTS;
+    if valid.contains(&components_x) && valid.contains(&components_y) {
+        Ok(())
+    } else {
+        Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "分量数量必须在 1 - {} 之间: {}x{}",
+                MAX_COMPONENTS, components_x, components_y
+            )),
+        ))
+    }
+}
+
+// 第 `i` 个分量在第 `x` 个坐标上的取值，按 `[i * size + x]` 排列
+fn cosines(components: usize, size: usize) -> Vec<f64> {
+    (0..components)
+        .flat_map(|i| (0..size).map(move |x| (PI * i as f64 * x as f64 / size as f64).cos()))
+        .collect()
+}
+
+fn srgb_to_linear(value: u8) -> f64 {
+    let v = f64::from(value) / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> u32 {
+    let v = value.clamp(0.0, 1.0);
+    let srgb = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0 + 0.5) as u32
+}
+
+fn sign_pow(value: f64, exponent: f64) -> f64 {
+    value.abs().powf(exponent).copysign(value)
+}
+
+// 追加 `length` 位base83编码
+fn push_base83(hash: &mut String, value: u32, length: u32) {
+    for i in 1..=length {
+        let digit = (value / 83u32.pow(length - i)) % 83;
+        hash.push(char::from(BASE83[digit as usize]));
+    }
+}
@@ -0,0 +1,84 @@
+// buffer_pool.rs
+// 输出缓冲区池
+//
+// 浏览器中长时间运行的页面会反复抽帧，每次都分配一块几MB的缓冲区，
+// WASM线性内存只增不减，频繁分配和释放容易产生碎片。
+// 开启后输出缓冲区从池中复用，调用方用完结果后通过 `recycle` 归还。
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+// 池中最多保留的缓冲区数量
+const MAX_POOLED: usize = 4;
+
+// 按最近多少次请求的最大尺寸决定缓冲区容量
+const RECENT_WINDOW: usize = 8;
+
+struct Pool {
+    enabled: bool,
+    buffers: Vec<Vec<u8>>,
+    recent: VecDeque<usize>,
+}
+
+impl Pool {
+    // 最近请求中的最大尺寸
+    fn largest_recent(&self) -> usize {
+        self.recent.iter().copied().max().unwrap_or(0)
+    }
+}
+
+static POOL: Mutex<Pool> = Mutex::new(Pool {
+    enabled: false,
+    buffers: Vec::new(),
+    recent: VecDeque::new(),
+});
+
+/// 开启或关闭输出缓冲区复用，关闭时释放池中所有缓冲区
+pub fn set_enabled(enabled: bool) {
+    if let Ok(mut pool) = POOL.lock() {
+        pool.enabled = enabled;
+        if !enabled {
+            pool.buffers.clear();
+            pool.recent.clear();
+        }
+    }
+}
+
+// 取一块至少能容纳 `len` 字节的空缓冲区
+pub(crate) fn take(len: usize) -> Vec<u8> {
+    let mut pool = match POOL.lock() {
+        Ok(pool) if pool.enabled => pool,
+        _ => return Vec::with_capacity(len),
+    };
+
+    if pool.recent.len() == RECENT_WINDOW {
+        pool.recent.pop_front();
+    }
+    pool.recent.push_back(len);
+
+    if let Some(index) = pool.buffers.iter().position(|b| b.capacity() >= len) {
+        let mut buffer = pool.buffers.swap_remove(index);
+        buffer.clear();
+        return buffer;
+    }
+
+    // 按最近的最大尺寸分配，之后更大的帧也能复用这块缓冲区
+    Vec::with_capacity(pool.largest_recent())
+}
+
+/// 归还不再使用的输出缓冲区
+///
+/// 未开启复用、池已满或缓冲区小于最近的帧尺寸时直接释放，没有分配过内存的空缓冲区直接忽略。
+pub fn recycle(buffer: Vec<u8>) {
+    if buffer.capacity() == 0 {
+        return;
+    }
+    if let Ok(mut pool) = POOL.lock() {
+        if pool.enabled
+            && pool.buffers.len() < MAX_POOLED
+            && buffer.capacity() >= pool.largest_recent()
+        {
+            pool.buffers.push(buffer);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// build_info.rs
+// 构建信息：链接的FFmpeg版本、启用的解码器/解复用器以及本库的特性
+//
+// 部署出去的WASM二进制裁剪了哪些FFmpeg组件无法从外部看出，
+// 排查问题时先取这份信息，确认所需的解码器和容器格式是否存在。
+
+use std::ffi::{c_void, CStr};
+use std::ptr;
+
+use crate::ffmpeg_init;
+
+use ffmpeg::ffi;
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+/// 构建信息
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct BuildInfo {
+    /// 本库版本
+    pub crate_version: String,
+    /// 链接的FFmpeg版本，例如 `"7.1"`
+    pub ffmpeg_version: String,
+    /// FFmpeg的configure参数
+    pub ffmpeg_configuration: String,
+    /// 可用的解码器名称
+    pub decoders: Vec<String>,
+    /// 可用的解复用器名称
+    pub demuxers: Vec<String>,
+    /// 编译时启用的本库特性
+    pub features: Vec<String>,
+}
+
+/// 收集当前二进制的构建信息
+pub fn get_build_info() -> BuildInfo {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    // 安全性：以下函数只返回FFmpeg内部的静态数据
+    unsafe {
+        BuildInfo {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            ffmpeg_version: CStr::from_ptr(ffi::av_version_info())
+                .to_string_lossy()
+                .into_owned(),
+            ffmpeg_configuration: ffmpeg::format::configuration().to_string(),
+            decoders: decoders(),
+            demuxers: demuxers(),
+            features: features(),
+        }
+    }
+}
+
+// 遍历所有已注册的解码器
+unsafe fn decoders() -> Vec<String> {
+    let mut names = Vec::new();
+    let mut opaque: *mut c_void = ptr::null_mut();
+    loop {
+        let codec = ffi::av_codec_iterate(&mut opaque);
+        if codec.is_null() {
+            break;
+        }
+        if ffi::av_codec_is_decoder(codec) != 0 {
+            names.push(CStr::from_ptr((*codec).name).to_string_lossy().into_owned());
+        }
+    }
+    names
+}
+
+// 遍历所有已注册的解复用器
+unsafe fn demuxers() -> Vec<String> {
+    let mut names = Vec::new();
+    let mut opaque: *mut c_void = ptr::null_mut();
+    loop {
+        let demuxer = ffi::av_demuxer_iterate(&mut opaque);
+        if demuxer.is_null() {
+            break;
+        }
+        names.push(
+            CStr::from_ptr((*demuxer).name)
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+    names
+}
+
+// 编译时启用的特性
+fn features() -> Vec<String> {
+    let mut features = Vec::new();
+    if cfg!(feature = "sandbox") {
+        features.push("sandbox".to_string());
+    }
+    if cfg!(feature = "opencv") {
+        features.push("opencv".to_string());
+    }
+    features
+}
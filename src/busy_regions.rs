@@ -0,0 +1,125 @@
+// busy_regions.rs
+// 画面繁杂区域分析
+//
+// 在封面上叠加标题、按钮时，文字放在细节多、边缘密集的区域里很难看清。
+// 这里把亮度图划分为网格，统计每格中边缘像素的比例，
+// 并给出上下左右四条边缘带中最安静的一条，供界面决定叠加文字的位置。
+
+use wasm_bindgen::prelude::*;
+
+// 网格的列数和行数
+const GRID_COLUMNS: usize = 4;
+const GRID_ROWS: usize = 4;
+
+// 与右侧和下方像素的亮度差之和超过该值时视为边缘像素
+const EDGE_THRESHOLD: u32 = 32;
+
+/// 画面边缘的一条带状区域，宽度为网格的一行或一列
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlayBand {
+    /// 顶部一行
+    Top,
+    /// 底部一行
+    #[default]
+    Bottom,
+    /// 左侧一列
+    Left,
+    /// 右侧一列
+    Right,
+}
+
+/// 画面各区域的繁杂程度，默认值为没有分析过的空网格
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BusyRegions {
+    /// 网格的列数
+    pub columns: u32,
+    /// 网格的行数
+    pub rows: u32,
+    /// 每格的边缘密度（0.0 - 1.0），从左上角开始逐行排列；越大越繁杂，叠加的文字越难辨认
+    pub density: Vec<f32>,
+    /// 四条边缘带中平均密度最低的一条，相同时依次优先底部、顶部、左侧、右侧
+    pub quietest: OverlayBand,
+}
+
+impl BusyRegions {
+    /// 某一格的边缘密度，超出网格时返回None
+    pub fn density_at(&self, column: u32, row: u32) -> Option<f32> {
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+        self.density
+            .get((row * self.columns + column) as usize)
+            .copied()
+    }
+
+    /// 一条边缘带的平均密度
+    pub fn band_density(&self, band: OverlayBand) -> f32 {
+        let cells: Vec<f32> = match band {
+            OverlayBand::Top => (0..self.columns)
+                .filter_map(|c| self.density_at(c, 0))
+                .collect(),
+            OverlayBand::Bottom => (0..self.columns)
+                .filter_map(|c| self.density_at(c, self.rows.saturating_sub(1)))
+                .collect(),
+            OverlayBand::Left => (0..self.rows)
+                .filter_map(|r| self.density_at(0, r))
+                .collect(),
+            OverlayBand::Right => (0..self.rows)
+                .filter_map(|r| self.density_at(self.columns.saturating_sub(1), r))
+                .collect(),
+        };
+        cells.iter().sum::<f32>() / cells.len().max(1) as f32
+    }
+}
+
+/// 分析亮度图各区域的繁杂程度
+///
+/// # 参数
+/// * `luma` - 紧密排列的8位亮度数据
+/// * `width` - 图像宽度
+/// * `height` - 图像高度
+///
+/// # 返回
+/// * `BusyRegions` - 4x4网格的边缘密度和最安静的边缘带；数据与尺寸不符时密度全部为0
+pub fn analyze_busy_regions(luma: &[u8], width: u32, height: u32) -> BusyRegions {
+    let (w, h) = (width as usize, height as usize);
+    let mut edges = [0u32; GRID_COLUMNS * GRID_ROWS];
+    let mut pixels = [0u32; GRID_COLUMNS * GRID_ROWS];
+
+    if w >= 2 && h >= 2 && luma.len() == w * h {
+        for y in 0..h - 1 {
+            let cell_row = y * GRID_ROWS / h;
+            for x in 0..w - 1 {
+                let at = |x: usize, y: usize| u32::from(luma[y * w + x]);
+                let gradient = at(x + 1, y).abs_diff(at(x, y)) + at(x, y + 1).abs_diff(at(x, y));
+                let cell = cell_row * GRID_COLUMNS + x * GRID_COLUMNS / w;
+                pixels[cell] += 1;
+                if gradient > EDGE_THRESHOLD {
+                    edges[cell] += 1;
+                }
+            }
+        }
+    }
+
+    let density: Vec<f32> = edges
+        .iter()
+        .zip(&pixels)
+        .map(|(&edges, &pixels)| edges as f32 / pixels.max(1) as f32)
+        .collect();
+    let mut regions = BusyRegions {
+        columns: GRID_COLUMNS as u32,
+        rows: GRID_ROWS as u32,
+        density,
+        quietest: OverlayBand::Bottom,
+    };
+
+    // 字幕和标题通常在底部，其他位置更安静时才改变
+    for band in [OverlayBand::Top, OverlayBand::Left, OverlayBand::Right] {
+        if regions.band_density(band) < regions.band_density(regions.quietest) {
+            regions.quietest = band;
+        }
+    }
+    regions
+}
@@ -0,0 +1,130 @@
+// clip_bounds.rs
+// 关键帧对齐的片段边界建议
+//
+// 流复制（不重新编码）剪辑只能从关键帧开始。调用方通常不知道文件的GOP结构，
+// 这里读取请求范围附近的数据包，找出最接近的关键帧作为起止点。
+
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::validation;
+use crate::video_processor;
+
+use ffmpeg::media::Type;
+use ffmpeg_next as ffmpeg;
+
+// 在请求范围前后额外扫描的时长（秒），需要覆盖一个完整的GOP
+const SCAN_MARGIN: f64 = 10.0;
+
+/// 建议的片段边界（秒）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipBounds {
+    /// 起点，总是落在关键帧上
+    pub start: f64,
+    /// 终点，落在下一段的关键帧上或流的结尾
+    pub end: f64,
+}
+
+/// 给出以 `around_sec` 为中心、时长接近 `target_duration` 的关键帧对齐片段
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `around_sec` - 片段中心的时间点（秒）
+/// * `target_duration` - 期望的片段时长（秒）
+///
+/// # 返回
+/// * `Result<ClipBounds, VideoError>` - 成功时返回可直接用于流复制剪辑的起止时间
+pub fn suggest_clip_bounds<P: AsRef<Path>>(
+    input_path: P,
+    around_sec: f64,
+    target_duration: f64,
+) -> Result<ClipBounds, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    validation::validate_time(around_sec)?;
+    if !target_duration.is_finite() || target_duration <= 0.0 {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的片段时长: {}", target_duration)),
+        ));
+    }
+
+    let mut ictx = video_processor::open_input(input_path.as_ref(), &config::default_options())?;
+    let (video_index, time_base) = ictx
+        .streams()
+        .best(Type::Video)
+        .map(|stream| (stream.index(), f64::from(stream.time_base())))
+        .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+    let desired_start = (around_sec - target_duration / 2.0).max(0.0);
+    let desired_end = desired_start + target_duration;
+
+    // 定位到扫描范围之前的关键帧
+    let scan_start = desired_start - SCAN_MARGIN.max(target_duration);
+    video_processor::seek_before(&mut ictx, scan_start)?;
+
+    let scan_end = desired_end + SCAN_MARGIN.max(target_duration);
+    let mut keyframes: Vec<f64> = Vec::new();
+    let mut stream_end: Option<f64> = None;
+    let mut reached_eof = true;
+
+    for (index, packet) in video_processor::read_packets(&mut ictx) {
+        if index != video_index {
+            continue;
+        }
+        let pts = match packet.pts().or(packet.dts()) {
+            Some(pts) => pts,
+            None => continue,
+        };
+        let time = pts as f64 * time_base;
+        if time > scan_end {
+            reached_eof = false;
+            break;
+        }
+
+        if packet.is_key() {
+            keyframes.push(time);
+        }
+        let end = (pts + packet.duration().max(0)) as f64 * time_base;
+        stream_end = Some(stream_end.map_or(end, |e| e.max(end)));
+    }
+
+    // 起点取最接近期望起点的关键帧
+    let start = nearest(&keyframes, desired_start).ok_or(VideoError::new(
+        VideoErrorCode::FrameNotFound,
+        Some("请求范围附近没有关键帧".to_string()),
+    ))?;
+
+    // 只有读到结尾时，最后一个数据包的结束时间才是流的结尾
+    let stream_end = stream_end.filter(|_| reached_eof);
+
+    // 终点取最接近期望终点的后续关键帧或流的结尾
+    let later: Vec<f64> = keyframes.iter().copied().filter(|k| *k > start).collect();
+    let end = match (nearest(&later, start + target_duration), stream_end) {
+        (Some(keyframe), Some(stream_end)) => {
+            if (stream_end - (start + target_duration)).abs()
+                < (keyframe - (start + target_duration)).abs()
+            {
+                stream_end
+            } else {
+                keyframe
+            }
+        }
+        (Some(keyframe), None) => keyframe,
+        (None, Some(stream_end)) => stream_end,
+        (None, None) => start + target_duration,
+    };
+
+    Ok(ClipBounds { start, end })
+}
+
+// 列表中最接近目标的时间点
+fn nearest(times: &[f64], target: f64) -> Option<f64> {
+    times
+        .iter()
+        .copied()
+        .min_by(|a, b| (a - target).abs().total_cmp(&(b - target).abs()))
+}
@@ -0,0 +1,172 @@
+// comparison.rs
+// 两个时间点的对比图
+//
+// 质检工具需要对比转码前后的画面，或者确认两个时间点之间画面是否被冻结。
+// 这里取出两帧，按相同尺寸拼成左右对照图，或者生成逐像素差异的热度图，
+// 同时给出平均差异，差异为0说明两帧完全相同。
+
+use std::path::Path;
+
+use crate::config;
+use crate::encode;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::options::{ExtractOptions, OutputFormat};
+use crate::video_processor::{self, FrameInfo, RgbFrame};
+
+// 差异热度图的放大倍数，压缩噪声等细微差异也能看出来
+const DIFF_GAIN: u32 = 4;
+
+/// 对比图的布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComparisonLayout {
+    /// 两帧左右并排，第一个时间点在左边
+    #[default]
+    SideBySide,
+    /// 逐像素差异的热度图：相同为黑色，差异越大越接近红、黄、白
+    Difference,
+}
+
+/// 对比结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// 对比图，RGB24格式；设置了 `ExtractOptions::output_encoding` 时为编码后的图片
+    pub image: RgbFrame,
+    /// 两帧的平均逐通道差异（0.0 - 255.0），0 表示两帧完全相同
+    pub mean_difference: f64,
+}
+
+/// 渲染两个时间点的对比图，使用全局默认选项
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `time1` - 第一个时间点（秒）
+/// * `time2` - 第二个时间点（秒）
+/// * `layout` - 对比图的布局
+///
+/// # 返回
+/// * `Result<Comparison, VideoError>` - 成功时返回对比图和平均差异
+pub fn render_comparison<P: AsRef<Path>>(
+    input_path: P,
+    time1: f64,
+    time2: f64,
+    layout: ComparisonLayout,
+) -> Result<Comparison, VideoError> {
+    render_comparison_with_options(input_path, time1, time2, layout, &config::default_options())
+}
+
+/// 使用自定义选项渲染两个时间点的对比图
+///
+/// 两帧按相同尺寸输出，第二帧所在位置分辨率不同时会缩放到与第一帧一致。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `time1` - 第一个时间点（秒）
+/// * `time2` - 第二个时间点（秒）
+/// * `layout` - 对比图的布局
+/// * `options` - 抽帧选项，输出尺寸和编码照常生效，输出格式固定为RGB24
+///
+/// # 返回
+/// * `Result<Comparison, VideoError>` - 成功时返回对比图和平均差异
+pub fn render_comparison_with_options<P: AsRef<Path>>(
+    input_path: P,
+    time1: f64,
+    time2: f64,
+    layout: ComparisonLayout,
+    options: &ExtractOptions,
+) -> Result<Comparison, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    // 固定两帧的尺寸，中途分辨率变化时也能逐像素对比
+    let mut frame_options = options.for_analysis(OutputFormat::Rgb24);
+    // 预估的是最终输出的尺寸，而要求的输出宽高对应调用方变换之前的画面，变换旋转90/270度时要换回来
+    let size = video_processor::estimate_output(&input_path, &frame_options)?;
+    let (width, height) = if options.transform.rotation.swaps_dimensions() {
+        (size.height, size.width)
+    } else {
+        (size.width, size.height)
+    };
+    frame_options.output_width = Some(width);
+    frame_options.output_height = Some(height);
+    frame_options.max_output_bytes = None;
+
+    let frames =
+        video_processor::extract_frames_with_options(&input_path, &[time1, time2], &frame_options)?;
+    let (first, second) = (&frames[0], &frames[1]);
+    let (width, height) = (first.width, first.height);
+
+    let mean_difference = first
+        .data
+        .iter()
+        .zip(&second.data)
+        .map(|(&a, &b)| f64::from(a.abs_diff(b)))
+        .sum::<f64>()
+        / first.data.len().max(1) as f64;
+
+    let (data, image_width) = match layout {
+        ComparisonLayout::SideBySide => (side_by_side(first, second), width * 2),
+        ComparisonLayout::Difference => (difference_heatmap(first, second), width),
+    };
+    let data = encode::encode_frame(
+        data,
+        image_width,
+        height,
+        OutputFormat::Rgb24,
+        options.output_encoding,
+        options.encoding_quality,
+    )?;
+
+    Ok(Comparison {
+        image: RgbFrame {
+            width: image_width,
+            height,
+            data,
+            info: FrameInfo {
+                storage_width: image_width,
+                storage_height: height,
+                display_width: image_width,
+                display_height: height,
+            },
+            warnings: Vec::new(),
+        },
+        mean_difference,
+    })
+}
+
+// 逐行把两帧拼在一起
+fn side_by_side(left: &RgbFrame, right: &RgbFrame) -> Vec<u8> {
+    let row_bytes = left.width as usize * 3;
+    let mut data = Vec::with_capacity(left.data.len() * 2);
+    for (a, b) in left
+        .data
+        .chunks_exact(row_bytes)
+        .zip(right.data.chunks_exact(row_bytes))
+    {
+        data.extend_from_slice(a);
+        data.extend_from_slice(b);
+    }
+    data
+}
+
+// 每个像素取三个通道的差异之和，放大后映射为黑-红-黄-白
+fn difference_heatmap(a: &RgbFrame, b: &RgbFrame) -> Vec<u8> {
+    a.data
+        .chunks_exact(3)
+        .zip(b.data.chunks_exact(3))
+        .flat_map(|(pa, pb)| {
+            let diff: u32 = pa
+                .iter()
+                .zip(pb)
+                .map(|(&x, &y)| u32::from(x.abs_diff(y)))
+                .sum();
+            // 放大后截断到 0 - 765，依次填满红、绿、蓝通道
+            let level = (diff * DIFF_GAIN).min(765);
+            [
+                level.min(255) as u8,
+                level.saturating_sub(255).min(255) as u8,
+                level.saturating_sub(510) as u8,
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,161 @@
+// concat.rs
+// 由多个文件或内存数据拼接而成的虚拟输入
+//
+// 编辑器的EDL预览需要按节目时间轴抽帧，而节目由多个物理文件依次拼成。
+// 这里生成一份ffconcat列表交给FFmpeg的concat解复用器，内存中的数据先落地为临时文件，
+// 打开后得到一条连续的时间轴，抽帧时间直接使用节目时间。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::options::{ExtractOptions, Position};
+use crate::temp_storage::{self, TempStorage};
+use crate::validation;
+use crate::video_processor::{self, RgbFrame};
+
+/// 虚拟输入中的一段
+#[derive(Debug, Clone, Copy)]
+pub enum ConcatPart<'a> {
+    /// 视频文件路径
+    Path(&'a Path),
+    /// 内存中的完整视频数据
+    Memory(&'a [u8]),
+}
+
+/// 拼接后的虚拟输入
+///
+/// 各段按顺序首尾相接，时间轴从第一段的开头开始。各段应当使用相同的编码参数，
+/// 否则解码器可能无法在段与段之间切换。丢弃时删除生成的列表和临时文件。
+pub struct ConcatInput {
+    // ffconcat列表文件
+    list: PathBuf,
+    // 列表和内存数据落地的临时文件
+    temp_files: TempFiles,
+}
+
+// 丢弃时删除的一组临时文件
+struct TempFiles {
+    storage: Arc<dyn TempStorage>,
+    paths: Vec<PathBuf>,
+}
+
+impl TempFiles {
+    fn store(&mut self, data: &[u8]) -> Result<PathBuf, VideoError> {
+        let path = self.storage.store(data)?;
+        self.paths.push(path.clone());
+        Ok(path)
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            self.storage.remove(path);
+        }
+    }
+}
+
+impl ConcatInput {
+    /// 按顺序拼接多段输入
+    ///
+    /// # 参数
+    /// * `parts` - 按播放顺序排列的各段
+    ///
+    /// # 返回
+    /// * `Result<ConcatInput, VideoError>` - 成功时返回虚拟输入，之后通过它抽帧
+    pub fn new(parts: &[ConcatPart]) -> Result<Self, VideoError> {
+        if parts.is_empty() {
+            return Err(VideoError::new(
+                VideoErrorCode::InvalidInput,
+                Some("拼接的输入为空".to_string()),
+            ));
+        }
+
+        // 中途失败时已经写出的临时文件随 `temp_files` 一起删除
+        let mut temp_files = TempFiles {
+            storage: temp_storage::current(),
+            paths: Vec::new(),
+        };
+
+        let mut list = String::from("ffconcat version 1.0\n");
+        for part in parts {
+            let path = match part {
+                // 列表中的相对路径相对于列表文件所在目录解析，需要先转换为绝对路径
+                ConcatPart::Path(path) => match std::path::absolute(path) {
+                    Ok(path) => path,
+                    Err(e) => {
+                        return Err(VideoError::new(
+                            VideoErrorCode::InvalidInput,
+                            Some(format!("无法解析路径 {}: {}", path.display(), e)),
+                        ))
+                    }
+                },
+                ConcatPart::Memory(data) => {
+                    validation::validate_buffer(data)?;
+                    temp_files.store(data)?
+                }
+            };
+            list.push_str(&format!("file {}\n", quote(&path)?));
+        }
+        let list = temp_files.store(list.as_bytes())?;
+
+        Ok(ConcatInput { list, temp_files })
+    }
+
+    /// 使用自定义选项提取节目时间轴上特定位置的帧
+    ///
+    /// # 参数
+    /// * `position` - 要提取的帧在整个节目中的位置，可以是秒数或 `Position::Percent`
+    /// * `options` - 打开输入和解码器时使用的选项
+    ///
+    /// # 返回
+    /// * `Result<RgbFrame, VideoError>` - 与 `video_processor::extract_frame_with_options` 相同
+    pub fn extract_frame<T: Into<Position>>(
+        &self,
+        position: T,
+        options: &ExtractOptions,
+    ) -> Result<RgbFrame, VideoError> {
+        video_processor::extract_frame_with_options(self.path(), position, &self.options(options))
+    }
+
+    /// 提取节目时间轴上多个时间点的帧
+    ///
+    /// # 参数
+    /// * `times` - 要提取的时间点（秒），可以无序，也可以重复
+    /// * `options` - 打开输入和解码器时使用的选项
+    ///
+    /// # 返回
+    /// * `Result<Vec<RgbFrame>, VideoError>` - 与 `video_processor::extract_frames_with_options` 相同
+    pub fn extract_frames(
+        &self,
+        times: &[f64],
+        options: &ExtractOptions,
+    ) -> Result<Vec<RgbFrame>, VideoError> {
+        video_processor::extract_frames_with_options(self.path(), times, &self.options(options))
+    }
+
+    /// ffconcat列表的路径，可以交给其他接受路径的接口（例如 `VideoSession`），
+    /// 此时需要同时使用 `options` 返回的选项
+    pub fn path(&self) -> &Path {
+        &self.list
+    }
+
+    /// 在调用方选项的基础上允许列表引用任意路径（concat解复用器默认只接受相对路径）
+    pub fn options(&self, options: &ExtractOptions) -> ExtractOptions {
+        let mut options = options.clone();
+        options
+            .format_options
+            .push(("safe".to_string(), "0".to_string()));
+        options
+    }
+}
+
+// 按ffconcat语法给路径加引号：单引号内的单引号写成 '\''
+fn quote(path: &Path) -> Result<String, VideoError> {
+    let path = path.to_str().ok_or(VideoError::new(
+        VideoErrorCode::InvalidInput,
+        Some(format!("路径不是有效的UTF-8: {}", path.display())),
+    ))?;
+    Ok(format!("'{}'", path.replace('\'', r"'\''")))
+}
@@ -0,0 +1,88 @@
+// config.rs
+// 全局配置：为所有调用设置一次默认值
+//
+// 不带选项参数的接口（`extract_frame`、WASM导出函数等）使用这里的默认选项，
+// 调用方不必在每个调用点重复传入相同的选项。
+
+use std::sync::{Arc, RwLock};
+
+use crate::ffmpeg_init;
+use crate::log_capture::LogLevel;
+use crate::options::ExtractOptions;
+use crate::temp_storage::{self, TempStorage};
+use crate::validation;
+
+/// 全局配置
+#[derive(Clone)]
+pub struct GlobalConfig {
+    /// 不传选项时使用的默认抽帧选项
+    pub default_options: ExtractOptions,
+    /// 允许的最大输入数据大小（字节），不能超过 `validation::MAX_INPUT_BYTES`
+    pub max_input_bytes: usize,
+    /// FFmpeg全局日志级别，`None` 时不修改当前级别
+    pub log_level: Option<LogLevel>,
+    /// 内存输入落地时使用的临时存储后端，`None` 时不修改当前后端
+    pub temp_storage: Option<Arc<dyn TempStorage>>,
+}
+
+impl Default for GlobalConfig {
+    fn default() -> Self {
+        Self {
+            default_options: ExtractOptions::default(),
+            max_input_bytes: validation::MAX_INPUT_BYTES,
+            log_level: None,
+            temp_storage: None,
+        }
+    }
+}
+
+static CONFIG: RwLock<Option<GlobalConfig>> = RwLock::new(None);
+
+/// 设置全局配置，替换之前的全部配置
+pub fn configure(config: GlobalConfig) {
+    // 先初始化，避免之后的初始化覆盖这里设置的日志级别
+    ffmpeg_init::initialize();
+
+    if let Some(level) = config.log_level {
+        ffmpeg_next::util::log::set_level(level.into());
+    }
+
+    if let Some(storage) = &config.temp_storage {
+        temp_storage::set_temp_storage(storage.clone());
+    }
+
+    if let Ok(mut current) = CONFIG.write() {
+        *current = Some(config);
+    }
+}
+
+/// 当前生效的全局配置
+pub fn global_config() -> GlobalConfig {
+    match CONFIG.read() {
+        Ok(current) => current.clone().unwrap_or_default(),
+        Err(_) => GlobalConfig::default(),
+    }
+}
+
+// 不传选项时使用的默认抽帧选项
+pub(crate) fn default_options() -> ExtractOptions {
+    match CONFIG.read() {
+        Ok(current) => current
+            .as_ref()
+            .map(|config| config.default_options.clone())
+            .unwrap_or_default(),
+        Err(_) => ExtractOptions::default(),
+    }
+}
+
+// 当前允许的最大输入数据大小（字节）
+pub(crate) fn max_input_bytes() -> usize {
+    match CONFIG.read() {
+        Ok(current) => current
+            .as_ref()
+            .map_or(validation::MAX_INPUT_BYTES, |config| {
+                config.max_input_bytes.min(validation::MAX_INPUT_BYTES)
+            }),
+        Err(_) => validation::MAX_INPUT_BYTES,
+    }
+}
@@ -0,0 +1,195 @@
+// custom_io.rs
+// 通过自定义AVIO从任意可随机读取的数据源打开输入
+//
+// 默认的打开方式需要一个文件路径，内存中的数据只能先落地为临时文件。
+// 这里把实现了 `Read + Seek` 的数据源包装成AVIO回调，
+// FFmpeg按需读取需要的片段，不必把整个文件读进内存。
+
+use std::ffi::{c_int, c_void};
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::options::ExtractOptions;
+use crate::validation;
+
+use ffmpeg::{ffi, format::context::Input};
+use ffmpeg_next as ffmpeg;
+
+// AVIO内部缓冲区大小
+const IO_BUFFER_SIZE: usize = 64 * 1024;
+
+// FFmpeg查询数据源总大小时使用的 whence 标志
+const AVSEEK_SIZE: c_int = 0x10000;
+
+// 可以忽略的强制定位标志
+const AVSEEK_FORCE: c_int = 0x20000;
+
+/// 可随机读取的数据源
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// 基于自定义AVIO打开的输入
+///
+/// 解引用为 `Input`，销毁时按顺序释放格式上下文、AVIO上下文和数据源。
+pub struct ReaderInput {
+    input: Option<Input>,
+    avio: *mut ffi::AVIOContext,
+    opaque: *mut Box<dyn ReadSeek>,
+}
+
+impl Deref for ReaderInput {
+    type Target = Input;
+
+    fn deref(&self) -> &Input {
+        // input 只在 drop 中被取出
+        self.input.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for ReaderInput {
+    fn deref_mut(&mut self) -> &mut Input {
+        self.input.as_mut().unwrap()
+    }
+}
+
+impl Drop for ReaderInput {
+    fn drop(&mut self) {
+        // 先关闭格式上下文；设置了 AVFMT_FLAG_CUSTOM_IO 时它不会释放 pb
+        drop(self.input.take());
+
+        unsafe {
+            if !self.avio.is_null() {
+                // 缓冲区可能已被FFmpeg替换，需要释放当前的那一个
+                ffi::av_freep(&mut (*self.avio).buffer as *mut *mut u8 as *mut c_void);
+                ffi::avio_context_free(&mut self.avio);
+            }
+            if !self.opaque.is_null() {
+                drop(Box::from_raw(self.opaque));
+            }
+        }
+    }
+}
+
+/// 从数据源打开输入并检查容器结构
+pub fn open_reader(
+    reader: Box<dyn ReadSeek>,
+    options: &ExtractOptions,
+) -> Result<ReaderInput, VideoError> {
+    unsafe {
+        let opaque = Box::into_raw(Box::new(reader));
+
+        let buffer = ffi::av_malloc(IO_BUFFER_SIZE) as *mut u8;
+        if buffer.is_null() {
+            drop(Box::from_raw(opaque));
+            return Err(VideoError::new(
+                VideoErrorCode::InitFailed,
+                Some("无法分配AVIO缓冲区".to_string()),
+            ));
+        }
+
+        let avio = ffi::avio_alloc_context(
+            buffer,
+            IO_BUFFER_SIZE as c_int,
+            0,
+            opaque as *mut c_void,
+            Some(read_packet),
+            None,
+            Some(seek),
+        );
+        if avio.is_null() {
+            ffi::av_free(buffer as *mut c_void);
+            drop(Box::from_raw(opaque));
+            return Err(VideoError::new(
+                VideoErrorCode::InitFailed,
+                Some("无法创建AVIO上下文".to_string()),
+            ));
+        }
+
+        // 从这里开始由 ReaderInput 负责释放 avio 和 opaque
+        let mut reader_input = ReaderInput {
+            input: None,
+            avio,
+            opaque,
+        };
+
+        let mut ps = ffi::avformat_alloc_context();
+        if ps.is_null() {
+            return Err(VideoError::new(
+                VideoErrorCode::InitFailed,
+                Some("无法创建格式上下文".to_string()),
+            ));
+        }
+        (*ps).pb = avio;
+        (*ps).flags |= ffi::AVFMT_FLAG_CUSTOM_IO as c_int;
+
+        let mut opts = options.format_dictionary().disown();
+        let res = ffi::avformat_open_input(&mut ps, ptr::null(), ptr::null(), &mut opts);
+        ffmpeg::Dictionary::own(opts);
+
+        // 打开失败时 avformat_open_input 会释放 ps
+        if res < 0 {
+            return Err(VideoError::new(
+                VideoErrorCode::InvalidInput,
+                Some(format!("无法打开视频数据: {}", ffmpeg::Error::from(res))),
+            ));
+        }
+
+        let res = ffi::avformat_find_stream_info(ps, ptr::null_mut());
+        reader_input.input = Some(Input::wrap(ps));
+        if res < 0 {
+            return Err(VideoError::new(
+                VideoErrorCode::InvalidInput,
+                Some(format!("无法读取流信息: {}", ffmpeg::Error::from(res))),
+            ));
+        }
+
+        validation::validate_input(&reader_input)?;
+
+        Ok(reader_input)
+    }
+}
+
+// AVIO读回调
+unsafe extern "C" fn read_packet(opaque: *mut c_void, buf: *mut u8, buf_size: c_int) -> c_int {
+    let reader = &mut *(opaque as *mut Box<dyn ReadSeek>);
+    let buf = std::slice::from_raw_parts_mut(buf, buf_size.max(0) as usize);
+
+    match reader.read(buf) {
+        Ok(0) => ffi::AVERROR_EOF,
+        Ok(n) => n as c_int,
+        Err(_) => ffi::AVERROR_EOF,
+    }
+}
+
+// AVIO定位回调
+unsafe extern "C" fn seek(opaque: *mut c_void, offset: i64, whence: c_int) -> i64 {
+    let reader = &mut *(opaque as *mut Box<dyn ReadSeek>);
+    let whence = whence & !AVSEEK_FORCE;
+
+    // 查询总大小，完成后恢复原来的读取位置
+    if whence == AVSEEK_SIZE {
+        let current = match reader.stream_position() {
+            Ok(pos) => pos,
+            Err(_) => return -1,
+        };
+        let size = reader.seek(SeekFrom::End(0));
+        let _ = reader.seek(SeekFrom::Start(current));
+        return size.map(|s| s as i64).unwrap_or(-1);
+    }
+
+    // whence 取值与C标准库的 SEEK_SET/SEEK_CUR/SEEK_END 相同
+    let target = match whence {
+        0 => SeekFrom::Start(offset.max(0) as u64),
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
+        _ => return -1,
+    };
+
+    match reader.seek(target) {
+        Ok(pos) => pos as i64,
+        Err(_) => -1,
+    }
+}
@@ -0,0 +1,308 @@
+// encode.rs
+// 在库内把抽出的帧编码为PNG、JPEG或WebP
+//
+// 原始像素跨越WASM边界后再由JavaScript重新编码很慢，
+// 直接返回压缩后的图片，传输的数据量通常只有原始像素的几十分之一。
+// 动态预览用的GIF、APNG和动画WebP也在这里编码。
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::memory_watch;
+use crate::options::{OutputEncoding, OutputFormat};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{Delay, ExtendedColorType, Frame, ImageEncoder, RgbaImage};
+
+// 未指定质量时JPEG使用的质量
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+// WebP扩展格式中VP8X的动画和透明通道标志
+const WEBP_FLAG_ANIMATION: u8 = 0x02;
+const WEBP_FLAG_ALPHA: u8 = 0x10;
+
+// ANMF帧标志：不与上一帧混合
+const WEBP_NO_BLEND: u8 = 0x02;
+
+// 按选项编码紧密排列的像素数据，`Raw` 时原样返回
+pub(crate) fn encode_frame(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    format: OutputFormat,
+    encoding: OutputEncoding,
+    quality: Option<u8>,
+) -> Result<Vec<u8>, VideoError> {
+    let mut encoded = Vec::new();
+    let result = match encoding {
+        OutputEncoding::Raw => return Ok(data),
+        OutputEncoding::Png => {
+            PngEncoder::new(&mut encoded).write_image(&data, width, height, color_type(format)?)
+        }
+        OutputEncoding::Jpeg => {
+            let quality = quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100);
+            let encoder = JpegEncoder::new_with_quality(&mut encoded, quality);
+            // JPEG没有透明通道，RGBA先去掉Alpha
+            match color_type(format)? {
+                ExtendedColorType::Rgba8 => {
+                    let rgb: Vec<u8> = data
+                        .chunks_exact(4)
+                        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                        .collect();
+                    encoder.write_image(&rgb, width, height, ExtendedColorType::Rgb8)
+                }
+                color => encoder.write_image(&data, width, height, color),
+            }
+        }
+        // 只支持无损WebP，设置了质量说明调用方期望有损压缩，不能静默忽略
+        OutputEncoding::WebP if quality.is_some() => {
+            return Err(VideoError::new(
+                VideoErrorCode::InvalidInput,
+                Some("WebP只支持无损编码，不能设置编码质量".to_string()),
+            ))
+        }
+        OutputEncoding::WebP => WebPEncoder::new_lossless(&mut encoded).write_image(
+            &data,
+            width,
+            height,
+            color_type(format)?,
+        ),
+    };
+
+    match result {
+        Ok(()) => Ok(encoded),
+        Err(e) => Err(VideoError::new(
+            VideoErrorCode::EncodeFailed,
+            Some(format!("图片编码失败: {}", e)),
+        )),
+    }
+}
+
+// 把同样尺寸的RGBA帧编码为循环播放的GIF动画，每帧附带显示时长（毫秒）
+pub(crate) fn encode_gif(
+    frames: Vec<(Vec<u8>, u32)>,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, VideoError> {
+    memory_watch::reserve(animation_bytes(&frames, width, height))?;
+    let mut animation = Vec::with_capacity(frames.len());
+    for (data, delay_ms) in frames {
+        let image = RgbaImage::from_raw(width, height, data).ok_or(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("帧数据与尺寸 {}x{} 不符", width, height)),
+        ))?;
+        animation.push(Frame::from_parts(
+            image,
+            0,
+            0,
+            Delay::from_numer_denom_ms(delay_ms, 1),
+        ));
+    }
+
+    let mut encoded = Vec::new();
+    let result = {
+        // 速度10是NeuQuant量化在画质和速度之间的默认折中
+        let mut encoder = GifEncoder::new_with_speed(&mut encoded, 10);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .and_then(|_| encoder.encode_frames(animation))
+    };
+
+    match result {
+        Ok(()) => Ok(encoded),
+        Err(e) => Err(VideoError::new(
+            VideoErrorCode::EncodeFailed,
+            Some(format!("GIF编码失败: {}", e)),
+        )),
+    }
+}
+
+// 把同样尺寸的RGBA帧编码为循环播放的APNG动画，每帧附带显示时长（毫秒）
+pub(crate) fn encode_apng(
+    frames: Vec<(Vec<u8>, u32)>,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, VideoError> {
+    memory_watch::reserve(animation_bytes(&frames, width, height))?;
+    let mut encoded = Vec::new();
+    match write_apng(&mut encoded, &frames, width, height) {
+        Ok(()) => Ok(encoded),
+        Err(e) => Err(VideoError::new(
+            VideoErrorCode::EncodeFailed,
+            Some(format!("APNG编码失败: {}", e)),
+        )),
+    }
+}
+
+fn write_apng(
+    out: &mut Vec<u8>,
+    frames: &[(Vec<u8>, u32)],
+    width: u32,
+    height: u32,
+) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // 循环次数为0表示无限循环
+    encoder.set_animated(frames.len() as u32, 0)?;
+    let mut writer = encoder.write_header()?;
+    for (data, delay_ms) in frames {
+        writer.set_frame_delay((*delay_ms).min(u32::from(u16::MAX)) as u16, 1000)?;
+        writer.write_image_data(data)?;
+    }
+    writer.finish()
+}
+
+// 把同样尺寸的RGBA帧编码为循环播放的动画WebP，每帧附带显示时长（毫秒）
+//
+// image的WebP编码器只能输出静态图片：这里逐帧编码为无损WebP，取出其中的VP8L数据块，
+// 再按WebP扩展格式包装为ANMF帧
+pub(crate) fn encode_animated_webp(
+    frames: Vec<(Vec<u8>, u32)>,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, VideoError> {
+    memory_watch::reserve(animation_bytes(&frames, width, height))?;
+    let mut body = Vec::new();
+
+    // VP8X：动画和透明通道标志，画布宽高减一
+    let mut header = vec![WEBP_FLAG_ANIMATION | WEBP_FLAG_ALPHA, 0, 0, 0];
+    header.extend_from_slice(&u24(width - 1));
+    header.extend_from_slice(&u24(height - 1));
+    push_chunk(&mut body, b"VP8X", &header);
+    // ANIM：背景色（BGRA）和循环次数，循环次数为0表示无限循环
+    push_chunk(&mut body, b"ANIM", &[0, 0, 0, 0, 0, 0]);
+
+    for (data, delay_ms) in frames {
+        let still = encode_frame(
+            data,
+            width,
+            height,
+            OutputFormat::Rgba,
+            OutputEncoding::WebP,
+            None,
+        )?;
+        let bitstream = find_chunk(&still, b"VP8L").ok_or(VideoError::new(
+            VideoErrorCode::EncodeFailed,
+            Some("WebP编码器的输出中没有VP8L数据".to_string()),
+        ))?;
+
+        // ANMF：帧偏移（两个24位的0）、帧宽高减一、显示时长和标志，之后是帧数据
+        let mut frame = vec![0u8; 6];
+        frame.extend_from_slice(&u24(width - 1));
+        frame.extend_from_slice(&u24(height - 1));
+        frame.extend_from_slice(&u24(delay_ms.min(0xFF_FFFF)));
+        // 每帧都覆盖整个画布：不与上一帧混合，显示后不清除
+        frame.push(WEBP_NO_BLEND);
+        push_chunk(&mut frame, b"VP8L", bitstream);
+        push_chunk(&mut body, b"ANMF", &frame);
+    }
+
+    let mut encoded = Vec::with_capacity(body.len() + 12);
+    encoded.extend_from_slice(b"RIFF");
+    encoded.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    encoded.extend_from_slice(b"WEBP");
+    encoded.extend_from_slice(&body);
+    Ok(encoded)
+}
+
+// 编码动画需要的内存：编码后的数据按不超过原始的RGBA帧估算
+fn animation_bytes(frames: &[(Vec<u8>, u32)], width: u32, height: u32) -> usize {
+    frames.len() * width as usize * height as usize * 4
+}
+
+// 追加一个RIFF数据块，奇数长度补一个字节
+pub(crate) fn push_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+// 在WebP文件中查找指定的数据块，返回其内容
+fn find_chunk<'a>(webp: &'a [u8], fourcc: &[u8; 4]) -> Option<&'a [u8]> {
+    // 跳过 "RIFF" <大小> "WEBP"
+    let mut offset = 12;
+    while offset + 8 <= webp.len() {
+        let size = u32::from_le_bytes(webp[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let start = offset + 8;
+        let end = start.checked_add(size).filter(|&end| end <= webp.len())?;
+        if &webp[offset..offset + 4] == fourcc {
+            return Some(&webp[start..end]);
+        }
+        offset = end + size % 2;
+    }
+    None
+}
+
+// 24位小端整数
+fn u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+// 输出格式对应的图片颜色类型
+fn color_type(format: OutputFormat) -> Result<ExtendedColorType, VideoError> {
+    match format {
+        OutputFormat::Rgb24 => Ok(ExtendedColorType::Rgb8),
+        OutputFormat::Rgba => Ok(ExtendedColorType::Rgba8),
+        OutputFormat::Gray8 => Ok(ExtendedColorType::L8),
+        OutputFormat::Bgra | OutputFormat::Bgr24 | OutputFormat::Yuv420p => Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("{:?} 格式的帧不能编码为图片", format)),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::codecs::webp::WebPDecoder;
+    use image::{AnimationDecoder, ImageDecoder};
+    use std::io::Cursor;
+
+    // 测试动画WebP的封装：解码后的画布尺寸、帧数、显示时长和像素与输入一致
+    #[test]
+    fn test_animated_webp() {
+        let (width, height) = (3, 2);
+        let red: Vec<u8> = (0..width * height).flat_map(|_| [255, 0, 0, 255]).collect();
+        let blue: Vec<u8> = (0..width * height).flat_map(|_| [0, 0, 255, 128]).collect();
+        let webp =
+            encode_animated_webp(vec![(red.clone(), 100), (blue.clone(), 250)], width, height)
+                .unwrap();
+
+        let decoder = WebPDecoder::new(Cursor::new(&webp)).unwrap();
+        assert!(decoder.has_animation());
+        assert_eq!(decoder.dimensions(), (width, height));
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].delay().numer_denom_ms(), (100, 1));
+        assert_eq!(frames[1].delay().numer_denom_ms(), (250, 1));
+        assert_eq!(frames[0].buffer().as_raw(), &red);
+        assert_eq!(frames[1].buffer().as_raw(), &blue);
+    }
+
+    // 测试WebP设置质量时返回错误，不设置时正常编码
+    #[test]
+    fn test_webp_quality() {
+        let rgb = vec![0u8; 2 * 2 * 3];
+        let error = encode_frame(
+            rgb.clone(),
+            2,
+            2,
+            OutputFormat::Rgb24,
+            OutputEncoding::WebP,
+            Some(80),
+        )
+        .unwrap_err();
+        assert_eq!(error.code, VideoErrorCode::InvalidInput);
+
+        let webp =
+            encode_frame(rgb, 2, 2, OutputFormat::Rgb24, OutputEncoding::WebP, None).unwrap();
+        assert_eq!(&webp[..4], b"RIFF");
+    }
+}
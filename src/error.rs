@@ -13,6 +13,8 @@ pub enum VideoErrorCode {
     InvalidInput = 5,  // 无效输入
     SeekFailed = 6,    // 定位失败
     FFmpegError = 7,   // FFmpeg错误
+    MemoryLimit = 8,   // 超出临时内存上限
+    EncodeFailed = 9,  // 图片编码失败
 }
 
 // VideoErrorCode 的常规方法实现
@@ -28,6 +30,8 @@ impl VideoErrorCode {
             VideoErrorCode::InvalidInput => "无效的输入数据".to_string(),
             VideoErrorCode::SeekFailed => "定位帧失败".to_string(),
             VideoErrorCode::FFmpegError => "FFmpeg内部错误".to_string(),
+            VideoErrorCode::MemoryLimit => "超出临时内存上限".to_string(),
+            VideoErrorCode::EncodeFailed => "图片编码失败".to_string(),
         }
     }
 
@@ -35,6 +39,22 @@ impl VideoErrorCode {
     pub fn get_code(&self) -> u32 {
         *self as u32
     }
+
+    // 从错误代码值还原，无法识别的值视为未知错误
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            1 => VideoErrorCode::InitFailed,
+            2 => VideoErrorCode::NoVideoStream,
+            3 => VideoErrorCode::DecoderFailed,
+            4 => VideoErrorCode::FrameNotFound,
+            5 => VideoErrorCode::InvalidInput,
+            6 => VideoErrorCode::SeekFailed,
+            7 => VideoErrorCode::FFmpegError,
+            8 => VideoErrorCode::MemoryLimit,
+            9 => VideoErrorCode::EncodeFailed,
+            _ => VideoErrorCode::Unknown,
+        }
+    }
 }
 
 // 内部使用的简化错误类型
@@ -42,6 +62,8 @@ impl VideoErrorCode {
 pub struct VideoError {
     pub code: VideoErrorCode,
     pub message: String,
+    // 开启日志捕获时收集到的FFmpeg日志
+    pub log: Vec<String>,
 }
 
 impl VideoError {
@@ -52,7 +74,17 @@ impl VideoError {
             None => format!("{:?}", code),
         };
 
-        Self { code, message }
+        Self {
+            code,
+            message,
+            log: Vec::new(),
+        }
+    }
+
+    // 附加捕获到的FFmpeg日志
+    pub fn with_log(mut self, log: Vec<String>) -> Self {
+        self.log = log;
+        self
     }
 
     // 获取错误代码
@@ -92,35 +124,51 @@ impl From<ffmpeg_next::Error> for VideoError {
 pub struct VideoResult {
     #[wasm_bindgen(skip)]
     buffer: Vec<u8>,
+    width: u32,
+    height: u32,
     success: bool,
     error_code: u32,
     error_message: String,
+    #[wasm_bindgen(skip)]
+    warnings: Vec<String>,
 }
 
 impl VideoResult {
     // 创建成功结果
-    pub fn success(data: Vec<u8>) -> Self {
+    pub fn success(data: Vec<u8>, width: u32, height: u32) -> Self {
         Self {
             buffer: data,
+            width,
+            height,
             success: true,
             error_code: VideoErrorCode::Unknown as u32,
             error_message: "".to_string(),
+            warnings: Vec::new(),
         }
     }
 
+    // 附加抽帧过程中记录的警告
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
     // 创建错误结果
     pub fn error(code: VideoErrorCode, message: &str) -> Self {
         Self {
             buffer: Vec::new(),
+            width: 0,
+            height: 0,
             success: false,
             error_code: code as u32,
             error_message: message.to_string(),
+            warnings: Vec::new(),
         }
     }
 
-    // 获取数据缓冲区
-    pub fn get_buffer(&self) -> Vec<u8> {
-        self.buffer.clone()
+    // 取出数据缓冲区，不复制；取出后结果中的缓冲区为空，不再归还到复用池
+    pub fn get_buffer(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
     }
 
     // 检查是否成功
@@ -144,6 +192,8 @@ impl VideoResult {
             5 => "无效的输入数据".to_string(),   // InvalidInput
             6 => "定位帧失败".to_string(),       // SeekFailed
             7 => "FFmpeg内部错误".to_string(),   // FFmpegError
+            8 => "超出临时内存上限".to_string(), // MemoryLimit
+            9 => "图片编码失败".to_string(),     // EncodeFailed
             _ => format!("未知错误代码: {}", self.error_code),
         }
     }
@@ -154,6 +204,42 @@ impl VideoResult {
     }
 }
 
+#[wasm_bindgen]
+impl VideoResult {
+    /// 帧宽度（像素），失败时为 0
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// 帧高度（像素），失败时为 0
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 抽帧成功但结果可能与预期不符时的警告，例如视频参数在流中发生变化、
+    /// 视频流只有一帧而忽略了请求的时间点；没有警告时为空数组
+    #[wasm_bindgen(getter)]
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.clone()
+    }
+
+    /// 用完结果后归还输出缓冲区，开启缓冲区复用（`setResultPooling`）时供下次抽帧使用
+    ///
+    /// 调用后该对象失效，不能再访问；与调用 `free()` 的效果相同。
+    pub fn recycle(self) {
+        drop(self);
+    }
+}
+
+// 结果释放时把仍持有的输出缓冲区归还到复用池
+impl Drop for VideoResult {
+    fn drop(&mut self) {
+        crate::buffer_pool::recycle(std::mem::take(&mut self.buffer));
+    }
+}
+
 // 辅助函数：日志记录
 #[allow(dead_code)]
 pub fn log_error(error: &VideoError) {
@@ -164,10 +250,16 @@ pub fn log_error(error: &VideoError) {
             "VideoError: [{:?}] {}",
             error.code, error.message
         )));
+        for line in &error.log {
+            web_sys::console::error_1(&JsValue::from_str(line));
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     {
         eprintln!("VideoError: [{:?}] {}", error.code, error.message);
+        for line in &error.log {
+            eprintln!("    {}", line);
+        }
     }
 }
@@ -0,0 +1,433 @@
+// frame_hash.rs
+// 逐帧内容哈希
+//
+// 转码或转封装之后需要确认内容的顺序和长度没有变化，但逐像素比较既慢又不现实。
+// 这里一次读取整个视频，为关键帧或按固定间隔取样的帧各算一个64位哈希，
+// 再把整个序列滚动合并为一个摘要，比较两个文件时只需要比较这些数字。
+//
+// 另外提供单帧的感知哈希（pHash、dHash），用于视频去重和识别几乎相同的重复上传：
+// 缩放、重新编码或轻微调色之后哈希只有少数几位不同。
+
+use std::f64::consts::PI;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::options::{ExtractOptions, OutputFormat};
+use crate::video_processor;
+
+// 均值哈希的取样间隔（秒）
+const SAMPLE_INTERVAL: f64 = 1.0;
+
+// 均值哈希使用的亮度缩略图尺寸，每个像素对应哈希的一位
+const HASH_SIZE: u32 = 8;
+
+// pHash先把帧缩小到该尺寸再做DCT，只取左上角8x8的低频分量
+const PHASH_SIZE: u32 = 32;
+const PHASH_LOW_FREQUENCIES: usize = 8;
+
+const PRIME1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME5: u64 = 0x27D4_EB2F_1656_67C5;
+
+/// 逐帧哈希的算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameHashAlgorithm {
+    /// 每个关键帧压缩数据的xxHash64，不需要解码，只能确认逐字节相同的流复制；
+    /// 任何重新编码都会改变压缩数据，MP4（AVCC）与TS（Annex B）之间转封装时
+    /// 码流的封装方式不同，也会改变
+    KeyframeXxHash,
+    /// 每秒取一帧，计算8x8亮度的均值哈希；能容忍重新编码带来的细微差异，
+    /// 用于确认转码后内容的顺序和长度
+    #[default]
+    SampledAverage,
+}
+
+/// 一帧的哈希
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameHash {
+    /// 帧的时间（秒）
+    pub time: f64,
+    /// 64位哈希
+    pub hash: u64,
+}
+
+/// 整个视频的逐帧哈希
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameHashes {
+    /// 使用的算法
+    pub algorithm: FrameHashAlgorithm,
+    /// 按时间顺序的逐帧哈希
+    pub frames: Vec<FrameHash>,
+    /// 按顺序合并所有哈希得到的摘要，顺序或数量变化时随之改变
+    pub digest: u64,
+}
+
+impl FrameHashes {
+    /// 与另一组哈希逐帧比较
+    ///
+    /// # 参数
+    /// * `other` - 另一个文件使用相同算法得到的哈希
+    /// * `max_distance` - 每帧允许的最大汉明距离（位）；
+    ///   `KeyframeXxHash` 应为0，`SampledAverage` 常用 5 - 10
+    ///
+    /// # 返回
+    /// * `bool` - 算法和帧数相同，并且每一帧的差异都在允许范围内时返回true
+    pub fn matches(&self, other: &FrameHashes, max_distance: u32) -> bool {
+        self.algorithm == other.algorithm
+            && self.frames.len() == other.frames.len()
+            && self
+                .frames
+                .iter()
+                .zip(&other.frames)
+                .all(|(a, b)| hamming_distance(a.hash, b.hash) <= max_distance)
+    }
+}
+
+/// 一次读取计算整个视频的逐帧哈希
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `algorithm` - 哈希算法
+///
+/// # 返回
+/// * `Result<FrameHashes, VideoError>` - 成功时返回逐帧哈希和摘要
+pub fn hash_frames<P: AsRef<Path>>(
+    input_path: P,
+    algorithm: FrameHashAlgorithm,
+) -> Result<FrameHashes, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let options = config::default_options();
+    let frames = match algorithm {
+        FrameHashAlgorithm::KeyframeXxHash => keyframe_hashes(input_path.as_ref(), &options)?,
+        FrameHashAlgorithm::SampledAverage => sampled_hashes(input_path.as_ref(), &options)?,
+    };
+
+    // 摘要对依次排列的哈希再做一次xxHash64
+    let bytes: Vec<u8> = frames
+        .iter()
+        .flat_map(|frame| frame.hash.to_le_bytes())
+        .collect();
+    Ok(FrameHashes {
+        algorithm,
+        digest: xxh64(&bytes, 0),
+        frames,
+    })
+}
+
+// 只读取压缩数据，对每个关键帧数据包做xxHash64，不需要解码器
+fn keyframe_hashes(path: &Path, options: &ExtractOptions) -> Result<Vec<FrameHash>, VideoError> {
+    let mut ictx = video_processor::open_input(path, options)?;
+    let video_index = video_processor::select_video_stream(&ictx, options)?;
+    video_processor::discard_other_streams(&mut ictx, video_index);
+    let time_base = ictx
+        .stream(video_index)
+        .map(|stream| f64::from(stream.time_base()))
+        .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+    Ok(video_processor::read_packets(&mut ictx)
+        .filter(|(index, packet)| *index == video_index && packet.is_key())
+        .map(|(_, packet)| FrameHash {
+            time: packet
+                .pts()
+                .or(packet.dts())
+                .map_or(f64::NAN, |ts| ts as f64 * time_base),
+            hash: xxh64(packet.data().unwrap_or_default(), 0),
+        })
+        .collect())
+}
+
+// 按固定间隔解码取样，对每个样本的亮度缩略图做均值哈希
+fn sampled_hashes(path: &Path, options: &ExtractOptions) -> Result<Vec<FrameHash>, VideoError> {
+    let mut frames = Vec::new();
+    let mut next_sample = f64::NEG_INFINITY;
+    video_processor::for_each_video_frame(path, options, 0.0, |frame, time| {
+        if time >= next_sample {
+            next_sample = time + SAMPLE_INTERVAL;
+            let luma = video_processor::convert_to_luma(frame, HASH_SIZE, HASH_SIZE, options)?;
+            frames.push(FrameHash {
+                time,
+                hash: average_hash(&luma),
+            });
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    Ok(frames)
+}
+
+/// 计算指定时间点帧的感知哈希（pHash）
+///
+/// 把亮度缩小到32x32做二维DCT，左上角8x8的低频分量高于其中位数的记为1。
+/// 对缩放、重新编码、亮度和对比度调整都不敏感，适合识别重复的上传。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `time_sec` - 帧的时间点（秒）
+///
+/// # 返回
+/// * `Result<u64, VideoError>` - 成功时返回64位哈希，用 `hamming_distance` 比较，
+///   距离不超过10左右通常可以视为同一画面
+pub fn frame_phash<P: AsRef<Path>>(input_path: P, time_sec: f64) -> Result<u64, VideoError> {
+    let luma = gray_thumbnail(input_path.as_ref(), time_sec, PHASH_SIZE, PHASH_SIZE)?;
+    Ok(perceptual_hash(&luma))
+}
+
+// 由32x32的亮度数据计算pHash
+fn perceptual_hash(luma: &[u8]) -> u64 {
+    let size = PHASH_SIZE as usize;
+    // 只需要低频分量，先对每行做一维DCT，再对结果的每列做
+    let cosines: Vec<f64> = (0..PHASH_LOW_FREQUENCIES)
+        .flat_map(|k| {
+            (0..size).map(move |n| (PI / size as f64 * (n as f64 + 0.5) * k as f64).cos())
+        })
+        .collect();
+    let dct = |values: &[f64], k: usize| -> f64 {
+        values
+            .iter()
+            .zip(&cosines[k * size..(k + 1) * size])
+            .map(|(value, cos)| value * cos)
+            .sum()
+    };
+
+    let levels: Vec<f64> = luma.iter().map(|&level| f64::from(level)).collect();
+    let rows: Vec<[f64; PHASH_LOW_FREQUENCIES]> = levels
+        .chunks_exact(size)
+        .map(|row| std::array::from_fn(|k| dct(row, k)))
+        .collect();
+    let columns: Vec<Vec<f64>> = (0..PHASH_LOW_FREQUENCIES)
+        .map(|u| rows.iter().map(|row| row[u]).collect())
+        .collect();
+    let mut coefficients = Vec::with_capacity(PHASH_LOW_FREQUENCIES * PHASH_LOW_FREQUENCIES);
+    for v in 0..PHASH_LOW_FREQUENCIES {
+        for column in &columns {
+            coefficients.push(dct(column, v));
+        }
+    }
+
+    let mut sorted = coefficients.clone();
+    sorted.sort_by(f64::total_cmp);
+    let median = (sorted[31] + sorted[32]) / 2.0;
+    coefficients
+        .iter()
+        .enumerate()
+        .filter(|&(_, &value)| value > median)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+/// 计算指定时间点帧的差值哈希（dHash）
+///
+/// 把亮度缩小到9x8，每行相邻两个像素中右边更亮的记为1。比pHash更快，
+/// 对亮度变化同样不敏感，但对裁剪和较大的缩放更敏感。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `time_sec` - 帧的时间点（秒）
+///
+/// # 返回
+/// * `Result<u64, VideoError>` - 成功时返回64位哈希，用 `hamming_distance` 比较
+pub fn frame_dhash<P: AsRef<Path>>(input_path: P, time_sec: f64) -> Result<u64, VideoError> {
+    let luma = gray_thumbnail(input_path.as_ref(), time_sec, HASH_SIZE + 1, HASH_SIZE)?;
+    Ok(difference_hash(&luma))
+}
+
+// 由9x8的亮度数据计算dHash，第 `row * 8 + column` 位对应该行第 `column + 1` 个像素是否比左边更亮
+fn difference_hash(luma: &[u8]) -> u64 {
+    luma.chunks_exact(HASH_SIZE as usize + 1)
+        .flat_map(|row| row.windows(2).map(|pair| pair[1] > pair[0]))
+        .enumerate()
+        .filter(|&(_, brighter)| brighter)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+/// 两个64位哈希之间不同的位数
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+// 按给定尺寸（不保持宽高比）缩小后的亮度数据
+fn gray_thumbnail(
+    input_path: &Path,
+    time_sec: f64,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, VideoError> {
+    let mut options = config::default_options().for_analysis(OutputFormat::Gray8);
+    options.output_width = Some(width);
+    options.output_height = Some(height);
+    options.max_output_bytes = None;
+    Ok(video_processor::extract_frame_with_options(input_path, time_sec, &options)?.data)
+}
+
+// 亮度高于平均值的像素记为1
+fn average_hash(luma: &[u8]) -> u64 {
+    let mean = luma.iter().map(|&level| u32::from(level)).sum::<u32>() / luma.len().max(1) as u32;
+    luma.iter()
+        .take(64)
+        .enumerate()
+        .filter(|&(_, &level)| u32::from(level) > mean)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+// xxHash64，与 https://github.com/Cyan4973/xxHash 的参考实现一致
+fn xxh64(data: &[u8], seed: u64) -> u64 {
+    let read64 = |bytes: &[u8]| u64::from_le_bytes(bytes[..8].try_into().unwrap_or_default());
+    let round = |acc: u64, input: u64| {
+        acc.wrapping_add(input.wrapping_mul(PRIME2))
+            .rotate_left(31)
+            .wrapping_mul(PRIME1)
+    };
+
+    let mut stripes = data.chunks_exact(32);
+    let mut hash = if data.len() >= 32 {
+        let mut acc = [
+            seed.wrapping_add(PRIME1).wrapping_add(PRIME2),
+            seed.wrapping_add(PRIME2),
+            seed,
+            seed.wrapping_sub(PRIME1),
+        ];
+        for stripe in &mut stripes {
+            for (lane, bytes) in acc.iter_mut().zip(stripe.chunks_exact(8)) {
+                *lane = round(*lane, read64(bytes));
+            }
+        }
+        let mut hash = acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        for lane in acc {
+            hash = (hash ^ round(0, lane))
+                .wrapping_mul(PRIME1)
+                .wrapping_add(PRIME4);
+        }
+        hash
+    } else {
+        seed.wrapping_add(PRIME5)
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    let mut rest = stripes.remainder();
+    while rest.len() >= 8 {
+        hash = (hash ^ round(0, read64(rest)))
+            .rotate_left(27)
+            .wrapping_mul(PRIME1)
+            .wrapping_add(PRIME4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        let word = u64::from(u32::from_le_bytes(rest[..4].try_into().unwrap_or_default()));
+        hash = (hash ^ word.wrapping_mul(PRIME1))
+            .rotate_left(23)
+            .wrapping_mul(PRIME2)
+            .wrapping_add(PRIME3);
+        rest = &rest[4..];
+    }
+    for &byte in rest {
+        hash = (hash ^ u64::from(byte).wrapping_mul(PRIME5))
+            .rotate_left(11)
+            .wrapping_mul(PRIME1);
+    }
+
+    // 雪崩混合
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME3);
+    hash ^ (hash >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试xxHash64与参考实现的一致性，保存下来的摘要跨版本比较时不能改变
+    #[test]
+    fn test_xxh64() {
+        // 官方参考值，覆盖短输入和超过32字节的分块路径
+        assert_eq!(xxh64(b"", 0), 0xEF46DB3751D8E999);
+        assert_eq!(xxh64(b"abc", 0), 0x44BC2CF5AD770999);
+        assert_eq!(
+            xxh64(b"Nobody inspects the spammish repetition", 0),
+            0xFBCEA83C8A378BF1
+        );
+    }
+
+    // 测试均值哈希和逐帧比较
+    #[test]
+    fn test_average_hash() {
+        // 前一半像素为黑，后一半为白
+        let luma: Vec<u8> = (0..64).map(|i| if i < 32 { 0 } else { 255 }).collect();
+        assert_eq!(average_hash(&luma), 0xFFFF_FFFF_0000_0000);
+
+        // 默认算法能容忍转码，适合确认转码结果
+        assert_eq!(
+            FrameHashAlgorithm::default(),
+            FrameHashAlgorithm::SampledAverage
+        );
+        let hashes = |hashes: &[u64]| FrameHashes {
+            algorithm: FrameHashAlgorithm::default(),
+            frames: hashes
+                .iter()
+                .enumerate()
+                .map(|(i, &hash)| FrameHash {
+                    time: i as f64,
+                    hash,
+                })
+                .collect(),
+            digest: 0,
+        };
+        let original = hashes(&[0b1111, 0]);
+        assert!(original.matches(&hashes(&[0b0111, 0]), 1));
+        assert!(!original.matches(&hashes(&[0b0011, 0]), 1));
+        assert!(!original.matches(&hashes(&[0b1111]), 1));
+    }
+
+    // 测试两个哈希之间的汉明距离
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0110), 3);
+        assert_eq!(hamming_distance(0, u64::MAX), 64);
+    }
+
+    // 测试dHash：每行从左到右变亮时所有位为1，变暗时为0；只有第一行变亮时只有低8位为1
+    #[test]
+    fn test_difference_hash() {
+        let brighter: Vec<u8> = (0..8).flat_map(|_| 0..9u8).collect();
+        let darker: Vec<u8> = (0..8).flat_map(|_| (0..9u8).rev()).collect();
+        assert_eq!(difference_hash(&brighter), u64::MAX);
+        assert_eq!(difference_hash(&darker), 0);
+        let first_row: Vec<u8> = brighter[..9].iter().chain(&darker[9..]).copied().collect();
+        assert_eq!(difference_hash(&first_row), 0xFF);
+    }
+
+    // 测试pHash：亮度和对比度的线性调整不改变哈希，画面左右翻转后大部分位不同
+    #[test]
+    fn test_perceptual_hash() {
+        let size = PHASH_SIZE as usize;
+        // 伪随机的偶数亮度，减半后没有舍入误差
+        let mut seed = 1u32;
+        let image: Vec<u8> = (0..size * size)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 24) as u8 & 0xFE
+            })
+            .collect();
+        let hash = perceptual_hash(&image);
+
+        let adjusted: Vec<u8> = image.iter().map(|&level| level / 2 + 40).collect();
+        assert_eq!(perceptual_hash(&adjusted), hash);
+
+        let mirrored: Vec<u8> = image
+            .chunks_exact(size)
+            .flat_map(|row| row.iter().rev().copied())
+            .collect();
+        assert!(hamming_distance(hash, perceptual_hash(&mirrored)) >= 16);
+    }
+}
@@ -0,0 +1,165 @@
+// frame_match.rs
+// 在视频中查找与参考图像最相似的帧
+//
+// 用于在长录像中定位场记板、台标或某个特定镜头。参考图像和每一帧都缩小到
+// 同样的小尺寸后逐像素比较，对轻微的压缩失真和尺寸差异不敏感，速度也足够快。
+
+use std::ops::{ControlFlow, Range};
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::options::ExtractOptions;
+use crate::validation;
+use crate::video_processor;
+
+use ffmpeg::util::{format::Pixel, frame::video::Video};
+use ffmpeg_next as ffmpeg;
+
+// 比较时使用的缩略图尺寸（像素）
+const COMPARE_SIZE: u32 = 32;
+
+/// 匹配到的帧
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameMatch {
+    /// 帧的时间（秒）
+    pub time: f64,
+    /// 与参考图像的相似度，范围 0.0 - 1.0，1.0 表示完全一致
+    pub similarity: f64,
+}
+
+/// 在时间范围内查找与参考图像最相似的帧
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `reference_rgb` - 参考图像，RGB24格式，逐行紧密排列
+/// * `reference_width` - 参考图像宽度（像素）
+/// * `reference_height` - 参考图像高度（像素）
+/// * `threshold` - 最低相似度（0.0 - 1.0），最相似的帧低于该值时视为没有找到
+/// * `range` - 扫描的时间范围（秒）
+///
+/// # 返回
+/// * `Result<Option<FrameMatch>, VideoError>` - 成功时返回范围内最相似的帧，没有达到阈值的帧时返回 `None`
+pub fn find_matching_frame<P: AsRef<Path>>(
+    input_path: P,
+    reference_rgb: &[u8],
+    reference_width: u32,
+    reference_height: u32,
+    threshold: f64,
+    range: Range<f64>,
+) -> Result<Option<FrameMatch>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    validation::validate_time(range.start)?;
+    if !range.end.is_finite() || range.end <= range.start {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的时间范围: {}..{}", range.start, range.end)),
+        ));
+    }
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的相似度阈值: {}", threshold)),
+        ));
+    }
+
+    let reference = reference_thumbnail(reference_rgb, reference_width, reference_height)?;
+
+    let options = config::default_options();
+    let mut scanner = Scanner {
+        reference,
+        range: range.clone(),
+        best: None,
+    };
+    video_processor::for_each_video_frame(
+        input_path.as_ref(),
+        &options,
+        range.start,
+        |frame, time| scanner.push(frame, time, &options),
+    )?;
+
+    Ok(scanner.best.filter(|best| best.similarity >= threshold))
+}
+
+// 逐帧比较并记录最相似的一帧
+struct Scanner {
+    reference: Vec<u8>,
+    range: Range<f64>,
+    best: Option<FrameMatch>,
+}
+
+impl Scanner {
+    // 越过范围终点后停止
+    fn push(
+        &mut self,
+        frame: &Video,
+        time: f64,
+        options: &ExtractOptions,
+    ) -> Result<ControlFlow<()>, VideoError> {
+        if time > self.range.end {
+            return Ok(ControlFlow::Break(()));
+        }
+        if time < self.range.start {
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        // 参考图像通常取自自动旋转后的输出帧，缩略图也要转到同样的方向
+        let thumbnail =
+            video_processor::convert_to_rgb(frame, COMPARE_SIZE, COMPARE_SIZE, options)?;
+        let transform = options
+            .transform
+            .after_rotation(video_processor::frame_rotation(frame, options));
+        let (thumbnail, _, _) =
+            video_processor::apply_transform(thumbnail, COMPARE_SIZE, COMPARE_SIZE, 3, &transform);
+        let similarity = similarity(&self.reference, &thumbnail);
+        if self.best.is_none_or(|best| similarity > best.similarity) {
+            self.best = Some(FrameMatch { time, similarity });
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+// 把参考图像缩小为比较用的缩略图
+fn reference_thumbnail(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, VideoError> {
+    validation::validate_dimensions(width as i32, height as i32)?;
+    let row_bytes = width as usize * 3;
+    if data.len() != row_bytes * height as usize {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "参考图像尺寸与数据长度不符: {}x{}，{} 字节",
+                width,
+                height,
+                data.len()
+            )),
+        ));
+    }
+
+    // 复制到带行对齐的帧中再交给缩放器
+    let mut frame = Video::new(Pixel::RGB24, width, height);
+    let stride = frame.stride(0);
+    let plane = frame.data_mut(0);
+    for (row, line) in data.chunks_exact(row_bytes).enumerate() {
+        plane[row * stride..row * stride + row_bytes].copy_from_slice(line);
+    }
+
+    video_processor::convert_to_rgb(
+        &frame,
+        COMPARE_SIZE,
+        COMPARE_SIZE,
+        &ExtractOptions::default(),
+    )
+}
+
+// 两张缩略图的相似度：1 减去平均绝对差的归一化值
+fn similarity(a: &[u8], b: &[u8]) -> f64 {
+    let total: u64 = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| u64::from(x.abs_diff(y)))
+        .sum();
+    1.0 - total as f64 / (a.len().max(1) as f64 * 255.0)
+}
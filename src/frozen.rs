@@ -0,0 +1,197 @@
+// frozen.rs
+// 冻结画面检测
+//
+// 广播质检的常规项目：编码器或信号源故障时画面会停在某一帧上，声音却继续播放。
+// 这里在指定范围内逐帧解码，把每帧缩小为亮度缩略图后与上一帧比较，
+// 平均差异低于噪声水平的连续帧构成一段冻结，持续足够长时返回。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::validation;
+use crate::video_processor;
+
+// 比较用的亮度缩略图尺寸；缩小同时平均掉了大部分压缩噪声
+pub(crate) const THUMB_WIDTH: u32 = 64;
+pub(crate) const THUMB_HEIGHT: u32 = 36;
+
+// 与上一帧的平均亮度差（0 - 255）低于该值视为同一画面，约为满幅的0.2%
+const MAX_MEAN_DIFF: f64 = 0.5;
+
+/// 一段冻结画面
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrozenSpan {
+    /// 冻结开始的时间（秒），即停住的那一帧
+    pub start: f64,
+    /// 冻结结束的时间（秒），即画面重新变化的那一帧；冻结持续到范围结尾时为最后一帧
+    pub end: f64,
+}
+
+impl FrozenSpan {
+    /// 持续时间（秒）
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// 检测指定范围内的冻结画面
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `start` - 检测范围的起点（秒）
+/// * `end` - 检测范围的终点（秒），传 `f64::INFINITY` 检测到结尾
+/// * `min_duration` - 至少持续多长时间（秒）才算冻结，过滤掉正常的静止镜头
+///
+/// # 返回
+/// * `Result<Vec<FrozenSpan>, VideoError>` - 成功时返回按时间排序的冻结片段
+pub fn detect_frozen<P: AsRef<Path>>(
+    input_path: P,
+    start: f64,
+    end: f64,
+    min_duration: f64,
+) -> Result<Vec<FrozenSpan>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    validation::validate_time(start)?;
+    if end.is_nan() || end <= start || !(min_duration >= 0.0 && min_duration.is_finite()) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "无效的检测范围: {} - {}，最短持续时间: {}",
+                start, end, min_duration
+            )),
+        ));
+    }
+
+    let options = config::default_options();
+    let mut detector = FreezeDetector::new(min_duration);
+    video_processor::for_each_video_frame(input_path.as_ref(), &options, start, |frame, time| {
+        if time > end {
+            return Ok(ControlFlow::Break(()));
+        }
+        if time >= start {
+            let luma =
+                video_processor::convert_to_luma(frame, THUMB_WIDTH, THUMB_HEIGHT, &options)?;
+            detector.push(time, luma);
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    Ok(detector.finish())
+}
+
+// 逐帧比较，记录持续足够长的冻结
+#[derive(Default)]
+pub(crate) struct FreezeDetector {
+    min_duration: f64,
+    previous: Option<Vec<u8>>,
+    // 上一帧的时间
+    last: f64,
+    // 当前冻结开始的时间和最近一帧的时间
+    run: Option<(f64, f64)>,
+    spans: Vec<FrozenSpan>,
+}
+
+impl FreezeDetector {
+    pub(crate) fn new(min_duration: f64) -> Self {
+        Self {
+            min_duration,
+            ..Default::default()
+        }
+    }
+
+    // 加入下一帧的亮度缩略图，尺寸为 THUMB_WIDTH x THUMB_HEIGHT
+    pub(crate) fn push(&mut self, time: f64, luma: Vec<u8>) {
+        let frozen = self
+            .previous
+            .as_ref()
+            .is_some_and(|previous| mean_diff(previous, &luma) < MAX_MEAN_DIFF);
+
+        match (frozen, self.run) {
+            // 冻结从上一帧开始
+            (true, None) => self.run = Some((self.last, time)),
+            (true, Some((start, _))) => self.run = Some((start, time)),
+            (false, Some((start, _))) => {
+                self.close(start, time);
+                self.run = None;
+            }
+            (false, None) => {}
+        }
+
+        self.previous = Some(luma);
+        self.last = time;
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<FrozenSpan> {
+        if let Some((start, last)) = self.run.take() {
+            self.close(start, last);
+        }
+        self.spans
+    }
+
+    fn close(&mut self, start: f64, end: f64) {
+        if end - start >= self.min_duration {
+            self.spans.push(FrozenSpan { start, end });
+        }
+    }
+}
+
+// 两张缩略图的平均亮度差
+fn mean_diff(a: &[u8], b: &[u8]) -> f64 {
+    let diff: u64 = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| u64::from(x.abs_diff(y)))
+        .sum();
+    diff as f64 / a.len().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 逐秒送入亮度相同的缩略图
+    fn detect(levels: &[u8], min_duration: f64) -> Vec<FrozenSpan> {
+        let mut detector = FreezeDetector::new(min_duration);
+        for (i, &level) in levels.iter().enumerate() {
+            detector.push(i as f64, vec![level; 4]);
+        }
+        detector.finish()
+    }
+
+    // 测试冻结片段的起止时间和最短持续时间
+    #[test]
+    fn test_freeze_detector() {
+        // 冻结从停住的那一帧开始，到画面重新变化的那一帧结束
+        let span = FrozenSpan {
+            start: 1.0,
+            end: 4.0,
+        };
+        assert_eq!(detect(&[0, 10, 10, 10, 20, 30], 2.0), [span]);
+        assert!(detect(&[0, 10, 10, 10, 20, 30], 3.5).is_empty());
+
+        // 持续到结尾的冻结以最后一帧结束
+        let span = FrozenSpan {
+            start: 1.0,
+            end: 3.0,
+        };
+        assert_eq!(detect(&[0, 10, 10, 10], 2.0), [span]);
+
+        // 低于噪声水平的差异视为同一画面，平均差异达到 MAX_MEAN_DIFF 时冻结结束
+        let mut detector = FreezeDetector::new(1.0);
+        detector.push(0.0, vec![10, 10, 10, 10]);
+        detector.push(1.0, vec![10, 10, 10, 11]);
+        detector.push(2.0, vec![10, 10, 12, 11]);
+        assert_eq!(
+            detector.finish(),
+            [FrozenSpan {
+                start: 0.0,
+                end: 2.0
+            }]
+        );
+    }
+}
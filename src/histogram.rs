@@ -0,0 +1,109 @@
+// histogram.rs
+// 帧直方图
+//
+// 自动生成的缩略图需要剔除欠曝和过曝的画面。这里统计提取出的帧的R、G、B和亮度直方图，
+// 各256档，并提供几个常用的曝光统计，调用方可以自行设定剔除的阈值。
+
+use std::path::Path;
+
+use crate::error::VideoError;
+use crate::options::{ExtractOptions, OutputFormat, Position};
+use crate::video_processor;
+
+use wasm_bindgen::prelude::*;
+
+// 直方图的档数
+const BINS: usize = 256;
+
+/// 一帧的R、G、B和亮度直方图
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameHistogram {
+    /// 红色通道，256档，第i档为取值为i的像素数量
+    pub red: Vec<u32>,
+    /// 绿色通道
+    pub green: Vec<u32>,
+    /// 蓝色通道
+    pub blue: Vec<u32>,
+    /// 亮度，按BT.709系数由RGB计算
+    pub luma: Vec<u32>,
+    /// 统计的像素总数
+    pub pixels: u32,
+}
+
+#[wasm_bindgen]
+impl FrameHistogram {
+    /// 平均亮度（0 - 255）
+    #[wasm_bindgen(js_name = meanLuma)]
+    pub fn mean_luma(&self) -> f64 {
+        let sum: u64 = self
+            .luma
+            .iter()
+            .enumerate()
+            .map(|(level, &count)| level as u64 * u64::from(count))
+            .sum();
+        sum as f64 / f64::from(self.pixels.max(1))
+    }
+
+    /// 亮度不高于 `level` 的像素比例（0.0 - 1.0），用于判断欠曝
+    #[wasm_bindgen(js_name = shadowRatio)]
+    pub fn shadow_ratio(&self, level: u8) -> f64 {
+        self.ratio(&self.luma[..=usize::from(level)])
+    }
+
+    /// 亮度不低于 `level` 的像素比例（0.0 - 1.0），用于判断过曝
+    #[wasm_bindgen(js_name = highlightRatio)]
+    pub fn highlight_ratio(&self, level: u8) -> f64 {
+        self.ratio(&self.luma[usize::from(level)..])
+    }
+}
+
+impl FrameHistogram {
+    /// 统计紧密排列的RGB24像素数据
+    pub fn from_rgb24(data: &[u8]) -> Self {
+        let mut histogram = Self {
+            red: vec![0; BINS],
+            green: vec![0; BINS],
+            blue: vec![0; BINS],
+            luma: vec![0; BINS],
+            pixels: 0,
+        };
+        for pixel in data.chunks_exact(3) {
+            let (r, g, b) = (pixel[0], pixel[1], pixel[2]);
+            histogram.red[usize::from(r)] += 1;
+            histogram.green[usize::from(g)] += 1;
+            histogram.blue[usize::from(b)] += 1;
+            // BT.709系数放大256倍后取整，三者之和为256
+            let luma = (54 * u32::from(r) + 183 * u32::from(g) + 19 * u32::from(b) + 128) >> 8;
+            histogram.luma[luma as usize] += 1;
+            histogram.pixels += 1;
+        }
+        histogram
+    }
+
+    fn ratio(&self, bins: &[u32]) -> f64 {
+        let count: u64 = bins.iter().map(|&count| u64::from(count)).sum();
+        count as f64 / f64::from(self.pixels.max(1))
+    }
+}
+
+/// 统计指定位置帧的直方图
+///
+/// 按抽帧选项的尺寸和旋转输出后再统计，与同样选项下提取出的帧一致。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 帧的位置，可以是秒数或百分比
+/// * `options` - 抽帧选项，输出格式、编码和行布局由本函数决定
+///
+/// # 返回
+/// * `Result<FrameHistogram, VideoError>` - 成功时返回直方图
+pub fn analyze_frame<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<FrameHistogram, VideoError> {
+    let frame_options = options.for_analysis(OutputFormat::Rgb24);
+    let frame = video_processor::extract_frame_with_options(input_path, position, &frame_options)?;
+    Ok(FrameHistogram::from_rgb24(&frame.data))
+}
@@ -0,0 +1,203 @@
+// intro_credits.rs
+// 片头和片尾字幕的启发式检测
+//
+// 按“取视频时长10%处的帧”这种简单策略生成的缩略图和预览片段，
+// 经常落在片头台标或制作名单上。片头和片尾通常画面变化很小、
+// 以深色背景上的文字为主，这里按固定间隔取样，用运动量和文字特征
+// 估计片头结束和片尾开始的位置，供选择缩略图和预览片段时避开。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::options::ExtractOptions;
+use crate::video_processor::{self, VideoScan};
+
+// 分析用的亮度缩略图尺寸
+const THUMB_WIDTH: u32 = 96;
+const THUMB_HEIGHT: u32 = 54;
+
+// 两次取样之间的间隔（秒）
+const SAMPLE_INTERVAL: f64 = 1.0;
+
+// 在开头扫描多长时间寻找片头（秒）
+const INTRO_SCAN: f64 = 120.0;
+
+// 在结尾扫描多长时间寻找片尾（秒）
+const CREDITS_SCAN: f64 = 300.0;
+
+// 连续多少个正常内容样本才认为片头已经结束，避免被片头中的单个镜头打断
+const CONTENT_RUN: usize = 3;
+
+// 片尾至少持续多长时间（秒），避免把结尾的一个黑场当成片尾
+const MIN_CREDITS: f64 = 10.0;
+
+// 与上一个样本的平均亮度差（归一化）低于该值视为画面基本静止
+const LOW_MOTION: f64 = 0.03;
+
+// 相邻像素亮度差超过该值视为边缘
+const EDGE_THRESHOLD: u8 = 60;
+
+// 亮度低于该值的像素视为深色背景
+const DARK_LEVEL: u8 = 50;
+
+/// 片头和片尾的检测结果
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct IntroCredits {
+    /// 片头的结束时间（秒），片头从视频开头持续到这里；没有检测到片头时为 `None`
+    pub intro_end: Option<f64>,
+    /// 片尾的开始时间（秒），片尾从这里持续到视频结尾；没有检测到片尾时为 `None`
+    pub credits_start: Option<f64>,
+}
+
+/// 检测视频的片头和片尾字幕区域
+///
+/// 基于运动量和文字特征的启发式判断，只能作为选择缩略图和预览片段的参考。
+/// 片头只在开头两分钟内查找，片尾只在最后五分钟内查找。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<IntroCredits, VideoError>` - 成功时返回片头结束和片尾开始的时间
+pub fn detect_intro_credits<P: AsRef<Path>>(input_path: P) -> Result<IntroCredits, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let options = config::default_options();
+    let mut scan = VideoScan::open(input_path.as_ref(), &options)?;
+    let start = video_processor::start_time_secs(scan.input());
+    let duration = video_processor::resolve_duration(scan.input());
+
+    // 片头：从开头顺序解码
+    let head = samples(&mut scan, start + INTRO_SCAN, &options)?;
+    let intro_end = intro_end(&head);
+
+    // 片尾：定位到结尾附近，不与片头的扫描范围重叠
+    let credits_start = match duration {
+        Some(duration) if duration > INTRO_SCAN => {
+            let from = start + (duration - CREDITS_SCAN).max(INTRO_SCAN);
+            scan.seek(from)?;
+            let tail: Vec<Sample> = samples(&mut scan, f64::INFINITY, &options)?
+                .into_iter()
+                .filter(|sample| sample.time >= from)
+                .collect();
+            credits_start(&tail)
+        }
+        _ => None,
+    };
+
+    Ok(IntroCredits {
+        intro_end,
+        credits_start,
+    })
+}
+
+// 一个样本的特征
+struct Sample {
+    time: f64,
+    // 与上一个样本的平均亮度差（0.0 - 1.0），第一个样本为 0
+    motion: f64,
+    // 深色背景上的文字
+    text_like: bool,
+    // 几乎全黑
+    dark: bool,
+}
+
+impl Sample {
+    fn is_intro_like(&self) -> bool {
+        self.motion < LOW_MOTION && (self.text_like || self.dark)
+    }
+
+    // 滚动字幕的运动量不小，片尾只看文字特征
+    fn is_credits_like(&self) -> bool {
+        self.text_like || self.dark
+    }
+}
+
+// 从当前读取位置开始按固定间隔取样，直到超过 `until`（秒）或输入结束
+fn samples(
+    scan: &mut VideoScan,
+    until: f64,
+    options: &ExtractOptions,
+) -> Result<Vec<Sample>, VideoError> {
+    let mut samples = Vec::new();
+    let mut previous: Option<Vec<u8>> = None;
+    let mut next_sample = f64::NEG_INFINITY;
+
+    scan.for_each_frame(|frame, time| {
+        if time > until {
+            return Ok(ControlFlow::Break(()));
+        }
+        if time < next_sample {
+            return Ok(ControlFlow::Continue(()));
+        }
+        next_sample = time + SAMPLE_INTERVAL;
+
+        let luma = video_processor::convert_to_luma(frame, THUMB_WIDTH, THUMB_HEIGHT, options)?;
+        samples.push(analyze(time, &luma, previous.as_deref()));
+        previous = Some(luma);
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    Ok(samples)
+}
+
+// 计算一张亮度缩略图的特征
+fn analyze(time: f64, luma: &[u8], previous: Option<&[u8]>) -> Sample {
+    let pixels = luma.len().max(1) as f64;
+
+    let motion = previous.map_or(0.0, |previous| {
+        let diff: u64 = luma
+            .iter()
+            .zip(previous)
+            .map(|(&a, &b)| u64::from(a.abs_diff(b)))
+            .sum();
+        diff as f64 / (pixels * 255.0)
+    });
+
+    let dark_ratio = luma.iter().filter(|&&v| v < DARK_LEVEL).count() as f64 / pixels;
+    let edges = luma
+        .chunks_exact(THUMB_WIDTH as usize)
+        .flat_map(|row| row.windows(2))
+        .filter(|pair| pair[0].abs_diff(pair[1]) > EDGE_THRESHOLD)
+        .count() as f64
+        / pixels;
+
+    Sample {
+        time,
+        motion,
+        // 文字在深色背景上形成稀疏但清晰的边缘
+        text_like: dark_ratio > 0.6 && (0.01..0.25).contains(&edges),
+        dark: dark_ratio > 0.98 && edges < 0.005,
+    }
+}
+
+// 片头结束于第一段连续的正常内容开始处
+fn intro_end(samples: &[Sample]) -> Option<f64> {
+    let first_content = samples
+        .windows(CONTENT_RUN)
+        .position(|run| run.iter().all(|sample| !sample.is_intro_like()))?;
+    if first_content == 0 {
+        return None;
+    }
+    Some(samples[first_content].time)
+}
+
+// 片尾开始于结尾处连续的片尾样本的第一个
+fn credits_start(samples: &[Sample]) -> Option<f64> {
+    let last = samples.last()?;
+    let run = samples
+        .iter()
+        .rev()
+        .take_while(|sample| sample.is_credits_like())
+        .count();
+    if run == 0 {
+        return None;
+    }
+
+    let start = samples[samples.len() - run].time;
+    (last.time + SAMPLE_INTERVAL - start >= MIN_CREDITS).then_some(start)
+}
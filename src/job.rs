@@ -0,0 +1,457 @@
+// job.rs
+// 可持久化的抽帧任务
+//
+// 服务端排队中的抽帧任务会随进程重启丢失。这里把一个待处理的任务（输入、全部选项和进度）
+// 序列化为带版本号的紧凑二进制格式，服务可以在每完成一帧后保存检查点，重启后从上次的
+// 进度继续。格式直接由本库的类型定义，不依赖JSON等外部序列化方案。
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::log_capture::LogLevel;
+use crate::options::{
+    Dither, ExtractOptions, FieldSelection, OutputEncoding, OutputFormat, Position, SeekMode,
+};
+use crate::transform::{Rotation, Transform};
+use crate::video_processor::{self, RgbFrame};
+
+// 文件头和格式版本；修改格式时递增版本号
+const MAGIC: &[u8; 4] = b"VCJB";
+const VERSION: u8 = 3;
+
+/// 一个可以保存和恢复的抽帧任务
+///
+/// 依次提取 `positions` 中的每个位置，`completed` 记录已经完成的数量。
+#[derive(Debug, Clone)]
+pub struct ExtractionJob {
+    /// 输入文件路径或FFmpeg支持的URL
+    pub input: PathBuf,
+    /// 按顺序提取的位置
+    pub positions: Vec<Position>,
+    /// 抽帧选项
+    pub options: ExtractOptions,
+    /// 已完成的位置数量，恢复后从 `positions[completed]` 继续
+    pub completed: usize,
+}
+
+impl ExtractionJob {
+    /// 创建一个尚未开始的任务
+    pub fn new<P: AsRef<Path>>(
+        input: P,
+        positions: Vec<Position>,
+        options: ExtractOptions,
+    ) -> Self {
+        Self {
+            input: input.as_ref().to_path_buf(),
+            positions,
+            options,
+            completed: 0,
+        }
+    }
+
+    /// 是否所有位置都已完成
+    pub fn is_finished(&self) -> bool {
+        self.completed >= self.positions.len()
+    }
+
+    /// 下一个待提取的位置，全部完成时返回 `None`
+    pub fn next_position(&self) -> Option<Position> {
+        self.positions.get(self.completed).copied()
+    }
+
+    /// 提取下一个位置的帧
+    ///
+    /// 成功时进度前进一步；失败时进度不变，调用方可以重试或调用 `skip` 跳过。
+    /// 全部完成时返回 `None`。
+    pub fn run_next(&mut self) -> Option<Result<RgbFrame, VideoError>> {
+        let position = self.next_position()?;
+        let result =
+            video_processor::extract_frame_with_options(&self.input, position, &self.options);
+        if result.is_ok() {
+            self.completed += 1;
+        }
+        Some(result)
+    }
+
+    /// 跳过下一个位置，例如该位置反复失败时
+    pub fn skip(&mut self) {
+        if !self.is_finished() {
+            self.completed += 1;
+        }
+    }
+
+    /// 序列化为二进制数据，用于保存检查点
+    ///
+    /// # 返回
+    /// * `Result<Vec<u8>, VideoError>` - 输入路径不是有效的UTF-8时返回 `InvalidInput`
+    pub fn to_bytes(&self) -> Result<Vec<u8>, VideoError> {
+        let input = self.input.to_str().ok_or(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("输入路径不是有效的UTF-8: {}", self.input.display())),
+        ))?;
+
+        let mut w = Writer(Vec::new());
+        w.0.extend_from_slice(MAGIC);
+        w.u8(VERSION);
+        w.str(input);
+        w.u64(self.completed as u64);
+        w.u32(self.positions.len() as u32);
+        for position in &self.positions {
+            match position {
+                Position::Seconds(time_sec) => {
+                    w.u8(0);
+                    w.f64(*time_sec);
+                }
+                Position::Percent(percent) => {
+                    w.u8(1);
+                    w.f64(*percent);
+                }
+            }
+        }
+        write_options(&mut w, &self.options);
+        Ok(w.0)
+    }
+
+    /// 从 `to_bytes` 生成的数据恢复任务
+    ///
+    /// # 返回
+    /// * `Result<ExtractionJob, VideoError>` - 数据损坏、被截断或版本不支持时返回 `InvalidInput`
+    pub fn from_bytes(data: &[u8]) -> Result<Self, VideoError> {
+        let mut r = Reader { data, offset: 0 };
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(corrupt("文件头不匹配"));
+        }
+        let version = r.u8()?;
+        if !(1..=VERSION).contains(&version) {
+            return Err(VideoError::new(
+                VideoErrorCode::InvalidInput,
+                Some(format!("不支持的任务数据版本: {}", version)),
+            ));
+        }
+
+        let input = PathBuf::from(r.string()?);
+        let completed = r.u64()? as usize;
+        let count = r.u32()? as usize;
+        let mut positions = Vec::with_capacity(count.min(r.remaining() / 9));
+        for _ in 0..count {
+            positions.push(match r.u8()? {
+                0 => Position::Seconds(r.f64()?),
+                1 => Position::Percent(r.f64()?),
+                _ => return Err(corrupt("未知的位置类型")),
+            });
+        }
+        let options = read_options(&mut r, version)?;
+
+        if r.remaining() != 0 {
+            return Err(corrupt("结尾有多余的数据"));
+        }
+        if completed > positions.len() {
+            return Err(corrupt("进度超出位置数量"));
+        }
+
+        Ok(Self {
+            input,
+            positions,
+            options,
+            completed,
+        })
+    }
+}
+
+// 按字段顺序写出全部选项；解构时列出所有字段，新增选项时编译器会提示更新格式
+fn write_options(w: &mut Writer, options: &ExtractOptions) {
+    let ExtractOptions {
+        fflags,
+        error_concealment,
+        format_options,
+        decoder_options,
+        capture_log,
+        transform,
+        ignore_rotation,
+        repair_timestamps,
+        seek_mode,
+        target_height,
+        output_width,
+        output_height,
+        max_output_bytes,
+        max_temp_bytes,
+        linear_light_scaling,
+        dither,
+        output_format,
+        output_row_alignment,
+        bottom_up_rows,
+        output_encoding,
+        encoding_quality,
+        field,
+        keep_non_square_pixels,
+    } = options;
+
+    w.option(fflags, |w, value| w.str(value));
+    w.option(error_concealment, |w, value| w.str(value));
+    w.pairs(format_options);
+    w.pairs(decoder_options);
+    w.option(capture_log, |w, level| w.u8(level.code()));
+    w.u8(transform.rotation.code());
+    w.bool(transform.flip_horizontal);
+    w.bool(transform.flip_vertical);
+    w.bool(*ignore_rotation);
+    w.bool(*repair_timestamps);
+    w.u8(seek_mode.code());
+    w.option(target_height, |w, value| w.u32(*value));
+    w.option(output_width, |w, value| w.u32(*value));
+    w.option(output_height, |w, value| w.u32(*value));
+    w.option(max_output_bytes, |w, value| w.u64(*value as u64));
+    w.option(max_temp_bytes, |w, value| w.u64(*value as u64));
+    w.bool(*linear_light_scaling);
+    w.u8(dither.code());
+    w.u8(output_format.code());
+    w.u8(output_encoding.code());
+    w.option(encoding_quality, |w, value| w.u8(*value));
+    w.u8(field.code());
+    w.bool(*keep_non_square_pixels);
+    w.option(output_row_alignment, |w, value| w.u32(*value));
+    w.bool(*bottom_up_rows);
+}
+
+// 旧版本的数据没有后来新增的选项，按默认值处理
+fn read_options(r: &mut Reader, version: u8) -> Result<ExtractOptions, VideoError> {
+    Ok(ExtractOptions {
+        fflags: r.option(Reader::string)?,
+        error_concealment: r.option(Reader::string)?,
+        format_options: r.pairs()?,
+        decoder_options: r.pairs()?,
+        capture_log: r.option(Reader::code)?,
+        transform: Transform {
+            rotation: r.code()?,
+            flip_horizontal: r.bool()?,
+            flip_vertical: r.bool()?,
+        },
+        ignore_rotation: r.bool()?,
+        repair_timestamps: r.bool()?,
+        seek_mode: r.code()?,
+        target_height: r.option(Reader::u32)?,
+        output_width: r.option(Reader::u32)?,
+        output_height: r.option(Reader::u32)?,
+        max_output_bytes: r.option(|r| r.u64().map(|value| value as usize))?,
+        max_temp_bytes: r.option(|r| r.u64().map(|value| value as usize))?,
+        linear_light_scaling: r.bool()?,
+        dither: r.code()?,
+        output_format: r.code()?,
+        output_encoding: r.code()?,
+        encoding_quality: r.option(Reader::u8)?,
+        field: r.code()?,
+        keep_non_square_pixels: r.bool()?,
+        output_row_alignment: if version >= 2 {
+            r.option(Reader::u32)?
+        } else {
+            None
+        },
+        bottom_up_rows: version >= 3 && r.bool()?,
+    })
+}
+
+// 选项中的枚举与序列化编号之间的转换；编号一经发布不能修改
+trait Code: Sized {
+    fn code(self) -> u8;
+    fn from_code(code: u8) -> Option<Self>;
+}
+
+macro_rules! codes {
+    ($ty:ty { $($variant:path => $code:literal),+ $(,)? }) => {
+        impl Code for $ty {
+            fn code(self) -> u8 {
+                match self {
+                    $($variant => $code),+
+                }
+            }
+
+            fn from_code(code: u8) -> Option<Self> {
+                match code {
+                    $($code => Some($variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+codes!(LogLevel {
+    LogLevel::Error => 0,
+    LogLevel::Warning => 1,
+    LogLevel::Info => 2,
+    LogLevel::Verbose => 3,
+    LogLevel::Debug => 4,
+    LogLevel::Trace => 5,
+});
+codes!(Rotation {
+    Rotation::None => 0,
+    Rotation::Rotate90 => 1,
+    Rotation::Rotate180 => 2,
+    Rotation::Rotate270 => 3,
+});
+codes!(SeekMode {
+    SeekMode::Accurate => 0,
+    SeekMode::Fast => 1,
+});
+codes!(Dither {
+    Dither::Auto => 0,
+    Dither::None => 1,
+    Dither::Bayer => 2,
+    Dither::ErrorDiffusion => 3,
+});
+codes!(OutputFormat {
+    OutputFormat::Rgb24 => 0,
+    OutputFormat::Rgba => 1,
+    OutputFormat::Bgra => 2,
+    OutputFormat::Gray8 => 3,
+    OutputFormat::Yuv420p => 4,
+    OutputFormat::Bgr24 => 5,
+});
+codes!(OutputEncoding {
+    OutputEncoding::Raw => 0,
+    OutputEncoding::Png => 1,
+    OutputEncoding::Jpeg => 2,
+    OutputEncoding::WebP => 3,
+});
+codes!(FieldSelection {
+    FieldSelection::Frame => 0,
+    FieldSelection::Top => 1,
+    FieldSelection::Bottom => 2,
+});
+
+// 小端序写出
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(u8::from(value));
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn option<T>(&mut self, value: &Option<T>, write: impl FnOnce(&mut Self, &T)) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                write(self, value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn pairs(&mut self, pairs: &[(String, String)]) {
+        self.u32(pairs.len() as u32);
+        for (key, value) in pairs {
+            self.str(key);
+            self.str(value);
+        }
+    }
+}
+
+// 按 `Writer` 的格式读取，数据不足或取值无效时返回错误
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VideoError> {
+        if self.remaining() < len {
+            return Err(corrupt("数据被截断"));
+        }
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], VideoError> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, VideoError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, VideoError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt("无效的布尔值")),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, VideoError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, VideoError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, VideoError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn string(&mut self) -> Result<String, VideoError> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| corrupt("字符串不是有效的UTF-8"))
+    }
+
+    fn code<T: Code>(&mut self) -> Result<T, VideoError> {
+        let code = self.u8()?;
+        T::from_code(code).ok_or(corrupt("未知的枚举值"))
+    }
+
+    fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, VideoError>,
+    ) -> Result<Option<T>, VideoError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => read(self).map(Some),
+            _ => Err(corrupt("无效的可选值标记")),
+        }
+    }
+
+    fn pairs(&mut self) -> Result<Vec<(String, String)>, VideoError> {
+        let count = self.u32()? as usize;
+        // 每对至少占8字节，按剩余数据限制预分配，损坏的数量不会导致巨大的分配
+        let mut pairs = Vec::with_capacity(count.min(self.remaining() / 8));
+        for _ in 0..count {
+            pairs.push((self.string()?, self.string()?));
+        }
+        Ok(pairs)
+    }
+}
+
+fn corrupt(reason: &str) -> VideoError {
+    VideoError::new(
+        VideoErrorCode::InvalidInput,
+        Some(format!("任务数据已损坏: {}", reason)),
+    )
+}
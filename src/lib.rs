@@ -1,8 +1,63 @@
 // 公开模块供测试使用
+pub mod activity;
+pub mod audio;
+pub mod audio_qc;
+pub mod av_sync;
+pub mod best_frame;
+pub mod blurhash;
+pub mod buffer_pool;
+pub mod build_info;
+pub mod busy_regions;
+pub mod clip_bounds;
+pub mod comparison;
+pub mod concat;
+pub mod config;
+pub mod custom_io;
+mod encode;
 pub mod error;
 pub mod ffmpeg_init;
+pub mod frame_hash;
+pub mod frame_match;
+pub mod frozen;
+pub mod histogram;
+pub mod intro_credits;
+pub mod job;
+pub mod log_capture;
+pub mod loudness;
+pub mod memory_watch;
+pub mod onset;
+#[cfg(all(feature = "opencv", not(target_arch = "wasm32")))]
+pub mod opencv_interop;
+pub mod options;
+pub mod palette;
+pub mod preview;
+pub mod qc;
+pub mod remux;
+#[cfg(all(feature = "sandbox", unix))]
+pub mod sandbox;
+pub mod scene;
+pub mod segment_gaps;
+pub mod self_test;
+pub mod session;
+pub mod slide_change;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod snapshot_service;
+pub mod sprite_sheet;
+pub mod storyboard;
+pub mod temp_storage;
+pub mod test_pattern;
+pub mod thumbhash;
+pub mod transform;
+pub mod validation;
 pub mod video_processor;
 mod wasm_interface;
 
 // 导出公开的 API
-pub use wasm_interface::extract_video_frame;
+pub use wasm_interface::{
+    analyze_frame, clip_preview, compute_blur_hash, compute_thumb_hash, extract_audio,
+    extract_palette, extract_video_frame, extract_video_frame_as, extract_video_frame_at_percent,
+    extract_video_frame_encoded, extract_video_frame_from_source, extract_video_frame_scaled,
+    extract_video_frames, frame_phash, generate_gif_preview, generate_poster, generate_preview,
+    generate_sprite_sheet, measure_loudness, probe_video, select_best_frame,
+    set_memory_growth_handler,
+};
@@ -0,0 +1,171 @@
+// log_capture.rs
+// 按次捕获FFmpeg日志，用于排查单个异常文件
+//
+// 全局日志级别在 `ffmpeg_init` 中按编译配置设置，生产环境只输出错误。
+// 这里安装一个自定义的日志回调：平时原样转交给FFmpeg默认回调，
+// 只有在某次调用开启捕获时，才把不高于指定级别的日志收集起来随错误一起返回。
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::ptr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, Once};
+
+use ffmpeg::ffi;
+use ffmpeg::util::log::Level;
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+// va_list 在不同平台上由 bindgen 生成的类型不同
+#[cfg(all(target_arch = "x86_64", not(target_os = "windows")))]
+type VaList = *mut ffi::__va_list_tag;
+#[cfg(not(all(target_arch = "x86_64", not(target_os = "windows"))))]
+type VaList = ffi::va_list;
+
+// 未开启捕获时的哨兵值
+const CAPTURE_OFF: i32 = i32::MIN;
+
+// 单条日志的最大长度，超出部分会被FFmpeg截断
+const LINE_SIZE: usize = 1024;
+
+// 单次捕获最多保留的日志行数，防止调试级别日志占满内存
+const MAX_LINES: usize = 4096;
+
+static CAPTURE_LEVEL: AtomicI32 = AtomicI32::new(CAPTURE_OFF);
+static CAPTURED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// 日志捕获级别
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Error,
+    Warning,
+    Info,
+    Verbose,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => Level::Error,
+            LogLevel::Warning => Level::Warning,
+            LogLevel::Info => Level::Info,
+            LogLevel::Verbose => Level::Verbose,
+            LogLevel::Debug => Level::Debug,
+            LogLevel::Trace => Level::Trace,
+        }
+    }
+}
+
+impl LogLevel {
+    fn av_level(self) -> c_int {
+        Level::from(self).into()
+    }
+}
+
+/// 一次日志捕获
+///
+/// 创建时开始收集日志，调用 `finish` 取回收集到的内容，
+/// 提前丢弃时自动结束捕获。
+///
+/// FFmpeg的日志回调是进程级别的，解码线程产生的日志也会被收集；
+/// 同一时刻有多个捕获时，它们的日志会混在一起。
+pub struct LogCapture {
+    active: bool,
+}
+
+impl LogCapture {
+    /// 开始捕获不高于 `level` 的日志
+    pub fn start(level: LogLevel) -> Self {
+        install_callback();
+
+        if let Ok(mut lines) = CAPTURED.lock() {
+            lines.clear();
+        }
+        CAPTURE_LEVEL.store(level.av_level(), Ordering::SeqCst);
+
+        Self { active: true }
+    }
+
+    /// 结束捕获并返回收集到的日志
+    pub fn finish(mut self) -> Vec<String> {
+        self.stop();
+        match CAPTURED.lock() {
+            Ok(mut lines) => std::mem::take(&mut *lines),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn stop(&mut self) {
+        if self.active {
+            CAPTURE_LEVEL.store(CAPTURE_OFF, Ordering::SeqCst);
+            self.active = false;
+        }
+    }
+}
+
+impl Drop for LogCapture {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 通过FFmpeg的日志系统输出一条本库自己的警告
+///
+/// 与FFmpeg自身的日志走同一条路径，开启捕获时会一起被收集。
+pub(crate) fn warn(message: &str) {
+    let message = match CString::new(message) {
+        Ok(message) => message,
+        Err(_) => return,
+    };
+    unsafe {
+        ffi::av_log(
+            ptr::null_mut(),
+            Level::Warning.into(),
+            c"%s\n".as_ptr(),
+            message.as_ptr(),
+        );
+    }
+}
+
+// 安装自定义日志回调，只需要一次
+fn install_callback() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| unsafe {
+        ffi::av_log_set_callback(Some(log_callback));
+    });
+}
+
+unsafe extern "C" fn log_callback(avcl: *mut c_void, level: c_int, fmt: *const c_char, vl: VaList) {
+    let capture_level = CAPTURE_LEVEL.load(Ordering::Relaxed);
+    if capture_level == CAPTURE_OFF || level > capture_level {
+        ffi::av_log_default_callback(avcl, level, fmt, vl);
+        return;
+    }
+
+    // va_list 只能使用一次，所以被捕获的日志由我们自己格式化，
+    // 并在满足全局日志级别时自行输出到 stderr
+    let mut buf = [0 as c_char; LINE_SIZE];
+    let mut print_prefix: c_int = 1;
+    ffi::av_log_format_line2(
+        avcl,
+        level,
+        fmt,
+        vl,
+        buf.as_mut_ptr(),
+        LINE_SIZE as c_int,
+        &mut print_prefix,
+    );
+    let line = CStr::from_ptr(buf.as_ptr()).to_string_lossy();
+
+    if level <= ffi::av_log_get_level() {
+        eprint!("{}", line);
+    }
+
+    if let Ok(mut lines) = CAPTURED.lock() {
+        if lines.len() < MAX_LINES {
+            lines.push(line.trim_end().to_string());
+        }
+    }
+}
@@ -0,0 +1,438 @@
+// loudness.rs
+// EBU R128响度测量
+//
+// 上传前给出音量归一化的建议需要知道整段音频的响度。这里按ITU-R BS.1770-4和EBU R128/Tech 3342
+// 解码主音频流的所有采样：K计权滤波后按100ms子块累计各声道加权的均方值，
+// 由400ms的瞬时块门限计算综合响度，由3s的短时响度计算响度范围，
+// 并把采样过采样后得到真峰值，结果与 `ffmpeg -af ebur128=peak=true` 基本一致。
+
+use std::f64::consts::PI;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::audio;
+use crate::config;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::onset;
+use crate::video_processor;
+
+use ffmpeg::ffi::{
+    AVChannelOrder, AV_CH_BACK_LEFT, AV_CH_BACK_RIGHT, AV_CH_LOW_FREQUENCY, AV_CH_LOW_FREQUENCY_2,
+    AV_CH_SIDE_LEFT, AV_CH_SIDE_RIGHT,
+};
+use ffmpeg::{format::Sample, util::frame::audio::Audio};
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+// 瞬时响度的块长为4个100ms子块，短时响度为30个
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+
+// 绝对门限（LUFS）
+const ABSOLUTE_GATE: f64 = -70.0;
+
+// 综合响度和响度范围的相对门限（LU）
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+
+// 响度范围取门限后短时响度分布的这两个百分位之差
+const RANGE_LOW_PERCENTILE: f64 = 0.10;
+const RANGE_HIGH_PERCENTILE: f64 = 0.95;
+
+// 真峰值插值滤波器每个相位的抽头数
+const TAPS_PER_PHASE: usize = 12;
+
+/// 响度测量结果
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// 综合响度（LUFS），静音或短于400ms时为负无穷
+    pub integrated: f64,
+    /// 响度范围（LU），动态越大数值越大
+    pub range: f64,
+    /// 真峰值（dBTP），没有声音时为负无穷
+    pub true_peak: f64,
+}
+
+#[wasm_bindgen]
+impl Loudness {
+    /// 归一化到目标响度需要的增益（dB）
+    ///
+    /// 提升音量会让真峰值超过 `max_true_peak` 时，只提升到真峰值恰好等于该值；
+    /// 常用的目标为 -23 LUFS（EBU R128广播）或 -14 LUFS（流媒体平台），真峰值上限为 -1 dBTP。
+    /// 综合响度为负无穷时返回0。
+    #[wasm_bindgen(js_name = normalizationGain)]
+    pub fn normalization_gain(&self, target_lufs: f64, max_true_peak: f64) -> f64 {
+        if !self.integrated.is_finite() {
+            return 0.0;
+        }
+        (target_lufs - self.integrated).min(max_true_peak - self.true_peak)
+    }
+}
+
+/// 测量主音频流的响度
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<Loudness, VideoError>` - 成功时返回综合响度、响度范围和真峰值，没有音频流时返回错误
+pub fn measure_loudness<P: AsRef<Path>>(input_path: P) -> Result<Loudness, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let mut meter = LoudnessMeter::default();
+    video_processor::for_each_audio_frame(
+        input_path.as_ref(),
+        &config::default_options(),
+        0.0,
+        |frame, _| {
+            push_frame(&mut meter, frame);
+            Ok(ControlFlow::Continue(()))
+        },
+    )?;
+
+    Ok(meter.finish())
+}
+
+// 把一帧音频的采样逐个送入测量器
+// 平面格式只有第一个平面的 linesize 有效，因此直接按 extended_data 读取
+fn push_frame(meter: &mut LoudnessMeter, frame: &Audio) {
+    let format = frame.format();
+    let channels = frame.channels() as usize;
+    let bytes = format.bytes();
+    if channels == 0 || bytes == 0 || frame.rate() == 0 || format == Sample::None {
+        return;
+    }
+    meter.configure(frame.rate(), channel_weights(frame, channels));
+
+    let mut samples = vec![0.0; channels];
+    // 安全性：解码器保证每个声道都有 `samples` 个该格式的采样
+    unsafe {
+        let data = (*frame.as_ptr()).extended_data;
+        for i in 0..frame.samples() {
+            for (ch, sample) in samples.iter_mut().enumerate() {
+                let (plane, index) = if format.is_planar() {
+                    (ch, i)
+                } else {
+                    (0, i * channels + ch)
+                };
+                let ptr = (*data.add(plane)).add(index * bytes);
+                *sample = f64::from(onset::read_sample(format, ptr));
+            }
+            meter.push(&samples);
+        }
+    }
+}
+
+// 各声道在响度中的权重：低频效果声道不计入，环绕声道为1.41，其余为1
+fn channel_weights(frame: &Audio, channels: usize) -> Vec<f64> {
+    let layout = audio::source_layout(frame);
+    // 只有原生顺序的布局才能按掩码确定每个声道的位置
+    if layout.0.order != AVChannelOrder::AV_CHANNEL_ORDER_NATIVE {
+        return vec![1.0; channels];
+    }
+
+    // 原生顺序下声道按掩码中置位的顺序排列
+    let mut mask = layout.bits();
+    (0..channels)
+        .map(|_| {
+            let bit = mask & mask.wrapping_neg();
+            mask &= !bit;
+            match bit {
+                AV_CH_LOW_FREQUENCY | AV_CH_LOW_FREQUENCY_2 => 0.0,
+                AV_CH_SIDE_LEFT | AV_CH_SIDE_RIGHT | AV_CH_BACK_LEFT | AV_CH_BACK_RIGHT => 1.41,
+                _ => 1.0,
+            }
+        })
+        .collect()
+}
+
+// 按100ms子块累计K计权后的加权均方值，同时跟踪真峰值
+#[derive(Default)]
+struct LoudnessMeter {
+    rate: u32,
+    weights: Vec<f64>,
+    // 每个声道的K计权滤波器（高架滤波和高通滤波两级）
+    filters: Vec<[Biquad; 2]>,
+    // 每个子块的采样数
+    block_len: usize,
+    // 当前子块中每个声道的平方和
+    sums: Vec<f64>,
+    count: usize,
+    // 每个完整子块的加权均方值
+    blocks: Vec<f64>,
+    interpolator: Interpolator,
+    // 每个声道最近的输入采样，第0个为最新的
+    history: Vec<Vec<f64>>,
+    peak: f64,
+}
+
+impl LoudnessMeter {
+    // 采样率或声道布局变化时重新设置滤波器，已经完成的子块保留
+    fn configure(&mut self, rate: u32, weights: Vec<f64>) {
+        if rate == self.rate && weights == self.weights {
+            return;
+        }
+        let channels = weights.len();
+        self.filters = vec![k_weighting(f64::from(rate)); channels];
+        self.block_len = (rate as usize).div_ceil(10);
+        self.sums = vec![0.0; channels];
+        self.count = 0;
+        self.interpolator = Interpolator::new(rate);
+        self.history = vec![vec![0.0; self.interpolator.history_len()]; channels];
+        self.rate = rate;
+        self.weights = weights;
+    }
+
+    // 送入一个采样帧（每个声道一个采样）
+    fn push(&mut self, samples: &[f64]) {
+        for (((&sample, filter), sum), history) in samples
+            .iter()
+            .zip(&mut self.filters)
+            .zip(&mut self.sums)
+            .zip(&mut self.history)
+        {
+            let [shelf, high_pass] = filter;
+            let weighted = high_pass.process(shelf.process(sample));
+            *sum += weighted * weighted;
+
+            history.rotate_right(1);
+            history[0] = sample;
+            self.peak = self.peak.max(self.interpolator.peak(history));
+        }
+
+        self.count += 1;
+        if self.count == self.block_len {
+            let power = self
+                .sums
+                .iter()
+                .zip(&self.weights)
+                .map(|(sum, weight)| weight * sum / self.block_len as f64)
+                .sum();
+            self.blocks.push(power);
+            self.sums.iter_mut().for_each(|sum| *sum = 0.0);
+            self.count = 0;
+        }
+    }
+
+    fn finish(self) -> Loudness {
+        let momentary = window_powers(&self.blocks, MOMENTARY_BLOCKS);
+        let short_term = window_powers(&self.blocks, SHORT_TERM_BLOCKS);
+        Loudness {
+            integrated: gated_power(&momentary, INTEGRATED_RELATIVE_GATE)
+                .map_or(f64::NEG_INFINITY, |(power, _)| loudness(power)),
+            range: loudness_range(&short_term),
+            true_peak: 20.0 * self.peak.log10(),
+        }
+    }
+}
+
+// 连续若干个子块组成的重叠窗口的均方值，窗口每次前进一个子块
+fn window_powers(blocks: &[f64], length: usize) -> Vec<f64> {
+    blocks
+        .windows(length)
+        .map(|window| window.iter().sum::<f64>() / length as f64)
+        .collect()
+}
+
+// 经过绝对门限和相对门限后的平均均方值及通过门限的窗口；没有窗口通过时返回None
+fn gated_power(powers: &[f64], relative_gate: f64) -> Option<(f64, Vec<f64>)> {
+    let absolute: Vec<f64> = powers
+        .iter()
+        .copied()
+        .filter(|&power| loudness(power) > ABSOLUTE_GATE)
+        .collect();
+    if absolute.is_empty() {
+        return None;
+    }
+    let threshold = loudness(mean(&absolute)) + relative_gate;
+    let gated: Vec<f64> = absolute
+        .into_iter()
+        .filter(|&power| loudness(power) > threshold)
+        .collect();
+    (!gated.is_empty()).then(|| (mean(&gated), gated))
+}
+
+// 门限后短时响度分布的第95和第10百分位之差（LU）
+fn loudness_range(short_term: &[f64]) -> f64 {
+    let mut levels: Vec<f64> = match gated_power(short_term, RANGE_RELATIVE_GATE) {
+        Some((_, gated)) => gated.into_iter().map(loudness).collect(),
+        None => return 0.0,
+    };
+    levels.sort_by(f64::total_cmp);
+    let percentile = |p: f64| levels[((levels.len() - 1) as f64 * p).round() as usize];
+    percentile(RANGE_HIGH_PERCENTILE) - percentile(RANGE_LOW_PERCENTILE)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+// 加权均方值对应的响度（LUFS）
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+// 直接II型转置结构的二阶IIR滤波器，分母的首项为1
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+// BS.1770的K计权滤波器，按模拟原型换算到任意采样率，48kHz时与标准给出的系数一致
+fn k_weighting(rate: f64) -> [Biquad; 2] {
+    // 模拟头部声学效应的高架滤波
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    // RLB高通滤波
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+// 真峰值的多相插值滤波器：96kHz以下4倍、192kHz以下2倍过采样
+#[derive(Default)]
+struct Interpolator {
+    // 每个相位的抽头，第j个抽头作用于倒数第j个输入采样
+    phases: Vec<Vec<f64>>,
+}
+
+impl Interpolator {
+    fn new(rate: u32) -> Self {
+        let factor = match rate {
+            0..96_000 => 4,
+            96_000..192_000 => 2,
+            _ => 1,
+        };
+        // 以原采样率的奈奎斯特频率为截止频率的加汉宁窗sinc，长度为奇数，
+        // 中心抽头落在原采样点上，相位0输出原采样
+        let length = factor * TAPS_PER_PHASE + 1;
+        let center = (length / 2) as f64;
+        let taps: Vec<f64> = (0..length)
+            .map(|n| {
+                let t = (n as f64 - center) / factor as f64;
+                let sinc = if t == 0.0 {
+                    1.0
+                } else {
+                    (PI * t).sin() / (PI * t)
+                };
+                let window = 0.5 - 0.5 * (2.0 * PI * n as f64 / (length - 1) as f64).cos();
+                sinc * window
+            })
+            .collect();
+        let phases = (0..factor)
+            .map(|phase| taps.iter().skip(phase).step_by(factor).copied().collect())
+            .collect();
+        Self { phases }
+    }
+
+    // 需要保留的输入采样数
+    fn history_len(&self) -> usize {
+        self.phases.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    // 最新的输入采样与之前的采样之间各插值点的最大幅度
+    fn peak(&self, history: &[f64]) -> f64 {
+        self.phases
+            .iter()
+            .map(|taps| {
+                taps.iter()
+                    .zip(history)
+                    .map(|(tap, sample)| tap * sample)
+                    .sum::<f64>()
+                    .abs()
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以48kHz单声道测量一段采样
+    fn measure(samples: impl IntoIterator<Item = f64>) -> Loudness {
+        let mut meter = LoudnessMeter::default();
+        meter.configure(48000, vec![1.0]);
+        for sample in samples {
+            meter.push(&[sample]);
+        }
+        meter.finish()
+    }
+
+    // 测试48kHz时K计权滤波器的系数与BS.1770给出的一致
+    #[test]
+    fn test_k_weighting() {
+        let [shelf, high_pass] = k_weighting(48000.0);
+        let expected = [
+            (
+                [1.53512485958697, -2.69169618940638, 1.19839281085285],
+                [-1.69065929318241, 0.73248077421585],
+            ),
+            ([1.0, -2.0, 1.0], [-1.99004745483398, 0.99007225036621]),
+        ];
+        for (filter, (b, a)) in [shelf, high_pass].iter().zip(expected) {
+            for (actual, expected) in filter.b.iter().chain(&filter.a).zip(b.iter().chain(&a)) {
+                assert!((actual - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    // 测试满幅1kHz正弦波、响度阶跃和静音的测量结果
+    #[test]
+    fn test_loudness_meter() {
+        let sine = |seconds: usize, level: f64| {
+            let amplitude = 10f64.powf(level / 20.0);
+            (0..seconds * 48000)
+                .map(move |i| amplitude * (2.0 * PI * 1000.0 * i as f64 / 48000.0).sin())
+        };
+
+        // 满幅正弦波为 -3.01 LUFS，真峰值为 0 dBTP
+        let result = measure(sine(5, 0.0));
+        assert!((result.integrated + 3.01).abs() < 0.05, "{:?}", result);
+        assert!(result.true_peak.abs() < 0.1, "{:?}", result);
+        assert!(result.range.abs() < 0.1, "{:?}", result);
+
+        // -20 dBFS和-30 dBFS各10秒，响度范围为10 LU
+        let result = measure(sine(10, -20.0).chain(sine(10, -30.0)));
+        assert!((result.range - 10.0).abs() < 0.1, "{:?}", result);
+
+        let result = measure(std::iter::repeat(0.0).take(48000));
+        assert_eq!(result.integrated, f64::NEG_INFINITY);
+        assert_eq!(result.true_peak, f64::NEG_INFINITY);
+        assert_eq!(result.range, 0.0);
+    }
+}
@@ -0,0 +1,212 @@
+// memory_watch.rs
+// WebAssembly线性内存增长的回调
+//
+// 线性内存只能增长不能缩小，抽取一张大帧就可能让整个页面的内存占用永久上升。
+// 宿主可以设置若干阈值，抽帧、解码、拼图、动画编码和音频提取在分配大块内存之前预估增长之后的大小，即将越过阈值时
+// 先通知宿主，由宿主配合自己的内存压力处理决定是否继续；拒绝时这次抽帧以
+// `MemoryLimit` 错误结束，不会分配。原生平台没有线性内存，回调不会被调用。
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::{VideoError, VideoErrorCode};
+
+/// 一次即将发生的内存增长
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryGrowth {
+    /// 当前线性内存的大小（字节）
+    pub current_bytes: usize,
+    /// 预估增长之后的大小（字节），所需的临时内存先用Rust堆上已有的空闲部分，不够的部分按新增长的内存计算
+    pub projected_bytes: usize,
+    /// 即将越过的阈值（字节）
+    pub threshold: usize,
+}
+
+// 已注册的阈值和回调
+struct Watch {
+    // 从小到大排列
+    thresholds: Vec<usize>,
+    hook: Box<dyn Fn(&MemoryGrowth) -> bool>,
+}
+
+// WASM是单线程的；回调可能持有不能跨线程的JavaScript对象，因此按线程保存
+thread_local! {
+    static WATCH: RefCell<Option<Rc<Watch>>> = const { RefCell::new(None) };
+}
+
+// WebAssembly内存页的大小（字节）
+#[cfg(target_arch = "wasm32")]
+const WASM_PAGE_SIZE: usize = 65536;
+
+// 记录Rust堆上正在使用的字节数和曾经达到的最大值；分配器不会把释放的内存还给线性内存，
+// 两者之差就是分配器已经取得、目前空闲、可以不增长内存直接复用的部分（不考虑碎片）
+#[cfg(target_arch = "wasm32")]
+mod heap {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    pub(super) struct CountingAllocator;
+
+    static IN_USE: AtomicUsize = AtomicUsize::new(0);
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    fn grow(bytes: usize) {
+        let in_use = IN_USE.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        PEAK.fetch_max(in_use, Ordering::Relaxed);
+    }
+
+    fn shrink(bytes: usize) {
+        IN_USE.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    // 安全性：只转发给系统分配器，并在成功时更新计数
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                grow(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                grow(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            shrink(layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                shrink(layout.size());
+                grow(new_size);
+            }
+            new_ptr
+        }
+    }
+
+    // Rust堆上空闲的字节数
+    pub(super) fn free_bytes() -> usize {
+        PEAK.load(Ordering::Relaxed)
+            .saturating_sub(IN_USE.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOCATOR: heap::CountingAllocator = heap::CountingAllocator;
+
+/// 设置内存增长阈值和回调，替换之前的设置
+///
+/// 抽帧预估线性内存将越过某个阈值时调用 `hook`，返回 `false` 时拒绝这次抽帧。
+/// 当前大小已经超过的阈值不再触发。
+///
+/// # 参数
+/// * `thresholds` - 阈值（字节），顺序任意
+/// * `hook` - 回调，返回是否允许继续
+pub fn set_memory_growth_hook<F>(thresholds: &[usize], hook: F)
+where
+    F: Fn(&MemoryGrowth) -> bool + 'static,
+{
+    let mut thresholds = thresholds.to_vec();
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    let watch = Watch {
+        thresholds,
+        hook: Box::new(hook),
+    };
+    WATCH.with(|current| *current.borrow_mut() = Some(Rc::new(watch)));
+}
+
+/// 移除内存增长回调
+pub fn clear_memory_growth_hook() {
+    WATCH.with(|current| *current.borrow_mut() = None);
+}
+
+// 即将分配 `bytes` 字节的临时内存；越过阈值且宿主拒绝时返回错误
+pub(crate) fn reserve(bytes: usize) -> Result<(), VideoError> {
+    let current = match memory_bytes() {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+    // 先取出回调再调用，回调中重新设置或移除回调不会冲突
+    let watch = match WATCH.with(|watch| watch.borrow().clone()) {
+        Some(watch) => watch,
+        None => return Ok(()),
+    };
+
+    let projected = projected_bytes(current, bytes, free_heap_bytes());
+    for &threshold in &watch.thresholds {
+        if threshold <= current {
+            continue;
+        }
+        if threshold >= projected {
+            break;
+        }
+
+        let growth = MemoryGrowth {
+            current_bytes: current,
+            projected_bytes: projected,
+            threshold,
+        };
+        if !(watch.hook)(&growth) {
+            return Err(VideoError::new(
+                VideoErrorCode::MemoryLimit,
+                Some(format!(
+                    "宿主拒绝内存增长: 预计从 {} 字节增长到 {} 字节，越过阈值 {} 字节",
+                    current, projected, threshold
+                )),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// 分配 `bytes` 字节之后线性内存的预估大小：先用堆上空闲的 `free` 字节，不够的部分需要增长
+fn projected_bytes(current: usize, bytes: usize, free: usize) -> usize {
+    current.saturating_add(bytes.saturating_sub(free))
+}
+
+// 当前线性内存的大小（字节），原生平台为 `None`
+#[cfg(target_arch = "wasm32")]
+fn memory_bytes() -> Option<usize> {
+    Some(core::arch::wasm32::memory_size(0) * WASM_PAGE_SIZE)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn memory_bytes() -> Option<usize> {
+    None
+}
+
+// Rust堆上已经取得但空闲的字节数，原生平台不需要
+#[cfg(target_arch = "wasm32")]
+fn free_heap_bytes() -> usize {
+    heap::free_bytes()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn free_heap_bytes() -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试预估大小：堆上的空闲部分足够时不增长，不够时只计入差额
+    #[test]
+    fn test_projected_bytes() {
+        assert_eq!(projected_bytes(1000, 100, 200), 1000);
+        assert_eq!(projected_bytes(1000, 300, 200), 1100);
+        assert_eq!(projected_bytes(1000, 300, 0), 1300);
+        assert_eq!(projected_bytes(usize::MAX, 1, 0), usize::MAX);
+    }
+}
@@ -0,0 +1,180 @@
+// onset.rs
+// 音频起音（节拍）检测
+//
+// 解码最佳音频流，混合为单声道后按固定长度分块计算能量，
+// 在对数能量的上升幅度（能量通量）上用自适应阈值挑选峰值，
+// 得到鼓点、重音等音乐上有意义的时刻，供预览片段和缩略图对齐使用。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::ptr;
+
+use crate::config;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::video_processor;
+
+use ffmpeg::{format::Sample, util::frame::audio::Audio};
+use ffmpeg_next as ffmpeg;
+
+// 每个能量块的采样数
+const HOP_SAMPLES: usize = 512;
+
+// 计算自适应阈值时向前后各看多少个块
+const THRESHOLD_RADIUS: usize = 8;
+
+// 能量通量超过局部平均值的倍数才算起音
+const THRESHOLD_RATIO: f32 = 1.5;
+
+// 避免静音段的微小波动被当成起音
+const THRESHOLD_FLOOR: f32 = 0.05;
+
+// 两个起音之间的最小间隔（秒）
+const MIN_ONSET_GAP: f64 = 0.1;
+
+/// 检测音频中的起音时刻
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<Vec<f64>, VideoError>` - 成功时返回按时间排序的起音时刻（秒），没有音频流时返回错误
+pub fn detect_onsets<P: AsRef<Path>>(input_path: P) -> Result<Vec<f64>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let mut envelope = EnergyEnvelope::default();
+    video_processor::for_each_audio_frame(
+        input_path.as_ref(),
+        &config::default_options(),
+        0.0,
+        |frame, time| {
+            envelope.push_frame(frame, time);
+            Ok(ControlFlow::Continue(()))
+        },
+    )?;
+
+    Ok(envelope.onsets())
+}
+
+// 按块累计的单声道能量包络
+#[derive(Default)]
+struct EnergyEnvelope {
+    sample_rate: u32,
+    // 第一帧的起始时间（秒）
+    start: Option<f64>,
+    // 每个块的对数能量
+    energies: Vec<f32>,
+    // 当前未满的块
+    sum: f32,
+    count: usize,
+}
+
+impl EnergyEnvelope {
+    fn push_frame(&mut self, frame: &Audio, time: Option<f64>) {
+        if self.sample_rate == 0 {
+            self.sample_rate = frame.rate();
+        }
+        if self.start.is_none() {
+            self.start = Some(time.unwrap_or(0.0));
+        }
+
+        for sample in mono_samples(frame) {
+            self.sum += sample * sample;
+            self.count += 1;
+            if self.count == HOP_SAMPLES {
+                let energy = self.sum / HOP_SAMPLES as f32;
+                self.energies.push((energy + 1e-10).ln());
+                self.sum = 0.0;
+                self.count = 0;
+            }
+        }
+    }
+
+    // 在能量通量上挑选峰值
+    fn onsets(&self) -> Vec<f64> {
+        if self.sample_rate == 0 || self.energies.len() < 2 {
+            return Vec::new();
+        }
+
+        // 只保留能量上升的部分
+        let flux: Vec<f32> = std::iter::once(0.0)
+            .chain(
+                self.energies
+                    .windows(2)
+                    .map(|pair| (pair[1] - pair[0]).max(0.0)),
+            )
+            .collect();
+
+        let block_secs = HOP_SAMPLES as f64 / f64::from(self.sample_rate);
+        let start = self.start.unwrap_or(0.0);
+        let mut onsets: Vec<f64> = Vec::new();
+
+        for i in 1..flux.len().saturating_sub(1) {
+            let lo = i.saturating_sub(THRESHOLD_RADIUS);
+            let hi = (i + THRESHOLD_RADIUS + 1).min(flux.len());
+            let mean = flux[lo..hi].iter().sum::<f32>() / (hi - lo) as f32;
+            let threshold = mean * THRESHOLD_RATIO + THRESHOLD_FLOOR;
+
+            let is_peak = flux[i] > threshold && flux[i] >= flux[i - 1] && flux[i] > flux[i + 1];
+            if !is_peak {
+                continue;
+            }
+
+            let time = start + i as f64 * block_secs;
+            if onsets
+                .last()
+                .is_none_or(|last| time - last >= MIN_ONSET_GAP)
+            {
+                onsets.push(time);
+            }
+        }
+
+        onsets
+    }
+}
+
+// 把一帧音频混合为单声道浮点采样
+// 平面格式只有第一个平面的 linesize 有效，因此直接按 extended_data 读取
+fn mono_samples(frame: &Audio) -> Vec<f32> {
+    let format = frame.format();
+    let channels = frame.channels() as usize;
+    let samples = frame.samples();
+    let bytes = format.bytes();
+    if channels == 0 || bytes == 0 || format == Sample::None {
+        return Vec::new();
+    }
+
+    let mut mono = Vec::with_capacity(samples);
+    // 安全性：解码器保证每个声道都有 `samples` 个该格式的采样
+    unsafe {
+        let data = (*frame.as_ptr()).extended_data;
+        for i in 0..samples {
+            let mut sum = 0.0;
+            for ch in 0..channels {
+                let (plane, index) = if format.is_planar() {
+                    (ch, i)
+                } else {
+                    (0, i * channels + ch)
+                };
+                let ptr = (*data.add(plane)).add(index * bytes);
+                sum += read_sample(format, ptr);
+            }
+            mono.push(sum / channels as f32);
+        }
+    }
+    mono
+}
+
+// 读取一个采样并归一化到 [-1, 1]
+pub(crate) unsafe fn read_sample(format: Sample, data: *const u8) -> f32 {
+    match format {
+        Sample::U8(_) => (f32::from(*data) - 128.0) / 128.0,
+        Sample::I16(_) => f32::from(ptr::read_unaligned(data as *const i16)) / 32768.0,
+        Sample::I32(_) => ptr::read_unaligned(data as *const i32) as f32 / 2_147_483_648.0,
+        Sample::I64(_) => ptr::read_unaligned(data as *const i64) as f32 / 9.223_372e18,
+        Sample::F32(_) => ptr::read_unaligned(data as *const f32),
+        Sample::F64(_) => ptr::read_unaligned(data as *const f64) as f32,
+        Sample::None => 0.0,
+    }
+}
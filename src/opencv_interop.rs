@@ -0,0 +1,178 @@
+// opencv_interop.rs
+// 以OpenCV的 Mat 返回提取的帧（仅原生平台，需要启用 `opencv` 特性）
+//
+// 计算机视觉的用户拿到 RgbFrame 之后，每个项目都要自己写一遍转换：选对 CV_8UC3/CV_8UC4、
+// 处理行尾填充的步长、把RGB交换成OpenCV习惯的BGR，写错了也只会得到错位或偏色的图像。
+// 这里按输出格式和行对齐方式构造类型和步长都正确的 Mat，默认直接输出BGR24，不需要再交换通道。
+
+use std::ffi::c_void;
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::options::{ExtractOptions, OutputFormat, Position};
+use crate::video_processor::{self, RgbFrame};
+
+use opencv::core::{Mat, MatTraitConst, CV_8UC1, CV_8UC3, CV_8UC4};
+
+/// 以BGR24格式提取指定时间点的帧，返回 `CV_8UC3` 的 Mat，其他选项使用全局默认值
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `time_sec` - 要提取的帧的时间点（秒）
+///
+/// # 返回
+/// * `Result<Mat, VideoError>` - 成功时返回连续存储的BGR图像
+pub fn extract_frame_mat<P: AsRef<Path>>(input_path: P, time_sec: f64) -> Result<Mat, VideoError> {
+    let mut options = config::default_options();
+    options.output_format = OutputFormat::Bgr24;
+    extract_frame_mat_with_options(input_path, time_sec, &options)
+}
+
+/// 使用自定义选项提取帧并返回 Mat
+///
+/// 输出格式决定 Mat 的类型：Rgb24和Bgr24为 `CV_8UC3`，Rgba和Bgra为 `CV_8UC4`，Gray8为 `CV_8UC1`；
+/// 交给OpenCV的函数处理时通常应选择Bgr24。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 帧的位置，可以是秒数或百分比
+/// * `options` - 抽帧选项，输出编码、行布局和场选择由本函数决定，不支持Yuv420p
+///
+/// # 返回
+/// * `Result<Mat, VideoError>` - 成功时返回连续存储的图像
+pub fn extract_frame_mat_with_options<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<Mat, VideoError> {
+    let options = mat_options(options)?;
+    let frame = video_processor::extract_frame_with_options(input_path, position, &options)?;
+    frame_to_mat(&frame, options.output_format, options.output_row_alignment)
+}
+
+/// 使用自定义选项提取多个时间点的帧并返回 Mat
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `times` - 要提取的帧的时间点（秒）
+/// * `options` - 抽帧选项，与 `extract_frame_mat_with_options` 相同
+///
+/// # 返回
+/// * `Result<Vec<Mat>, VideoError>` - 成功时按时间点的顺序返回图像
+pub fn extract_frames_mat_with_options<P: AsRef<Path>>(
+    input_path: P,
+    times: &[f64],
+    options: &ExtractOptions,
+) -> Result<Vec<Mat>, VideoError> {
+    let options = mat_options(options)?;
+    video_processor::extract_frames_with_options(input_path, times, &options)?
+        .iter()
+        .map(|frame| frame_to_mat(frame, options.output_format, options.output_row_alignment))
+        .collect()
+}
+
+/// 把已经提取的原始帧复制为 Mat
+///
+/// # 参数
+/// * `frame` - 以原始像素输出、从第一行开始排列的帧
+/// * `format` - 提取时使用的输出格式，不支持Yuv420p
+/// * `row_alignment` - 提取时使用的 `ExtractOptions::output_row_alignment`
+///
+/// # 返回
+/// * `Result<Mat, VideoError>` - 成功时返回连续存储的图像；数据长度与尺寸不符（例如是编码后的图片）时返回错误
+pub fn frame_to_mat(
+    frame: &RgbFrame,
+    format: OutputFormat,
+    row_alignment: Option<u32>,
+) -> Result<Mat, VideoError> {
+    let mat_type = mat_type(format)?;
+    let step = format.row_stride(frame.width, row_alignment);
+    let expected = format.aligned_frame_bytes(frame.width, frame.height, row_alignment);
+    if frame.data.len() != expected {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "帧数据长度 {} 与 {}x{} 的 {:?} 格式不符",
+                frame.data.len(),
+                frame.width,
+                frame.height,
+                format
+            )),
+        ));
+    }
+
+    // 安全性：数据长度已经检查过，Mat 只在复制期间借用 `frame.data`，不会写入
+    let borrowed = unsafe {
+        Mat::new_rows_cols_with_data_unsafe(
+            frame.height as i32,
+            frame.width as i32,
+            mat_type,
+            frame.data.as_ptr() as *mut c_void,
+            step,
+        )
+    }
+    .map_err(opencv_error)?;
+    // 复制为连续存储的 Mat，去掉行尾填充，返回值不再依赖 `frame` 的生命周期
+    borrowed.try_clone().map_err(opencv_error)
+}
+
+// Mat 只能表示从第一行开始的原始像素
+fn mat_options(options: &ExtractOptions) -> Result<ExtractOptions, VideoError> {
+    mat_type(options.output_format)?;
+    Ok(options.for_analysis(options.output_format))
+}
+
+// 输出格式对应的 Mat 类型
+fn mat_type(format: OutputFormat) -> Result<i32, VideoError> {
+    match format {
+        OutputFormat::Rgb24 | OutputFormat::Bgr24 => Ok(CV_8UC3),
+        OutputFormat::Rgba | OutputFormat::Bgra => Ok(CV_8UC4),
+        OutputFormat::Gray8 => Ok(CV_8UC1),
+        OutputFormat::Yuv420p => Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("Mat 不支持平面YUV420P格式，请使用Bgr24或Gray8".to_string()),
+        )),
+    }
+}
+
+fn opencv_error(e: opencv::Error) -> VideoError {
+    VideoError::new(
+        VideoErrorCode::Unknown,
+        Some(format!("无法创建OpenCV Mat: {}", e)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video_processor::FrameInfo;
+    use opencv::prelude::*;
+
+    // 测试把带行尾填充的帧复制为连续存储的 Mat
+    #[test]
+    fn test_frame_to_mat() {
+        // 2x2的BGR24，每行6字节，按4字节对齐后补2个0
+        let frame = RgbFrame {
+            width: 2,
+            height: 2,
+            data: vec![1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12, 0, 0],
+            info: FrameInfo {
+                storage_width: 2,
+                storage_height: 2,
+                display_width: 2,
+                display_height: 2,
+            },
+            warnings: Vec::new(),
+        };
+        let mat = frame_to_mat(&frame, OutputFormat::Bgr24, Some(4)).unwrap();
+        assert_eq!((mat.rows(), mat.cols(), mat.typ()), (2, 2, CV_8UC3));
+        assert!(mat.is_continuous());
+        assert_eq!(mat.data_bytes().unwrap(), (1..=12).collect::<Vec<u8>>());
+
+        // 数据长度与对齐方式不符、不支持的格式时返回错误
+        let err = frame_to_mat(&frame, OutputFormat::Bgr24, None).unwrap_err();
+        assert_eq!(err.code(), VideoErrorCode::InvalidInput);
+        assert!(frame_to_mat(&frame, OutputFormat::Yuv420p, Some(4)).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+// options.rs
+// 抽帧选项，控制打开输入和解码时的行为
+
+use std::ffi::CStr;
+
+use crate::log_capture::LogLevel;
+use crate::transform::Transform;
+
+use ffmpeg::util::format::Pixel;
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+/// 抽帧位置
+///
+/// 可以直接用秒数构造：`Position::from(1.5)` 等价于 `Position::Seconds(1.5)`。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    /// 时间点（秒）
+    Seconds(f64),
+    /// 占视频时长的百分比（0.0 - 100.0），由库内部解析时长
+    Percent(f64),
+}
+
+impl From<f64> for Position {
+    fn from(time_sec: f64) -> Self {
+        Position::Seconds(time_sec)
+    }
+}
+
+/// 按时间点抽帧时的定位方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeekMode {
+    /// 从目标之前的关键帧向后解码，返回目标时间点正在显示的帧（时间戳不晚于目标的最后一帧）
+    #[default]
+    Accurate,
+    /// 返回目标之前最近的关键帧，只需要解码一帧，速度最快但时间不精确
+    Fast,
+}
+
+/// 降低位深（例如10位转8位输出）时使用的抖动方式
+///
+/// 10位HDR或SDR母版直接截断到8位时，平滑的渐变容易出现色带，抖动可以把误差打散。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dither {
+    /// 由FFmpeg根据输入和输出格式自动选择
+    #[default]
+    Auto,
+    /// 不抖动，直接截断
+    None,
+    /// 有序（Bayer）抖动，速度快，可能出现规则的纹理
+    Bayer,
+    /// 误差扩散抖动，渐变最平滑，速度较慢
+    ErrorDiffusion,
+}
+
+impl Dither {
+    // 对应的缩放器选项值（sws_dither），自动选择时返回 None
+    pub(crate) fn sws_name(self) -> Option<&'static CStr> {
+        match self {
+            Dither::Auto => None,
+            Dither::None => Some(c"none"),
+            Dither::Bayer => Some(c"bayer"),
+            Dither::ErrorDiffusion => Some(c"ed"),
+        }
+    }
+}
+
+/// 输出帧的像素格式
+///
+/// 除 `Yuv420p` 外都是单平面打包格式，逐行紧密排列。
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// 每像素三字节，依次为R、G、B
+    #[default]
+    Rgb24,
+    /// 每像素四字节，依次为R、G、B、A，可以直接放入Canvas的 `ImageData`
+    Rgba,
+    /// 每像素四字节，依次为B、G、R、A，DirectX（`DXGI_FORMAT_B8G8R8A8_UNORM`）等部分GPU管线使用这种顺序
+    Bgra,
+    /// 每像素一字节的灰度
+    Gray8,
+    /// 平面YUV 4:2:0，依次为完整尺寸的Y平面和宽高各减半（向上取整）的U、V平面，平面之间没有填充
+    Yuv420p,
+    /// 每像素三字节，依次为B、G、R，OpenCV的 `CV_8UC3` 和Windows GDI的24位位图使用这种顺序
+    Bgr24,
+}
+
+impl OutputFormat {
+    // 对应的FFmpeg像素格式
+    pub(crate) fn pixel(self) -> Pixel {
+        match self {
+            OutputFormat::Rgb24 => Pixel::RGB24,
+            OutputFormat::Rgba => Pixel::RGBA,
+            OutputFormat::Bgra => Pixel::BGRA,
+            OutputFormat::Gray8 => Pixel::GRAY8,
+            OutputFormat::Yuv420p => Pixel::YUV420P,
+            OutputFormat::Bgr24 => Pixel::BGR24,
+        }
+    }
+
+    // 打包格式的每像素字节数；YUV420P平均每像素1.5字节，按2字节保守估算输出预算
+    pub(crate) fn bytes_per_pixel(self) -> usize {
+        match self {
+            OutputFormat::Rgb24 | OutputFormat::Bgr24 => 3,
+            OutputFormat::Rgba | OutputFormat::Bgra => 4,
+            OutputFormat::Gray8 => 1,
+            OutputFormat::Yuv420p => 2,
+        }
+    }
+
+    /// 给定尺寸的一帧输出数据的字节数
+    pub fn frame_bytes(self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+        match self {
+            OutputFormat::Yuv420p => width * height + 2 * width.div_ceil(2) * height.div_ceil(2),
+            format => width * height * format.bytes_per_pixel(),
+        }
+    }
+
+    /// 每行像素数据加上行尾填充之后的字节数；YUV420P为Y平面的一行
+    ///
+    /// `alignment` 与 `ExtractOptions::output_row_alignment` 相同，None 表示不填充。
+    pub fn row_stride(self, width: u32, alignment: Option<u32>) -> usize {
+        let (row_bytes, _) = self.planes(width, 1)[0];
+        align(row_bytes, alignment)
+    }
+
+    /// 给定尺寸和行对齐方式的一帧输出数据的字节数
+    pub fn aligned_frame_bytes(self, width: u32, height: u32, alignment: Option<u32>) -> usize {
+        self.planes(width, height)
+            .iter()
+            .map(|&(row_bytes, rows)| align(row_bytes, alignment) * rows)
+            .sum()
+    }
+
+    // 各平面每行的有效字节数和行数；打包格式只有一个平面
+    pub(crate) fn planes(self, width: u32, height: u32) -> Vec<(usize, usize)> {
+        let (width, height) = (width as usize, height as usize);
+        match self {
+            OutputFormat::Yuv420p => {
+                let chroma = (width.div_ceil(2), height.div_ceil(2));
+                vec![(width, height), chroma, chroma]
+            }
+            format => vec![(width * format.bytes_per_pixel(), height)],
+        }
+    }
+}
+
+// 把行字节数向上取整到对齐值的倍数
+fn align(row_bytes: usize, alignment: Option<u32>) -> usize {
+    match alignment {
+        Some(alignment) if alignment > 1 => row_bytes.next_multiple_of(alignment as usize),
+        _ => row_bytes,
+    }
+}
+
+/// 隔行扫描视频输出整帧还是其中一场
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldSelection {
+    /// 输出整帧
+    #[default]
+    Frame,
+    /// 只输出顶场（第0、2、4……行）
+    Top,
+    /// 只输出底场（第1、3、5……行）
+    Bottom,
+}
+
+/// 输出帧的编码方式
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputEncoding {
+    /// 不编码，返回原始像素
+    #[default]
+    Raw,
+    /// PNG，无损
+    Png,
+    /// JPEG，有损，质量见 `ExtractOptions::encoding_quality`
+    Jpeg,
+    /// 无损WebP，不能设置 `ExtractOptions::encoding_quality`
+    WebP,
+}
+
+/// 抽帧选项
+///
+/// 所有字段都有默认值。不传选项的接口使用 `config::configure` 设置的全局默认选项，
+/// 未设置时等同于 `ExtractOptions::default()`。
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// 解复用器标志（fflags），例如 `"+genpts+igndts"`
+    ///
+    /// 部分损坏的摄像机文件只有在设置特定标志后才能正确解复用。
+    pub fflags: Option<String>,
+    /// 解码器错误隐藏标志（ec），例如 `"guess_mvs+deblock"`
+    pub error_concealment: Option<String>,
+    /// 其他透传给解复用器（avformat_open_input）的选项
+    pub format_options: Vec<(String, String)>,
+    /// 其他透传给解码器（avcodec_open2）的选项
+    pub decoder_options: Vec<(String, String)>,
+    /// 为本次调用捕获不高于该级别的FFmpeg日志，失败时通过 `VideoError::log` 返回
+    ///
+    /// 不影响全局日志级别，适合在生产环境中单独排查某个异常文件。
+    pub capture_log: Option<LogLevel>,
+    /// 对输出帧应用的旋转和翻转，在按容器中的旋转元数据自动旋转之后应用
+    ///
+    /// 只作用于RGB和灰度输出，原始帧（`extract_raw_frame`）保持解码器输出的方向。
+    pub transform: Transform,
+    /// 忽略容器中的旋转元数据（显示矩阵），按编码时的方向输出
+    ///
+    /// 默认按元数据自动旋转，手机竖拍的视频不会横着输出。
+    pub ignore_rotation: bool,
+    /// 时间戳修复模式
+    ///
+    /// 源文件时间戳损坏或不单调时开启：解复用时重新生成PTS（`+genpts+igndts`），
+    /// 不再按时间戳定位而是从头顺序解码，并把回退或缺失的帧时间戳按帧率推算为单调序列。
+    /// 顺序解码较慢，只应对已知有问题的文件开启。
+    pub repair_timestamps: bool,
+    /// 定位方式，默认为精确定位
+    ///
+    /// 修复模式（`repair_timestamps`）不按时间戳定位，总是精确解码到目标帧。
+    pub seek_mode: SeekMode,
+    /// 目标高度（像素），用于从多码率输入中选择视频流
+    ///
+    /// 输入包含多条视频轨（多轨MP4、HLS主播放列表等）时，选择不低于该高度的最小一条，
+    /// 例如缩略图只需要解码360p的那一条。未设置时使用FFmpeg认为最佳的视频流。
+    pub target_height: Option<u32>,
+    /// 输出宽度（像素），例如缩略图只需要160像素宽
+    ///
+    /// 只设置宽高中的一个时，另一个按原始宽高比计算；两个都设置时按给定尺寸缩放。
+    /// 都不设置时按解码后的原始尺寸输出。缩放在颜色转换时一并完成，不会先生成全尺寸的RGB数据。
+    pub output_width: Option<u32>,
+    /// 输出高度（像素），与 `output_width` 配合使用
+    pub output_height: Option<u32>,
+    /// 输出数据的字节数上限
+    ///
+    /// 按原始尺寸（或 `output_width`/`output_height` 指定的尺寸）输出会超出上限时自动等比缩小，
+    /// 保证不会返回意外的大缓冲区。
+    /// 实际采用的尺寸可以事先通过 `estimate_output` 得到。
+    pub max_output_bytes: Option<usize>,
+    /// 单次抽帧的临时内存上限（字节）
+    ///
+    /// 计入解码后的帧、拆出的单场、缩放和颜色转换的中间数据、旋转或翻转后的副本、
+    /// 按行布局重新排列后的副本以及编码后的输出；不计入解码器内部保留的参考帧、
+    /// 解封装器的读取缓冲以及从内存数据创建的临时文件。
+    /// 打开解码器时先按流的尺寸和像素格式估算，解码出帧后再按实际的帧检查一次，
+    /// 超出时返回 `MemoryLimit` 错误，不再分配，
+    /// 用于在内存受限的WebView中保证WASM堆不会因为一次抽帧增长到阈值以上。
+    /// 与 `max_output_bytes` 不同，超出时不会自动缩小。
+    pub max_temp_bytes: Option<usize>,
+    /// 缩小输出时在线性光空间中缩放
+    ///
+    /// 在伽马空间中缩小会让细小的亮部细节变暗。开启后先解码sRGB伽马、缩放、再编码回去，
+    /// 更慢但画质更好，适合用于营销页面的高质量缩略图。不缩小时没有影响。
+    pub linear_light_scaling: bool,
+    /// 转换到8位输出时的抖动方式，只在源帧位深更高时有影响
+    pub dither: Dither,
+    /// 输出帧的像素格式，默认为RGB24
+    ///
+    /// 线性光缩放（`linear_light_scaling`）只作用于RGB24输出。
+    pub output_format: OutputFormat,
+    /// 输出行对齐的字节数，必须是2的幂，例如4、8或256
+    ///
+    /// 部分GPU上传路径要求每行的字节数是固定值的倍数（WebGPU的 `bytesPerRow` 为256）。
+    /// 设置后每行（YUV420P为每个平面的每行）末尾用0填充到对齐值的倍数，
+    /// 返回的数据可以直接作为纹理上传，不需要再在JavaScript或C中重新排列。
+    /// 行字节数见 `OutputFormat::row_stride`，整帧大小见 `OutputFormat::aligned_frame_bytes`。
+    /// 只作用于原始像素输出，编码为图片时不生效；`max_output_bytes` 按不含填充的大小计算。
+    pub output_row_alignment: Option<u32>,
+    /// 从最后一行开始排列输出数据（BMP和OpenGL纹理的约定）
+    ///
+    /// 只改变原始像素数据在内存中的行顺序，YUV420P的每个平面各自倒序，画面内容不变；
+    /// 编码为图片时不生效。需要真正上下翻转画面时使用 `transform`。
+    pub bottom_up_rows: bool,
+    /// 把输出帧编码为图片，默认返回原始像素
+    ///
+    /// 编码后 `data` 为完整的图片文件内容，宽高仍为图片的像素尺寸。
+    /// 只支持RGB24、RGBA和GRAY8输出格式；JPEG会丢弃RGBA的透明通道。
+    pub output_encoding: OutputEncoding,
+    /// JPEG编码质量（1 - 100），未设置时为85
+    ///
+    /// PNG忽略该值；WebP只支持无损编码，设置时返回 `InvalidInput` 错误。
+    pub encoding_quality: Option<u8>,
+    /// 隔行扫描视频只输出其中一场，用于逐场查看快速运动
+    ///
+    /// 单场只有一半的行数，输出时在垂直方向上拉伸回整帧的尺寸。
+    /// 需要同时得到两场时使用 `video_processor::extract_fields`。
+    pub field: FieldSelection,
+    /// 保留非正方形像素，按存储尺寸输出
+    ///
+    /// 默认按采样宽高比（SAR）把变形视频缩放为正方形像素，否则DV、DVB等来源的画面会被横向压扁。
+    /// 容器和码流都声明了采样宽高比时以容器为准。
+    /// 需要与源帧逐像素对应（例如后续再自行处理）时开启。
+    pub keep_non_square_pixels: bool,
+}
+
+impl ExtractOptions {
+    // 库内部分析像素时使用的选项：打开输入、解码和缩放的设置保持不变，
+    // 输出改为 `format` 格式、从第一行开始紧密排列的整帧原始像素
+    pub(crate) fn for_analysis(&self, format: OutputFormat) -> ExtractOptions {
+        ExtractOptions {
+            output_format: format,
+            output_encoding: OutputEncoding::Raw,
+            output_row_alignment: None,
+            bottom_up_rows: false,
+            field: FieldSelection::Frame,
+            ..self.clone()
+        }
+    }
+
+    // 打开输入时使用的选项字典
+    pub(crate) fn format_dictionary(&self) -> ffmpeg::Dictionary<'static> {
+        let mut dict = ffmpeg::Dictionary::new();
+        match (&self.fflags, self.repair_timestamps) {
+            (Some(fflags), true) => dict.set("fflags", &format!("{}+genpts+igndts", fflags)),
+            (Some(fflags), false) => dict.set("fflags", fflags),
+            (None, true) => dict.set("fflags", "+genpts+igndts"),
+            (None, false) => {}
+        }
+        for (key, value) in &self.format_options {
+            dict.set(key, value);
+        }
+        dict
+    }
+
+    // 打开解码器时使用的选项字典
+    pub(crate) fn decoder_dictionary(&self) -> ffmpeg::Dictionary<'static> {
+        let mut dict = ffmpeg::Dictionary::new();
+        if let Some(ec) = &self.error_concealment {
+            dict.set("ec", ec);
+        }
+        for (key, value) in &self.decoder_options {
+            dict.set(key, value);
+        }
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试分析用的选项只改变输出的格式和布局，解码和缩放的设置保持不变
+    #[test]
+    fn test_for_analysis() {
+        let options = ExtractOptions {
+            output_width: Some(320),
+            output_encoding: OutputEncoding::Jpeg,
+            output_row_alignment: Some(64),
+            bottom_up_rows: true,
+            field: FieldSelection::Bottom,
+            repair_timestamps: true,
+            ..ExtractOptions::default()
+        };
+        let analysis = options.for_analysis(OutputFormat::Gray8);
+        assert_eq!(analysis.output_format, OutputFormat::Gray8);
+        assert_eq!(analysis.output_encoding, OutputEncoding::Raw);
+        assert_eq!(analysis.output_row_alignment, None);
+        assert!(!analysis.bottom_up_rows);
+        assert_eq!(analysis.field, FieldSelection::Frame);
+        assert_eq!(analysis.output_width, Some(320));
+        assert!(analysis.repair_timestamps);
+    }
+}
@@ -0,0 +1,221 @@
+// palette.rs
+// 主色调和调色板
+//
+// 播放器常用画面的主色调做氛围背景。在JavaScript里对整帧RGB数据聚类既慢又要先把
+// 全尺寸的像素传过去，这里在WASM内部把帧缩小后统计平均颜色，并用k-means聚类出少量代表色。
+
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::options::{ExtractOptions, OutputFormat};
+use crate::video_processor;
+
+use wasm_bindgen::prelude::*;
+
+// 统计前把帧缩小到的宽度；主色调不需要细节
+const SAMPLE_WIDTH: u32 = 64;
+
+// 调色板颜色数量上限
+const MAX_COLORS: u32 = 16;
+
+// 聚类前每个颜色通道保留的位数，相近的颜色先合并，减少迭代的计算量
+const BUCKET_BITS: u32 = 5;
+
+// k-means的最大迭代次数
+const MAX_ITERATIONS: usize = 16;
+
+/// 平均颜色和调色板
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorPalette {
+    /// 所有采样像素的平均颜色，依次为R、G、B
+    pub average: Vec<u8>,
+    /// 调色板颜色，每三个字节为一个RGB颜色，按所占比例从大到小排列
+    pub colors: Vec<u8>,
+    /// 每个调色板颜色所占的像素比例（0.0 - 1.0），与 `colors` 一一对应
+    pub weights: Vec<f32>,
+}
+
+/// 从若干时间点的帧中提取平均颜色和调色板，使用全局默认选项
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `times` - 采样帧的时间点（秒），只取一帧时传一个时间点
+/// * `colors` - 调色板颜色数量（1 - 16），常用5
+///
+/// # 返回
+/// * `Result<ColorPalette, VideoError>` - 成功时返回平均颜色和调色板；
+///   画面颜色种类少于 `colors` 时调色板也相应较少
+pub fn extract_palette<P: AsRef<Path>>(
+    input_path: P,
+    times: &[f64],
+    colors: u32,
+) -> Result<ColorPalette, VideoError> {
+    extract_palette_with_options(input_path, times, colors, &config::default_options())
+}
+
+/// 使用自定义选项提取平均颜色和调色板
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `times` - 采样帧的时间点（秒）
+/// * `colors` - 调色板颜色数量（1 - 16）
+/// * `options` - 抽帧选项，输出尺寸、格式和编码由本函数决定
+///
+/// # 返回
+/// * `Result<ColorPalette, VideoError>` - 成功时返回平均颜色和调色板
+pub fn extract_palette_with_options<P: AsRef<Path>>(
+    input_path: P,
+    times: &[f64],
+    colors: u32,
+    options: &ExtractOptions,
+) -> Result<ColorPalette, VideoError> {
+    if !(1..=MAX_COLORS).contains(&colors) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "调色板颜色数量必须在 1 - {} 之间: {}",
+                MAX_COLORS, colors
+            )),
+        ));
+    }
+    if times.is_empty() {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("至少需要一个采样时间点".to_string()),
+        ));
+    }
+
+    let mut frame_options = options.for_analysis(OutputFormat::Rgb24);
+    frame_options.output_width = Some(SAMPLE_WIDTH);
+    frame_options.output_height = None;
+    let frames = video_processor::extract_frames_with_options(input_path, times, &frame_options)?;
+
+    let pixels: Vec<u8> = frames.into_iter().flat_map(|frame| frame.data).collect();
+    Ok(palette_from_pixels(&pixels, colors as usize))
+}
+
+/// 从RGB24像素数据计算平均颜色和调色板
+///
+/// 相同的输入总是得到相同的结果，聚类的初始中心按确定的规则选取。
+///
+/// # 参数
+/// * `pixels` - 紧密排列的RGB24像素数据，可以是多帧拼接在一起
+/// * `colors` - 调色板颜色数量上限
+///
+/// # 返回
+/// * `ColorPalette` - 平均颜色和调色板；没有像素时平均颜色为黑色，调色板为空
+pub fn palette_from_pixels(pixels: &[u8], colors: usize) -> ColorPalette {
+    // 相近的颜色先归入同一个桶，记录像素数量和颜色之和
+    let mut buckets = vec![(0u32, [0u64; 3]); 1 << (BUCKET_BITS * 3)];
+    let mut total = [0u64; 3];
+    let mut count = 0u64;
+    for pixel in pixels.chunks_exact(3) {
+        let key = pixel.iter().fold(0usize, |key, &value| {
+            (key << BUCKET_BITS) | usize::from(value >> (8 - BUCKET_BITS))
+        });
+        let (n, sum) = &mut buckets[key];
+        *n += 1;
+        for ((sum, total), &value) in sum.iter_mut().zip(&mut total).zip(pixel) {
+            *sum += u64::from(value);
+            *total += u64::from(value);
+        }
+        count += 1;
+    }
+    let average = total.map(|sum| (sum as f64 / count.max(1) as f64).round() as u8);
+
+    let points: Vec<([f64; 3], f64)> = buckets
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|&(n, sum)| (sum.map(|s| s as f64 / f64::from(n)), f64::from(n)))
+        .collect();
+
+    let mut clusters = kmeans(&points, colors);
+    clusters.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ColorPalette {
+        average: average.to_vec(),
+        colors: clusters
+            .iter()
+            .flat_map(|(center, _)| center.map(|value| value.round().clamp(0.0, 255.0) as u8))
+            .collect(),
+        weights: clusters
+            .iter()
+            .map(|(_, weight)| (weight / count.max(1) as f64) as f32)
+            .collect(),
+    }
+}
+
+// 对带权重的点做k-means，返回非空的聚类中心及其总权重
+fn kmeans(points: &[([f64; 3], f64)], k: usize) -> Vec<([f64; 3], f64)> {
+    // 初始中心：权重最大的点，之后每次取离已有中心最远（按权重放大）的点
+    let mut centers: Vec<[f64; 3]> = Vec::with_capacity(k);
+    if let Some(&(first, _)) = points.iter().max_by(|a, b| a.1.total_cmp(&b.1)) {
+        centers.push(first);
+    }
+    while centers.len() < k {
+        let farthest = points
+            .iter()
+            .map(|(color, weight)| (color, weight * nearest(&centers, color).1))
+            .filter(|&(_, score)| score > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match farthest {
+            Some((&color, _)) => centers.push(color),
+            // 剩下的点都与某个中心重合，颜色种类不足k个
+            None => break,
+        }
+    }
+
+    let mut assignment = vec![usize::MAX; points.len()];
+    let mut weights = vec![0.0; centers.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (point, assigned) in points.iter().zip(&mut assignment) {
+            let (index, _) = nearest(&centers, &point.0);
+            if *assigned != index {
+                *assigned = index;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+
+        let mut sums = vec![[0.0f64; 3]; centers.len()];
+        weights.iter_mut().for_each(|weight| *weight = 0.0);
+        for ((color, weight), &index) in points.iter().zip(&assignment) {
+            for (sum, value) in sums[index].iter_mut().zip(color) {
+                *sum += value * weight;
+            }
+            weights[index] += weight;
+        }
+        for ((center, sum), &weight) in centers.iter_mut().zip(&sums).zip(&weights) {
+            if weight > 0.0 {
+                *center = sum.map(|s| s / weight);
+            }
+        }
+    }
+
+    centers
+        .into_iter()
+        .zip(weights)
+        .filter(|&(_, weight)| weight > 0.0)
+        .collect()
+}
+
+// 最近的中心的序号和距离的平方
+fn nearest(centers: &[[f64; 3]], color: &[f64; 3]) -> (usize, f64) {
+    centers
+        .iter()
+        .map(|center| {
+            center
+                .iter()
+                .zip(color)
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<f64>()
+        })
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
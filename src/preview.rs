@@ -0,0 +1,321 @@
+// preview.rs
+// 鼠标悬停时播放的动态预览
+//
+// 悬停预览是这个库的主要用途之一，以前只能逐帧抽出后再交给另一套工具合成动画。
+// 这里定位到起点后顺序解码一小段视频，按目标帧率取帧并缩小，直接编码为GIF、
+// 动画WebP或APNG动画。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::encode;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::options::{ExtractOptions, OutputFormat};
+use crate::validation;
+use crate::video_processor::{self, RgbFrame, VideoScan};
+
+use wasm_bindgen::prelude::*;
+
+// GIF的帧延迟以百分之一秒为单位，浏览器会把小于2的延迟当成10，帧率不能超过50
+const MAX_GIF_FPS: f64 = 50.0;
+
+// APNG和动画WebP的最高帧率
+const MAX_FPS: f64 = 60.0;
+
+// 一段预览最多包含的帧数，避免参数写错时生成巨大的动画
+const MAX_FRAMES: usize = 600;
+
+// 一段预览所有RGBA帧加起来的字节数上限，编码之前这些帧同时留在内存中
+const MAX_FRAME_BYTES: u64 = 256 * 1024 * 1024;
+
+/// 动态预览的容器格式
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewFormat {
+    /// GIF，兼容性最好，帧率不超过50
+    #[default]
+    Gif,
+    /// 无损全彩色的动画WebP，没有GIF的256色限制，但视频内容的文件通常比GIF大
+    WebP,
+    /// APNG，不支持WebP的环境中代替GIF
+    Apng,
+}
+
+/// 动态预览的参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviewOptions {
+    /// 预览的起点（秒），与 `extract_frame` 的时间点含义相同
+    pub start: f64,
+    /// 预览的时长（秒）
+    pub duration: f64,
+    /// 动画的帧率，GIF不超过50，其他格式不超过60
+    pub fps: f64,
+    /// 动画的宽度（像素），高度按显示宽高比计算
+    pub width: u32,
+    /// 容器格式
+    pub format: PreviewFormat,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            duration: 3.0,
+            fps: 10.0,
+            width: 320,
+            format: PreviewFormat::Gif,
+        }
+    }
+}
+
+/// 生成动态预览
+///
+/// 定位到 `preview.start` 后顺序解码 `preview.duration` 秒，按 `preview.fps` 取帧，
+/// 编码为 `preview.format` 指定格式的无限循环动画。
+/// 解码之前按帧数和尺寸检查所有帧加起来的大小，超过256MB时返回错误。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `preview` - 预览的时间范围、帧率、宽度和格式
+/// * `options` - 抽帧选项，输出尺寸、格式和编码由本函数决定，其他选项（旋转、变换、抖动等）照常生效
+///
+/// # 返回
+/// * `Result<Vec<u8>, VideoError>` - 成功时返回动画文件内容
+pub fn generate_preview<P: AsRef<Path>>(
+    input_path: P,
+    preview: &PreviewOptions,
+    options: &ExtractOptions,
+) -> Result<Vec<u8>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let max_fps = match preview.format {
+        PreviewFormat::Gif => MAX_GIF_FPS,
+        PreviewFormat::WebP | PreviewFormat::Apng => MAX_FPS,
+    };
+    if !(preview.fps > 0.0 && preview.fps <= max_fps) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "{:?} 的帧率必须在 0 - {} 之间: {}",
+                preview.format, max_fps, preview.fps
+            )),
+        ));
+    }
+
+    let frames = decode_segment(
+        input_path.as_ref(),
+        preview.start,
+        preview.duration,
+        preview.fps,
+        preview.width,
+        options,
+    )?;
+    let (width, height) = (frames[0].frame.width, frames[0].frame.height);
+    let frames = frames
+        .into_iter()
+        .map(|frame| (frame.frame.data, frame.delay_ms))
+        .collect();
+    match preview.format {
+        PreviewFormat::Gif => encode::encode_gif(frames, width, height),
+        PreviewFormat::WebP => encode::encode_animated_webp(frames, width, height),
+        PreviewFormat::Apng => encode::encode_apng(frames, width, height),
+    }
+}
+
+/// 生成GIF动画预览，使用全局默认选项
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `start` - 预览的起点（秒），与 `extract_frame` 的时间点含义相同
+/// * `duration` - 预览的时长（秒）
+/// * `fps` - 动画的帧率，不超过50
+/// * `width` - 动画的宽度（像素），高度按显示宽高比计算
+///
+/// # 返回
+/// * `Result<Vec<u8>, VideoError>` - 成功时返回无限循环的GIF文件内容
+pub fn generate_gif_preview<P: AsRef<Path>>(
+    input_path: P,
+    start: f64,
+    duration: f64,
+    fps: f64,
+    width: u32,
+) -> Result<Vec<u8>, VideoError> {
+    generate_gif_preview_with_options(
+        input_path,
+        start,
+        duration,
+        fps,
+        width,
+        &config::default_options(),
+    )
+}
+
+/// 使用自定义选项生成GIF动画预览，等同于格式为GIF的 `generate_preview`
+pub fn generate_gif_preview_with_options<P: AsRef<Path>>(
+    input_path: P,
+    start: f64,
+    duration: f64,
+    fps: f64,
+    width: u32,
+    options: &ExtractOptions,
+) -> Result<Vec<u8>, VideoError> {
+    let preview = PreviewOptions {
+        start,
+        duration,
+        fps,
+        width,
+        format: PreviewFormat::Gif,
+    };
+    generate_preview(input_path, &preview, options)
+}
+
+// 预览中的一帧及其显示时长
+struct PreviewFrame {
+    frame: RgbFrame,
+    delay_ms: u32,
+}
+
+// 解码从 `start` 开始、持续 `duration` 秒的片段，按 `fps` 取帧并缩放为 `width` 宽的RGBA；
+// 至少返回一帧
+fn decode_segment(
+    path: &Path,
+    start: f64,
+    duration: f64,
+    fps: f64,
+    width: u32,
+    options: &ExtractOptions,
+) -> Result<Vec<PreviewFrame>, VideoError> {
+    validation::validate_time(start)?;
+    if !(duration.is_finite() && duration > 0.0) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的预览时长: {}", duration)),
+        ));
+    }
+    let count = (duration * fps).ceil() as usize;
+    if count > MAX_FRAMES {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("预览帧数 {} 超出上限 {}", count, MAX_FRAMES)),
+        ));
+    }
+    validation::validate_dimensions(width as i32, 1)?;
+
+    let mut frame_options = options.for_analysis(OutputFormat::Rgba);
+    frame_options.output_width = Some(width);
+    frame_options.output_height = None;
+    frame_options.max_output_bytes = None;
+
+    // 事先确定动画高度并固定下来，中途分辨率或宽高比变化时每帧的尺寸仍然一致
+    let mut scan = VideoScan::open(path, options)?;
+    let height = video_processor::estimate_input_output(scan.input(), &frame_options)?.height;
+    frame_options.output_height = Some(height);
+    check_frame_bytes(count, width, height)?;
+
+    // 每个取帧时间点取第一个不早于它的帧，帧率低于 `fps` 时一帧覆盖多个时间点
+    let step = 1.0 / fps;
+    let mut picked: Vec<(f64, RgbFrame)> = Vec::new();
+    let mut index = 0usize;
+
+    // 定位到起点之前的关键帧，之后顺序解码
+    scan.seek(start)?;
+    scan.for_each_frame(|frame, time| {
+        let requested = start + index as f64 * step;
+        if time < requested {
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        picked.push((
+            requested,
+            video_processor::to_rgb_frame(frame, &frame_options)?,
+        ));
+        while index < count && start + index as f64 * step <= time {
+            index += 1;
+        }
+        Ok(if index >= count {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        })
+    })?;
+
+    if picked.is_empty() {
+        return Err(VideoError::new(
+            VideoErrorCode::FrameNotFound,
+            Some(format!("{} 秒之后没有可用的帧", start)),
+        ));
+    }
+
+    // 每帧显示到下一帧的时间点，最后一帧显示到片段结尾
+    let end = start + duration;
+    let times: Vec<f64> = picked.iter().map(|(time, _)| *time).collect();
+    Ok(picked
+        .into_iter()
+        .enumerate()
+        .map(|(i, (time, frame))| {
+            let next = times.get(i + 1).copied().unwrap_or(end);
+            PreviewFrame {
+                frame,
+                delay_ms: ((next - time) * 1000.0).round().max(1.0) as u32,
+            }
+        })
+        .collect())
+}
+
+// `count` 帧 `width`x`height` 的RGBA加起来超出上限时返回错误
+fn check_frame_bytes(count: usize, width: u32, height: u32) -> Result<(), VideoError> {
+    let bytes = count as u64 * u64::from(width) * u64::from(height) * 4; // RGBA每像素四字节
+    if bytes > MAX_FRAME_BYTES {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "预览的 {} 帧共需 {} 字节，超出上限 {} 字节，请缩短时长、降低帧率或缩小宽度",
+                count, bytes, MAX_FRAME_BYTES
+            )),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ffmpeg_next::format::Pixel;
+    use ffmpeg_next::frame::Video;
+    use ffmpeg_next::Rational;
+
+    // 测试帧数据总量的上限
+    #[test]
+    fn test_check_frame_bytes() {
+        assert!(check_frame_bytes(30, 320, 180).is_ok());
+        assert!(check_frame_bytes(MAX_FRAMES, 320, 180).is_ok());
+        assert!(check_frame_bytes(MAX_FRAMES, 1920, 1080).is_err());
+    }
+
+    // 测试固定高度之后，分辨率和宽高比中途变化的帧仍然输出同样的尺寸，可以编码为一个动画
+    #[test]
+    fn test_pinned_height() {
+        ffmpeg_init::initialize();
+
+        let options = ExtractOptions {
+            output_width: Some(32),
+            output_height: Some(18),
+            output_format: OutputFormat::Rgba,
+            ..ExtractOptions::default()
+        };
+        let mut frames = Vec::new();
+        for (width, height, sar) in [(64, 36, (1, 1)), (48, 48, (1, 1)), (48, 36, (4, 3))] {
+            let mut frame = Video::new(Pixel::YUV420P, width, height);
+            // 安全性：只修改测试自己创建的帧
+            unsafe { (*frame.as_mut_ptr()).sample_aspect_ratio = Rational::from(sar).into() };
+            let rgba = video_processor::to_rgb_frame(&frame, &options).unwrap();
+            assert_eq!((rgba.width, rgba.height), (32, 18));
+            frames.push((rgba.data, 100));
+        }
+        assert!(encode::encode_gif(frames, 32, 18).is_ok());
+    }
+}
@@ -0,0 +1,383 @@
+// qc.rs
+// 质检报告
+//
+// 把画面和声音的质检项目汇总到一次调用中：黑场、冻结画面、数字静音断音和削波、
+// 音视频同步以及损坏的数据包。各项分别调用时每一项都要重新读取甚至解码整个文件，
+// 这里只读取一遍，每个数据包和解码出的每一帧同时交给所有选中的检查项。
+
+use std::path::Path;
+
+use crate::audio_qc::{AudioChecker, AudioDropout, AudioQc, ClippingSpan};
+use crate::av_sync::{AvSyncReport, SyncTracker};
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::frozen::{self, FreezeDetector, FrozenSpan};
+use crate::options::ExtractOptions;
+use crate::video_processor;
+
+use ffmpeg::media::Type;
+use ffmpeg::util::frame::{audio::Audio, video::Video};
+use ffmpeg_next as ffmpeg;
+
+// 画面至少停住这么久（秒）才报告为冻结，与FFmpeg freezedetect滤镜的默认值相同
+const FROZEN_MIN_DURATION: f64 = 2.0;
+
+// 黑场至少持续这么久（秒）才报告，与FFmpeg blackdetect滤镜的默认值相同
+const BLACK_MIN_DURATION: f64 = 2.0;
+
+// 亮度不超过该值的像素视为黑色，留出有限范围视频（黑电平16）的余量
+const BLACK_PIXEL_LEVEL: u8 = 32;
+
+// 黑色像素超过该比例的画面视为黑场
+const BLACK_RATIO: f64 = 0.98;
+
+/// 要执行的质检项目，默认全部执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QcChecks {
+    /// 黑场
+    pub black_frames: bool,
+    /// 冻结画面
+    pub frozen_frames: bool,
+    /// 数字静音断音和削波，需要解码音频
+    pub audio: bool,
+    /// 音视频同步，只读取时间戳
+    pub av_sync: bool,
+    /// 损坏的数据包
+    pub corrupt_packets: bool,
+}
+
+impl Default for QcChecks {
+    fn default() -> Self {
+        Self {
+            black_frames: true,
+            frozen_frames: true,
+            audio: true,
+            av_sync: true,
+            corrupt_packets: true,
+        }
+    }
+}
+
+impl QcChecks {
+    // 画面检查和音视频同步需要视频流；只检查音频和数据包时纯音频文件也可以
+    fn needs_video(&self) -> bool {
+        self.black_frames || self.frozen_frames || self.av_sync
+    }
+}
+
+/// 一段黑场
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlackSpan {
+    /// 第一帧黑场的时间（秒）
+    pub start: f64,
+    /// 画面恢复的时间（秒）；黑场持续到结尾时为最后一帧
+    pub end: f64,
+}
+
+/// 一个损坏的数据包
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CorruptPacket {
+    /// 所属流的序号
+    pub stream_index: usize,
+    /// 数据包的时间（秒），没有时间戳时为None
+    pub time: Option<f64>,
+}
+
+/// 质检报告，没有选中的项目为空
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QcReport {
+    /// 黑场
+    pub black: Vec<BlackSpan>,
+    /// 冻结画面
+    pub frozen: Vec<FrozenSpan>,
+    /// 是否有音频流；没有时下面的音频检查结果为空
+    pub has_audio: bool,
+    /// 数字静音断音
+    pub dropouts: Vec<AudioDropout>,
+    /// 削波
+    pub clipping: Vec<ClippingSpan>,
+    /// 音视频同步检查结果，没有音频流或时间戳时为None
+    pub av_sync: Option<AvSyncReport>,
+    /// 容器标记为损坏或解码器拒绝的数据包
+    pub corrupt_packets: Vec<CorruptPacket>,
+}
+
+impl QcReport {
+    /// 是否没有发现任何问题
+    pub fn is_clean(&self) -> bool {
+        self.black.is_empty()
+            && self.frozen.is_empty()
+            && self.dropouts.is_empty()
+            && self.clipping.is_empty()
+            && self.av_sync.as_ref().is_none_or(|sync| sync.in_sync)
+            && self.corrupt_packets.is_empty()
+    }
+}
+
+/// 在一次读取中执行选中的质检项目
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `checks` - 要执行的质检项目；没有选中黑场、冻结画面和音视频同步时不需要视频流
+///
+/// # 返回
+/// * `Result<QcReport, VideoError>` - 成功时返回质检报告，没有音频流时音频相关的项目为空
+pub fn qc_report<P: AsRef<Path>>(input_path: P, checks: &QcChecks) -> Result<QcReport, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let options = config::default_options();
+    let mut ictx = video_processor::open_input(input_path.as_ref(), &options)?;
+    let video_index = if checks.needs_video() {
+        Some(video_processor::select_video_stream(&ictx, &options)?)
+    } else {
+        None
+    };
+
+    let mut video = VideoChecks {
+        black: checks.black_frames.then(BlackDetector::default),
+        frozen: checks
+            .frozen_frames
+            .then(|| FreezeDetector::new(FROZEN_MIN_DURATION)),
+    };
+    let audio_stream = ictx.streams().best(Type::Audio);
+    let audio_index = audio_stream.as_ref().map(|stream| stream.index());
+    let (video_tb, mut video_decoder, mut sync) = match video_index {
+        Some(video_index) => {
+            let video_stream = ictx
+                .stream(video_index)
+                .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+            let decoder = if video.is_active() {
+                Some(video_processor::open_decoder(&video_stream, &options)?)
+            } else {
+                None
+            };
+            let sync = match &audio_stream {
+                Some(audio_stream) if checks.av_sync => {
+                    Some(SyncTracker::new(&video_stream, audio_stream))
+                }
+                _ => None,
+            };
+            (f64::from(video_stream.time_base()), decoder, sync)
+        }
+        None => (0.0, None, None),
+    };
+    let mut audio = match audio_index.and_then(|index| ictx.stream(index)) {
+        Some(stream) if checks.audio => Some((
+            AudioChecker::default(),
+            video_processor::open_audio_decoder(&stream)?,
+            f64::from(stream.time_base()),
+        )),
+        _ => None,
+    };
+
+    let time_bases: Vec<f64> = ictx
+        .streams()
+        .map(|stream| f64::from(stream.time_base()))
+        .collect();
+    let mut corrupt_packets = Vec::new();
+    let mut video_frame = Video::empty();
+    let mut audio_frame = Audio::empty();
+
+    for (index, packet) in video_processor::read_packets(&mut ictx) {
+        if let Some(sync) = &mut sync {
+            sync.push(index, &packet);
+        }
+
+        let mut corrupt = packet.is_corrupt();
+        if Some(index) == video_index {
+            if let Some(decoder) = &mut video_decoder {
+                // 解码器拒绝的数据包同样视为损坏，继续处理后面的数据
+                if decoder.send_packet(&packet).is_ok() {
+                    while decoder.receive_frame(&mut video_frame).is_ok() {
+                        video.push_frame(&video_frame, video_tb, &options)?;
+                    }
+                } else {
+                    corrupt = true;
+                }
+            }
+        } else if Some(index) == audio_index {
+            if let Some((checker, decoder, time_base)) = &mut audio {
+                if decoder.send_packet(&packet).is_ok() {
+                    while decoder.receive_frame(&mut audio_frame).is_ok() {
+                        let time = audio_frame.timestamp().map(|ts| ts as f64 * *time_base);
+                        checker.push_frame(&audio_frame, time);
+                    }
+                } else {
+                    corrupt = true;
+                }
+            }
+        }
+
+        if corrupt && checks.corrupt_packets {
+            let time_base = time_bases[index];
+            corrupt_packets.push(CorruptPacket {
+                stream_index: index,
+                time: packet
+                    .pts()
+                    .or(packet.dts())
+                    .map(|ts| ts as f64 * time_base),
+            });
+        }
+    }
+
+    if let Some(decoder) = &mut video_decoder {
+        if decoder.send_eof().is_ok() {
+            while decoder.receive_frame(&mut video_frame).is_ok() {
+                video.push_frame(&video_frame, video_tb, &options)?;
+            }
+        }
+    }
+    if let Some((checker, decoder, time_base)) = &mut audio {
+        if decoder.send_eof().is_ok() {
+            while decoder.receive_frame(&mut audio_frame).is_ok() {
+                let time = audio_frame.timestamp().map(|ts| ts as f64 * *time_base);
+                checker.push_frame(&audio_frame, time);
+            }
+        }
+    }
+
+    let AudioQc { dropouts, clipping } = audio
+        .map(|(checker, _, _)| checker.finish())
+        .unwrap_or_default();
+
+    Ok(QcReport {
+        black: video.black.map_or_else(Vec::new, |black| black.finish()),
+        frozen: video.frozen.map_or_else(Vec::new, |frozen| frozen.finish()),
+        has_audio: audio_index.is_some(),
+        dropouts,
+        clipping,
+        // 时间戳不可用时无法判断同步，不影响其他项目
+        av_sync: sync.and_then(|sync| sync.finish().ok()),
+        corrupt_packets,
+    })
+}
+
+// 需要解码画面的检查项
+struct VideoChecks {
+    black: Option<BlackDetector>,
+    frozen: Option<FreezeDetector>,
+}
+
+impl VideoChecks {
+    fn is_active(&self) -> bool {
+        self.black.is_some() || self.frozen.is_some()
+    }
+
+    // 每帧只缩小一次，亮度缩略图同时交给各检查项
+    fn push_frame(
+        &mut self,
+        frame: &Video,
+        time_base: f64,
+        options: &ExtractOptions,
+    ) -> Result<(), VideoError> {
+        let time = match frame.timestamp() {
+            Some(ts) => ts as f64 * time_base,
+            None => return Ok(()),
+        };
+        let luma = video_processor::convert_to_luma(
+            frame,
+            frozen::THUMB_WIDTH,
+            frozen::THUMB_HEIGHT,
+            options,
+        )?;
+        if let Some(black) = &mut self.black {
+            black.push(time, &luma);
+        }
+        if let Some(frozen) = &mut self.frozen {
+            frozen.push(time, luma);
+        }
+        Ok(())
+    }
+}
+
+// 记录持续足够长的黑场
+#[derive(Default)]
+struct BlackDetector {
+    // 当前黑场开始的时间和最近一帧的时间
+    run: Option<(f64, f64)>,
+    spans: Vec<BlackSpan>,
+}
+
+impl BlackDetector {
+    fn push(&mut self, time: f64, luma: &[u8]) {
+        let dark = luma
+            .iter()
+            .filter(|&&level| level <= BLACK_PIXEL_LEVEL)
+            .count();
+        let black = dark as f64 >= luma.len() as f64 * BLACK_RATIO;
+
+        match (black, self.run) {
+            (true, None) => self.run = Some((time, time)),
+            (true, Some((start, _))) => self.run = Some((start, time)),
+            (false, Some((start, _))) => {
+                self.close(start, time);
+                self.run = None;
+            }
+            (false, None) => {}
+        }
+    }
+
+    fn finish(mut self) -> Vec<BlackSpan> {
+        if let Some((start, last)) = self.run.take() {
+            self.close(start, last);
+        }
+        self.spans
+    }
+
+    fn close(&mut self, start: f64, end: f64) {
+        if end - start >= BLACK_MIN_DURATION {
+            self.spans.push(BlackSpan { start, end });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试只有画面检查和音视频同步需要视频流
+    #[test]
+    fn test_needs_video() {
+        assert!(QcChecks::default().needs_video());
+        let audio_only = QcChecks {
+            black_frames: false,
+            frozen_frames: false,
+            av_sync: false,
+            ..QcChecks::default()
+        };
+        assert!(!audio_only.needs_video());
+        assert!(QcChecks {
+            av_sync: true,
+            ..audio_only
+        }
+        .needs_video());
+    }
+
+    // 测试黑场的判定比例和最短持续时间
+    #[test]
+    fn test_black_detector() {
+        // 50个像素中49个为暗像素时恰好达到98%
+        let black: Vec<u8> = (0..50)
+            .map(|i| if i == 0 { 200 } else { BLACK_PIXEL_LEVEL })
+            .collect();
+        let gray: Vec<u8> = (0..50).map(|i| if i < 2 { 200 } else { 0 }).collect();
+
+        let mut detector = BlackDetector::default();
+        for (time, luma) in [&gray, &black, &black, &black, &gray, &black]
+            .into_iter()
+            .enumerate()
+        {
+            detector.push(time as f64, luma);
+        }
+        // 结尾的黑场不足2秒，不计入
+        assert_eq!(
+            detector.finish(),
+            [BlackSpan {
+                start: 1.0,
+                end: 4.0
+            }]
+        );
+    }
+}
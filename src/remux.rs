@@ -0,0 +1,213 @@
+// remux.rs
+// 不重新编码的短视频预览
+//
+// 网页上的"播放预览"以前需要服务端用ffmpeg命令行切出一小段。
+// 这里定位到起点之前最近的关键帧，把视频流和主音频流的数据包原样复制到新的MP4中，
+// 时间戳平移到从0开始，整个过程不解码，速度只取决于读取数据的快慢。
+
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::log_capture;
+use crate::temp_storage;
+use crate::validation;
+use crate::video_processor;
+
+use ffmpeg::format::context::{Input, Output};
+use ffmpeg::media::Type;
+use ffmpeg::{codec, Rational};
+use ffmpeg_next as ffmpeg;
+
+// 一段预览的最长时长（秒），避免参数写错时复制出接近完整的文件
+const MAX_DURATION: f64 = 60.0;
+
+/// 从最近的关键帧开始截取一段视频，不重新编码，封装为MP4
+///
+/// 片段从 `start` 之前（含）最近的视频关键帧开始，因此实际起点可能比 `start` 略早，
+/// 时长按该关键帧计算。包含视频流和主音频流，MP4不支持的音频编码会被略去。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `start` - 片段的起点（秒），与 `extract_frame` 的时间点含义相同
+/// * `duration` - 片段的时长（秒），不超过60秒
+///
+/// # 返回
+/// * `Result<Vec<u8>, VideoError>` - 成功时返回MP4文件内容，moov位于文件开头，可以边下载边播放
+pub fn clip_preview<P: AsRef<Path>>(
+    input_path: P,
+    start: f64,
+    duration: f64,
+) -> Result<Vec<u8>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    validation::validate_time(start)?;
+    if !(duration > 0.0 && duration <= MAX_DURATION) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "片段时长必须在 0 - {} 秒之间: {}",
+                MAX_DURATION, duration
+            )),
+        ));
+    }
+
+    let options = config::default_options();
+    let mut ictx = video_processor::open_input(input_path.as_ref(), &options)?;
+    let video_index = video_processor::select_video_stream(&ictx, &options)?;
+    let audio_index = ictx
+        .streams()
+        .best(Type::Audio)
+        .map(|stream| stream.index());
+
+    // MP4的muxer需要可以回写的文件，先写到临时存储中，完成后读回内存
+    let storage = temp_storage::current();
+    let output_path = storage.store(&[])?;
+    let result = remux(
+        &mut ictx,
+        &output_path,
+        video_index,
+        audio_index,
+        start,
+        duration,
+    )
+    .and_then(|_| {
+        std::fs::read(&output_path).map_err(|e| {
+            VideoError::new(
+                VideoErrorCode::FFmpegError,
+                Some(format!("无法读取输出文件: {}", e)),
+            )
+        })
+    });
+    storage.remove(&output_path);
+
+    result
+}
+
+// 把 [start, start + duration) 内的数据包复制到 `output_path`
+fn remux(
+    ictx: &mut Input,
+    output_path: &Path,
+    video_index: usize,
+    audio_index: Option<usize>,
+    start: f64,
+    duration: f64,
+) -> Result<(), VideoError> {
+    let mut octx = ffmpeg::format::output_as(output_path, "mp4")?;
+
+    // 输入流序号对应的输出流序号，不复制的流为None
+    let mut mapping = vec![None; ictx.nb_streams() as usize];
+    for index in std::iter::once(video_index).chain(audio_index) {
+        let parameters = match ictx.stream(index) {
+            Some(stream) => stream.parameters(),
+            None => continue,
+        };
+        let id = parameters.id();
+        if !mp4_supports(&octx, id) {
+            if index == video_index {
+                return Err(VideoError::new(
+                    VideoErrorCode::InvalidInput,
+                    Some(format!("MP4不支持该视频编码: {:?}", id)),
+                ));
+            }
+            log_capture::warn(&format!("MP4不支持音频编码 {:?}，预览中不包含声音", id));
+            continue;
+        }
+
+        let mut stream = octx.add_stream(ffmpeg::encoder::find(codec::Id::None))?;
+        stream.set_parameters(parameters);
+        // 安全性：只修改刚创建的输出流的参数；清零后由muxer按MP4的规则重新选择codec tag
+        unsafe {
+            (*stream.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+        mapping[index] = Some(stream.index());
+    }
+
+    let mut header = ffmpeg::Dictionary::new();
+    // 把moov放到文件开头，浏览器不用下载完整文件就能开始播放
+    header.set("movflags", "+faststart");
+    octx.write_header_with(header)?;
+
+    // 写入文件头之后muxer可能调整了输出流的时间基
+    let input_time_bases: Vec<Rational> = ictx.streams().map(|s| s.time_base()).collect();
+    let output_time_bases: Vec<Rational> = octx.streams().map(|s| s.time_base()).collect();
+
+    // 定位到起点之前的关键帧
+    video_processor::seek_before(ictx, start)?;
+
+    // 片段的第一个视频关键帧：解码时间作为输出的零点，显示时间作为片段的起点
+    let mut bounds: Option<(f64, f64)> = None;
+    for (index, mut packet) in video_processor::read_packets(ictx) {
+        let output_index = match mapping[index] {
+            Some(output_index) => output_index,
+            None => continue,
+        };
+        let dts = match packet.dts().or(packet.pts()) {
+            Some(dts) => dts,
+            None => continue,
+        };
+        let time_base = f64::from(input_time_bases[index]);
+        let pts = packet.pts().unwrap_or(dts);
+
+        let origin = if index == video_index {
+            let (origin, clip_start) = match bounds {
+                Some(bounds) => bounds,
+                // 定位后第一个关键帧之前的数据包无法独立解码
+                None if packet.is_key() => {
+                    let first = (dts as f64 * time_base, pts as f64 * time_base);
+                    bounds = Some(first);
+                    first
+                }
+                None => continue,
+            };
+            // 按解码顺序截断，保留的每一帧所参考的帧都在它之前
+            if dts as f64 * time_base >= clip_start + duration {
+                break;
+            }
+            origin
+        } else {
+            let (origin, clip_start) = match bounds {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+            // 音频只保留显示时间落在片段内的数据包
+            let time = pts as f64 * time_base;
+            if time < clip_start || time >= clip_start + duration {
+                continue;
+            }
+            origin
+        };
+
+        let offset = (origin / time_base).round() as i64;
+        packet.set_pts(packet.pts().map(|ts| ts - offset));
+        packet.set_dts(packet.dts().map(|ts| ts - offset));
+        packet.rescale_ts(input_time_bases[index], output_time_bases[output_index]);
+        packet.set_position(-1);
+        packet.set_stream(output_index);
+        packet.write_interleaved(&mut octx)?;
+    }
+
+    if bounds.is_none() {
+        return Err(VideoError::new(
+            VideoErrorCode::FrameNotFound,
+            Some(format!("{} 秒之后没有视频关键帧", start)),
+        ));
+    }
+
+    octx.write_trailer()?;
+    Ok(())
+}
+
+// MP4容器能否直接存放该编码的数据
+fn mp4_supports(octx: &Output, id: codec::Id) -> bool {
+    // 安全性：只读取已创建的输出格式描述
+    unsafe {
+        ffmpeg::ffi::avformat_query_codec(
+            octx.format().as_ptr(),
+            id.into(),
+            ffmpeg::ffi::FF_COMPLIANCE_NORMAL as i32,
+        ) == 1
+    }
+}
@@ -0,0 +1,299 @@
+// sandbox.rs
+// 在独立子进程中执行抽帧，隔离解码器崩溃（仅原生平台）
+//
+// 父进程通过命令行参数把任务交给 `video-capture-sandbox` 子进程，
+// 子进程先设置资源限制（rlimit）再执行解码，最后把结果序列化到标准输出。
+// 即使解码器在恶意输入上崩溃，也只会终止子进程，父进程得到一个普通的错误。
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::options::OutputFormat;
+use crate::video_processor::{self, FrameInfo, RgbFrame};
+
+/// 子进程可执行文件的默认名称
+pub const HELPER_NAME: &str = "video-capture-sandbox";
+
+/// 用于覆盖子进程可执行文件路径的环境变量
+pub const HELPER_ENV: &str = "VIDEO_CAPTURE_SANDBOX_HELPER";
+
+// 序列化结果的状态字节
+const STATUS_OK: u8 = 0;
+const STATUS_ERR: u8 = 1;
+
+/// 沙箱子进程的资源限制
+#[derive(Debug, Clone)]
+pub struct SandboxLimits {
+    /// 子进程最大虚拟内存（字节），0 表示不限制
+    pub max_memory_bytes: u64,
+    /// 子进程最大CPU时间（秒），0 表示不限制
+    pub max_cpu_secs: u64,
+    /// 子进程可执行文件路径，未指定时依次查找环境变量和当前可执行文件所在目录
+    pub helper_path: Option<PathBuf>,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: 1024 * 1024 * 1024,
+            max_cpu_secs: 30,
+            helper_path: None,
+        }
+    }
+}
+
+/// 在沙箱子进程中从视频文件提取特定时间点的帧
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `time_sec` - 要提取的帧所在的时间点（秒）
+/// * `limits` - 子进程的资源限制
+///
+/// # 返回
+/// * `Result<RgbFrame, VideoError>` - 与 `video_processor::extract_frame` 相同；
+///   子进程崩溃或被资源限制终止时返回 `DecoderFailed`
+pub fn extract_frame_isolated<P: AsRef<Path>>(
+    input_path: P,
+    time_sec: f64,
+    limits: &SandboxLimits,
+) -> Result<RgbFrame, VideoError> {
+    let helper = resolve_helper(limits)?;
+
+    let mut child = match Command::new(&helper)
+        .arg(input_path.as_ref())
+        .arg(time_sec.to_string())
+        .arg(limits.max_memory_bytes.to_string())
+        .arg(limits.max_cpu_secs.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            return Err(VideoError::new(
+                VideoErrorCode::InitFailed,
+                Some(format!("无法启动沙箱子进程 {}: {}", helper.display(), e)),
+            ))
+        }
+    };
+
+    // 先读完输出再等待退出，避免管道写满导致子进程阻塞
+    let mut output = Vec::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        if let Err(e) = stdout.read_to_end(&mut output) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(VideoError::new(
+                VideoErrorCode::Unknown,
+                Some(format!("读取沙箱子进程输出失败: {}", e)),
+            ));
+        }
+    }
+
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(e) => {
+            return Err(VideoError::new(
+                VideoErrorCode::Unknown,
+                Some(format!("等待沙箱子进程失败: {}", e)),
+            ))
+        }
+    };
+
+    // 子进程被信号终止（段错误、超出CPU/内存限制等）时可能只写出了一部分帧数据，
+    // 非正常退出时只接受子进程主动报告的错误
+    if !status.success() && output.first() != Some(&STATUS_ERR) {
+        return Err(VideoError::new(
+            VideoErrorCode::DecoderFailed,
+            Some(format!("沙箱子进程异常退出: {}", status)),
+        ));
+    }
+
+    decode_response(&output)
+}
+
+/// 沙箱子进程的入口
+///
+/// 参数依次为：输入路径、时间点（秒）、最大内存（字节）、最大CPU时间（秒）。
+/// 返回进程退出码。
+pub fn helper_main(args: &[String]) -> i32 {
+    if args.len() != 4 {
+        eprintln!(
+            "用法: {} <输入文件> <时间点> <最大内存> <最大CPU秒数>",
+            HELPER_NAME
+        );
+        return 2;
+    }
+
+    let time_sec = args[1].parse::<f64>().unwrap_or(f64::NAN);
+    let max_memory_bytes = args[2].parse::<u64>().unwrap_or(0);
+    let max_cpu_secs = args[3].parse::<u64>().unwrap_or(0);
+
+    let result = match apply_limits(max_memory_bytes, max_cpu_secs) {
+        Ok(()) if time_sec.is_finite() => video_processor::extract_frame(&args[0], time_sec),
+        Ok(()) => Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的时间点: {}", args[1])),
+        )),
+        Err(e) => Err(e),
+    };
+
+    let response = encode_response(&result);
+    let mut stdout = std::io::stdout().lock();
+    if stdout
+        .write_all(&response)
+        .and_then(|_| stdout.flush())
+        .is_err()
+    {
+        return 1;
+    }
+
+    match result {
+        Ok(_) => 0,
+        Err(_) => 1,
+    }
+}
+
+// 查找子进程可执行文件
+fn resolve_helper(limits: &SandboxLimits) -> Result<PathBuf, VideoError> {
+    if let Some(path) = &limits.helper_path {
+        return Ok(path.clone());
+    }
+
+    if let Some(path) = std::env::var_os(HELPER_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+
+    // 默认与当前可执行文件放在同一目录下
+    let candidate = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(HELPER_NAME)));
+
+    match candidate {
+        Some(path) if path.exists() => Ok(path),
+        _ => Err(VideoError::new(
+            VideoErrorCode::InitFailed,
+            Some(format!(
+                "未找到沙箱子进程 {}，请设置 {} 或 SandboxLimits::helper_path",
+                HELPER_NAME, HELPER_ENV
+            )),
+        )),
+    }
+}
+
+// 在子进程内设置资源限制
+fn apply_limits(max_memory_bytes: u64, max_cpu_secs: u64) -> Result<(), VideoError> {
+    let limits = [
+        (libc::RLIMIT_AS, max_memory_bytes),
+        (libc::RLIMIT_CPU, max_cpu_secs),
+    ];
+
+    for (resource, value) in limits {
+        if value == 0 {
+            continue;
+        }
+
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+
+        // 安全性：只向 setrlimit 传递栈上有效的结构体指针
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(VideoError::new(
+                VideoErrorCode::InitFailed,
+                Some(format!(
+                    "设置资源限制失败: {}",
+                    std::io::Error::last_os_error()
+                )),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// 序列化子进程的结果：状态字节 + 负载
+///
+/// 成功：[0] + 宽度、高度、存储宽度、存储高度、显示宽度、显示高度(各u32, 小端) + 帧数据；
+/// 失败：[1] + 错误代码(u32, 小端) + UTF-8错误消息
+pub fn encode_response(result: &Result<RgbFrame, VideoError>) -> Vec<u8> {
+    match result {
+        Ok(frame) => {
+            let mut buf = Vec::with_capacity(25 + frame.data.len());
+            buf.push(STATUS_OK);
+            let info = &frame.info;
+            for value in [
+                frame.width,
+                frame.height,
+                info.storage_width,
+                info.storage_height,
+                info.display_width,
+                info.display_height,
+            ] {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            buf.extend_from_slice(&frame.data);
+            buf
+        }
+        Err(e) => {
+            let mut buf = Vec::with_capacity(5 + e.message.len());
+            buf.push(STATUS_ERR);
+            buf.extend_from_slice(&e.code.get_code().to_le_bytes());
+            buf.extend_from_slice(e.message.as_bytes());
+            buf
+        }
+    }
+}
+
+/// 反序列化子进程的输出
+///
+/// 成功结果的帧数据长度必须与宽高对应的RGB24帧一致，被截断的输出返回错误。
+pub fn decode_response(output: &[u8]) -> Result<RgbFrame, VideoError> {
+    match output.split_first() {
+        Some((&STATUS_OK, rest)) if rest.len() >= 24 => {
+            let value =
+                |i: usize| u32::from_le_bytes([rest[i], rest[i + 1], rest[i + 2], rest[i + 3]]);
+            // 子进程使用默认选项抽帧，输出为紧密排列的RGB24
+            let expected = OutputFormat::Rgb24.frame_bytes(value(0), value(4));
+            if rest.len() - 24 != expected {
+                return Err(VideoError::new(
+                    VideoErrorCode::DecoderFailed,
+                    Some(format!(
+                        "沙箱子进程输出的帧数据不完整: {} / {} 字节",
+                        rest.len() - 24,
+                        expected
+                    )),
+                ));
+            }
+            Ok(RgbFrame {
+                width: value(0),
+                height: value(4),
+                data: rest[24..].to_vec(),
+                info: FrameInfo {
+                    storage_width: value(8),
+                    storage_height: value(12),
+                    display_width: value(16),
+                    display_height: value(20),
+                },
+                // 子进程的警告不经过序列化
+                warnings: Vec::new(),
+            })
+        }
+        Some((&STATUS_ERR, rest)) if rest.len() >= 4 => {
+            let code = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            let message = String::from_utf8_lossy(&rest[4..]).into_owned();
+            Err(VideoError::new(
+                VideoErrorCode::from_code(code),
+                Some(message),
+            ))
+        }
+        _ => Err(VideoError::new(
+            VideoErrorCode::Unknown,
+            Some("沙箱子进程返回了无法解析的结果".to_string()),
+        )),
+    }
+}
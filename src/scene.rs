@@ -0,0 +1,231 @@
+// scene.rs
+// 镜头切换检测
+//
+// 缩略图放在镜头开头比放在固定的时间偏移处更有代表性。这里逐帧比较相邻两帧的亮度，
+// 按FFmpeg select滤镜的scene算法打分：两帧平均差异大、并且比前一对帧的差异突然增大时
+// 才是切换，这样快速运动的镜头不会被误判。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::video_processor;
+
+use ffmpeg::util::frame::video::Video;
+use ffmpeg_next as ffmpeg;
+
+// 两次切换之间的最短间隔（秒），闪光等造成的连续切换只保留得分最高的一次
+const MIN_SCENE_GAP: f64 = 0.5;
+
+/// 一次镜头切换
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneCut {
+    /// 新镜头第一帧的时间（秒）
+    pub time: f64,
+    /// 切换的置信度（0.0 - 1.0），与FFmpeg `select='gt(scene,x)'` 中的scene值含义相同
+    pub score: f64,
+}
+
+/// 镜头切换检测的参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneOptions {
+    /// 得分超过该值才算切换，常用 0.3 - 0.4
+    pub threshold: f64,
+    /// 分析时把画面缩小到的宽度（像素），高度按比例计算；None 表示按原始分辨率分析
+    pub analysis_width: Option<u32>,
+}
+
+impl Default for SceneOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.3,
+            analysis_width: Some(160),
+        }
+    }
+}
+
+/// 检测镜头切换，分析时把画面缩小到160像素宽
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `threshold` - 得分超过该值才算切换（0.0 - 1.0），常用 0.3 - 0.4
+///
+/// # 返回
+/// * `Result<Vec<SceneCut>, VideoError>` - 成功时返回按时间排序的切换，不包括第一个镜头的开始
+pub fn detect_scenes<P: AsRef<Path>>(
+    input_path: P,
+    threshold: f64,
+) -> Result<Vec<SceneCut>, VideoError> {
+    let scene_options = SceneOptions {
+        threshold,
+        ..Default::default()
+    };
+    detect_scenes_with_options(input_path, &scene_options)
+}
+
+/// 使用自定义参数检测镜头切换
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `scene_options` - 阈值和分析分辨率
+///
+/// # 返回
+/// * `Result<Vec<SceneCut>, VideoError>` - 成功时返回按时间排序的切换，不包括第一个镜头的开始
+pub fn detect_scenes_with_options<P: AsRef<Path>>(
+    input_path: P,
+    scene_options: &SceneOptions,
+) -> Result<Vec<SceneCut>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    if !(scene_options.threshold > 0.0 && scene_options.threshold <= 1.0) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "切换阈值必须在 0 - 1 之间: {}",
+                scene_options.threshold
+            )),
+        ));
+    }
+    if scene_options.analysis_width == Some(0) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("分析宽度必须大于0".to_string()),
+        ));
+    }
+
+    let options = config::default_options();
+    let mut detector = SceneDetector {
+        threshold: scene_options.threshold,
+        ..Default::default()
+    };
+    video_processor::for_each_video_frame(input_path.as_ref(), &options, 0.0, |frame, time| {
+        let (width, height) = analysis_size(frame, scene_options.analysis_width);
+        let luma = video_processor::convert_to_luma(frame, width, height, &options)?;
+        detector.push(time, luma);
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    Ok(detector.cuts)
+}
+
+// 分析用的尺寸：按宽度等比缩小，不放大
+fn analysis_size(frame: &Video, analysis_width: Option<u32>) -> (u32, u32) {
+    let (width, height) = (frame.width(), frame.height());
+    match analysis_width {
+        Some(target) if target < width => {
+            let scaled = (u64::from(height) * u64::from(target) / u64::from(width)).max(1);
+            (target, scaled as u32)
+        }
+        _ => (width, height),
+    }
+}
+
+// 逐帧打分，记录超过阈值的切换
+#[derive(Default)]
+struct SceneDetector {
+    threshold: f64,
+    previous: Option<Vec<u8>>,
+    // 上一对帧的平均差异（百分比）
+    previous_mafd: f64,
+    cuts: Vec<SceneCut>,
+}
+
+impl SceneDetector {
+    fn push(&mut self, time: f64, luma: Vec<u8>) {
+        let previous = match self.previous.replace(luma) {
+            Some(previous) => previous,
+            None => return,
+        };
+        let current = self.previous.as_deref().unwrap_or_default();
+        // 分辨率中途改变时无法逐像素比较，从新分辨率重新开始
+        if previous.len() != current.len() {
+            self.previous_mafd = 0.0;
+            return;
+        }
+
+        // 与FFmpeg相同：平均差异和差异的突变量取较小者，再换算到 0 - 1
+        let mafd = mean_abs_diff(&previous, current) * 100.0 / 255.0;
+        let score = (mafd.min((mafd - self.previous_mafd).abs()) / 100.0).clamp(0.0, 1.0);
+        self.previous_mafd = mafd;
+        if score <= self.threshold {
+            return;
+        }
+
+        let cut = SceneCut { time, score };
+        match self.cuts.last_mut() {
+            Some(last) if time - last.time < MIN_SCENE_GAP => {
+                if score > last.score {
+                    *last = cut;
+                }
+            }
+            _ => self.cuts.push(cut),
+        }
+    }
+}
+
+// 两张缩略图的平均亮度差（0 - 255）
+fn mean_abs_diff(a: &[u8], b: &[u8]) -> f64 {
+    let diff: u64 = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| u64::from(x.abs_diff(y)))
+        .sum();
+    diff as f64 / a.len().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 依次送入纯色的2x2亮度缩略图
+    fn detect(frames: &[(f64, u8)]) -> Vec<SceneCut> {
+        let mut detector = SceneDetector {
+            threshold: 0.3,
+            ..Default::default()
+        };
+        for &(time, level) in frames {
+            detector.push(time, vec![level; 4]);
+        }
+        detector.cuts
+    }
+
+    // 测试镜头切换的打分：突变记为切换，渐变不算，相隔很近的切换只保留得分最高的一个
+    #[test]
+    fn test_scene_detector() {
+        let cuts = detect(&[
+            (0.0, 0),
+            (0.04, 0),
+            (0.08, 0),
+            (0.12, 255),
+            (0.16, 128),
+            (1.0, 128),
+            (1.04, 138),
+            (1.08, 148),
+            (1.12, 158),
+            (2.0, 0),
+        ]);
+        assert_eq!(cuts.len(), 2);
+        assert_eq!(
+            cuts[0],
+            SceneCut {
+                time: 0.12,
+                score: 1.0
+            }
+        );
+        assert_eq!(cuts[1].time, 2.0);
+        assert!((cuts[1].score - 0.5804).abs() < 1e-3, "{:?}", cuts[1]);
+
+        // 分辨率变化时从新分辨率重新开始比较
+        let mut detector = SceneDetector {
+            threshold: 0.3,
+            ..Default::default()
+        };
+        detector.push(0.0, vec![0; 4]);
+        detector.push(0.04, vec![255; 9]);
+        detector.push(0.08, vec![255; 9]);
+        assert!(detector.cuts.is_empty());
+    }
+}
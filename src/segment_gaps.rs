@@ -0,0 +1,173 @@
+// segment_gaps.rs
+// 分段输入（HLS的TS分段、fMP4分段）边界处的时间戳缺口和重叠检测
+//
+// 相邻分段的时间戳首尾不相接时，按时间点抽帧会落进缺口或两份重复的画面之间，
+// 表现为难以解释的 FrameNotFound。这里只读取数据包、不解码，
+// 比较每个分段视频时间戳的起止，报告边界上的真实问题。
+
+use std::path::Path;
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::temp_storage;
+use crate::video_processor;
+
+use ffmpeg::media::Type;
+use ffmpeg_next as ffmpeg;
+
+// 无法从流信息得到帧间隔时使用的默认值（秒）
+const DEFAULT_FRAME_INTERVAL: f64 = 1.0 / 30.0;
+
+/// 分段边界问题的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryIssueKind {
+    /// 下一段开始得比上一段结束晚，中间没有画面
+    Gap,
+    /// 下一段开始得比上一段结束早，两段有重复的时间范围
+    Overlap,
+}
+
+/// 一个分段边界上的问题
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundaryIssue {
+    /// 问题所在边界之前的分段序号，边界位于第 `index` 段和第 `index + 1` 段之间
+    pub index: usize,
+    /// 种类
+    pub kind: BoundaryIssueKind,
+    /// 上一段最后一帧的结束时间（秒）
+    pub previous_end: f64,
+    /// 下一段第一帧的开始时间（秒）
+    pub next_start: f64,
+    /// 缺口或重叠的长度（秒），总是正数
+    pub amount: f64,
+}
+
+/// 检查按顺序排列的分段之间的时间戳缺口和重叠
+///
+/// 相差不超过半帧的边界视为正常衔接。
+///
+/// # 参数
+/// * `segments` - 按播放顺序排列的分段文件路径
+/// * `init_segment` - fMP4分段的初始化分段（`EXT-X-MAP`），TS分段传 `None`
+///
+/// # 返回
+/// * `Result<Vec<BoundaryIssue>, VideoError>` - 成功时按边界顺序返回发现的问题，没有问题时为空
+pub fn check_segment_boundaries<P: AsRef<Path>>(
+    segments: &[P],
+    init_segment: Option<&Path>,
+) -> Result<Vec<BoundaryIssue>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let init = match init_segment {
+        Some(path) => Some(read_file(path)?),
+        None => None,
+    };
+
+    let mut spans = Vec::with_capacity(segments.len());
+    for segment in segments {
+        let span = match &init {
+            // fMP4分段本身不含moov，需要接在初始化分段后面才能打开
+            Some(init) => {
+                let mut data = init.clone();
+                data.extend_from_slice(&read_file(segment.as_ref())?);
+                let storage = temp_storage::current();
+                let path = storage.store(&data)?;
+                let span = segment_span(&path);
+                storage.remove(&path);
+                span?
+            }
+            None => segment_span(segment.as_ref())?,
+        };
+        spans.push(span);
+    }
+
+    let mut issues = Vec::new();
+    for (index, pair) in spans.windows(2).enumerate() {
+        let (previous, next) = (&pair[0], &pair[1]);
+        let tolerance = previous.frame_interval.min(next.frame_interval) / 2.0;
+        let delta = next.start - previous.end;
+        if delta.abs() <= tolerance {
+            continue;
+        }
+        issues.push(BoundaryIssue {
+            index,
+            kind: if delta > 0.0 {
+                BoundaryIssueKind::Gap
+            } else {
+                BoundaryIssueKind::Overlap
+            },
+            previous_end: previous.end,
+            next_start: next.start,
+            amount: delta.abs(),
+        });
+    }
+
+    Ok(issues)
+}
+
+// 一个分段中视频帧覆盖的时间范围
+struct SegmentSpan {
+    start: f64,
+    end: f64,
+    frame_interval: f64,
+}
+
+// 读取分段中所有视频数据包的时间戳，得到起止时间
+fn segment_span(path: &Path) -> Result<SegmentSpan, VideoError> {
+    let mut ictx = video_processor::open_input(path, &config::default_options())?;
+    let (video_index, time_base, frame_interval) = ictx
+        .streams()
+        .best(Type::Video)
+        .map(|stream| {
+            let rate = stream.avg_frame_rate();
+            let interval = if rate.numerator() > 0 && rate.denominator() > 0 {
+                f64::from(rate.invert())
+            } else {
+                DEFAULT_FRAME_INTERVAL
+            };
+            (stream.index(), f64::from(stream.time_base()), interval)
+        })
+        .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+    let mut range: Option<(f64, f64)> = None;
+    for (index, packet) in video_processor::read_packets(&mut ictx) {
+        if index != video_index {
+            continue;
+        }
+        let ts = match packet.pts().or(packet.dts()) {
+            Some(ts) => ts as f64 * time_base,
+            None => continue,
+        };
+        // TS分段的数据包经常不带时长，按帧间隔估算
+        let duration = if packet.duration() > 0 {
+            packet.duration() as f64 * time_base
+        } else {
+            frame_interval
+        };
+        range = Some(match range {
+            Some((start, end)) => (start.min(ts), end.max(ts + duration)),
+            None => (ts, ts + duration),
+        });
+    }
+
+    let (start, end) = range.ok_or(VideoError::new(
+        VideoErrorCode::FrameNotFound,
+        Some(format!("分段中没有带时间戳的视频数据: {}", path.display())),
+    ))?;
+    Ok(SegmentSpan {
+        start,
+        end,
+        frame_interval,
+    })
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, VideoError> {
+    std::fs::read(path).map_err(|e| {
+        VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无法读取分段 {}: {}", path.display(), e)),
+        )
+    })
+}
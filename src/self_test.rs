@@ -0,0 +1,145 @@
+// self_test.rs
+// 启动时自检
+//
+// 在处理用户文件之前，用内置的极小测试数据走一遍解码和颜色转换，
+// 让构建裁剪、链接错误等集成问题在启动时暴露，而不是在第一个用户文件上才出现。
+
+use crate::ffmpeg_init;
+
+use ffmpeg::{
+    codec,
+    software::scaling::{context::Context, flag::Flags},
+    util::{format::Pixel, frame::video::Video},
+};
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+// 测试图像尺寸，左半边为红色，右半边为蓝色
+const TEST_WIDTH: u32 = 16;
+const TEST_HEIGHT: u32 = 16;
+
+/// 自检结果
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    /// 所有检查是否都通过
+    pub passed: bool,
+    /// 已执行的检查项名称
+    pub checks: Vec<String>,
+    /// 失败的检查项，格式为 `"检查项: 原因"`
+    pub failures: Vec<String>,
+}
+
+impl SelfTestReport {
+    fn record(&mut self, name: &str, result: Result<(), String>) {
+        self.checks.push(name.to_string());
+        if let Err(reason) = result {
+            self.failures.push(format!("{}: {}", name, reason));
+        }
+    }
+}
+
+/// 执行自检
+///
+/// 依次检查：FFmpeg初始化、内置PPM测试图像的解码、YUV到RGB的颜色转换。
+pub fn run_self_test() -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+
+    // 初始化失败会直接panic，这里把它转换为一条失败记录
+    let init = std::panic::catch_unwind(ffmpeg_init::initialize)
+        .map_err(|_| "FFmpeg初始化失败".to_string());
+    let initialized = init.is_ok();
+    report.record("init", init);
+
+    if initialized {
+        report.record("decode", check_decode());
+        report.record("scale", check_scale());
+    }
+
+    report.passed = report.failures.is_empty();
+    report
+}
+
+// 生成内置的PPM测试图像
+fn test_bitstream() -> Vec<u8> {
+    let mut data = format!("P6\n{} {}\n255\n", TEST_WIDTH, TEST_HEIGHT).into_bytes();
+    for _ in 0..TEST_HEIGHT {
+        for x in 0..TEST_WIDTH {
+            if x < TEST_WIDTH / 2 {
+                data.extend_from_slice(&[255, 0, 0]);
+            } else {
+                data.extend_from_slice(&[0, 0, 255]);
+            }
+        }
+    }
+    data
+}
+
+// 用PPM解码器解码内置测试图像，并检查尺寸和像素
+fn check_decode() -> Result<(), String> {
+    let codec = ffmpeg::decoder::find(codec::Id::PPM).ok_or("找不到PPM解码器".to_string())?;
+    let mut decoder = codec::context::Context::new_with_codec(codec)
+        .decoder()
+        .open_as(codec)
+        .and_then(|opened| opened.video())
+        .map_err(|e| format!("无法打开解码器: {}", e))?;
+
+    let packet = ffmpeg::Packet::copy(&test_bitstream());
+    decoder
+        .send_packet(&packet)
+        .map_err(|e| format!("发送数据包失败: {}", e))?;
+    let _ = decoder.send_eof();
+
+    let mut frame = Video::empty();
+    decoder
+        .receive_frame(&mut frame)
+        .map_err(|e| format!("没有解码出帧: {}", e))?;
+
+    if frame.width() != TEST_WIDTH || frame.height() != TEST_HEIGHT {
+        return Err(format!("尺寸不符: {}x{}", frame.width(), frame.height()));
+    }
+    if frame.format() != Pixel::RGB24 {
+        return Err(format!("像素格式不符: {:?}", frame.format()));
+    }
+
+    // 检查左上角和右上角的像素
+    let data = frame.data(0);
+    let right = (TEST_WIDTH as usize - 1) * 3;
+    if data[0..3] != [255, 0, 0] || data[right..right + 3] != [0, 0, 255] {
+        return Err("解码出的像素与测试图像不一致".to_string());
+    }
+
+    Ok(())
+}
+
+// 把一帧中灰YUV420P转换为RGB24，检查缩放器是否可用且结果正确
+fn check_scale() -> Result<(), String> {
+    let mut frame = Video::new(Pixel::YUV420P, TEST_WIDTH, TEST_HEIGHT);
+    for plane in 0..3 {
+        frame.data_mut(plane).fill(128);
+    }
+
+    let mut scaler = Context::get(
+        Pixel::YUV420P,
+        TEST_WIDTH,
+        TEST_HEIGHT,
+        Pixel::RGB24,
+        TEST_WIDTH,
+        TEST_HEIGHT,
+        Flags::BILINEAR,
+    )
+    .map_err(|e| format!("创建缩放器失败: {}", e))?;
+
+    let mut rgb = Video::empty();
+    scaler
+        .run(&frame, &mut rgb)
+        .map_err(|e| format!("颜色转换失败: {}", e))?;
+
+    // 有限范围的Y=128对应RGB约为130，允许少量舍入误差
+    let pixel = &rgb.data(0)[0..3];
+    if pixel.iter().any(|&c| !(126..=134).contains(&c)) {
+        return Err(format!("颜色转换结果异常: {:?}", pixel));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,424 @@
+// session.rs
+// 可复用的视频会话
+//
+// 一次性的抽帧接口每次调用都要重新打开输入、探测容器并创建解码器。
+// 播放器拖动预览时会对同一个文件反复抽帧，会话只打开一次输入和解码器，
+// 之后每次抽帧只需要定位和解码。
+//
+// 逐帧输出结果的批量接口通过事件通知JavaScript：用 `on` 注册回调，
+// 和浏览器的EventTarget一样，不需要轮询也不需要给每个接口单独传回调。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config;
+use crate::error::{log_error, VideoError, VideoErrorCode, VideoResult};
+use crate::ffmpeg_init;
+use crate::options::{ExtractOptions, Position};
+use crate::temp_storage::{self, TempStorage};
+use crate::validation;
+use crate::video_processor::{self, RgbFrame};
+
+use ffmpeg::format::context::Input;
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const SESSION_EVENTS_TS: &'static str = r#"
+/** VideoSession 的事件名称 */
+export type SessionEventType = "progress" | "frame" | "error" | "indexed";
+
+/**
+ * VideoSession 的事件回调
+ *
+ * 参数依次为：progress - SessionProgress，frame - SessionFrame，
+ * error - Error，indexed - SessionIndex
+ */
+export type SessionListener = (event: any) => void;
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    /// JavaScript侧注册的事件回调
+    #[wasm_bindgen(typescript_type = "SessionListener")]
+    pub type SessionListener;
+
+    // 通过 Function.prototype.call 调用；回调抛出的异常不会穿过WASM
+    #[wasm_bindgen(method, catch)]
+    fn call(
+        this: &SessionListener,
+        this_arg: &JsValue,
+        event: &JsValue,
+    ) -> Result<JsValue, JsValue>;
+}
+
+/// 会话的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SessionEvent {
+    /// 批量抽帧每完成一帧（无论成功与否）
+    Progress,
+    /// 批量抽帧得到一帧
+    Frame,
+    /// 批量接口中的某一步失败
+    Error,
+    /// 关键帧索引建立完成
+    Indexed,
+}
+
+impl SessionEvent {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "progress" => Some(SessionEvent::Progress),
+            "frame" => Some(SessionEvent::Frame),
+            "error" => Some(SessionEvent::Error),
+            "indexed" => Some(SessionEvent::Indexed),
+            _ => None,
+        }
+    }
+}
+
+/// `progress` 事件的数据
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionProgress {
+    /// 已经处理的数量
+    pub completed: u32,
+    /// 总数量
+    pub total: u32,
+}
+
+/// `frame` 事件的数据
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionFrame {
+    /// 在请求的时间点列表中的序号
+    pub index: u32,
+    /// 请求的时间点（秒）
+    pub time: f64,
+    /// 帧数据
+    pub data: Vec<u8>,
+    /// 宽度（像素）
+    pub width: u32,
+    /// 高度（像素）
+    pub height: u32,
+}
+
+/// `indexed` 事件的数据
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionIndex {
+    /// 所有关键帧的时间（秒），从小到大排列
+    pub keyframes: Vec<f64>,
+}
+
+/// 会话打开的视频的基本信息
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionMetadata {
+    /// 时长（秒），无法确定时为 `None`
+    pub duration: Option<f64>,
+    /// 解码后的宽度（像素）
+    pub width: u32,
+    /// 解码后的高度（像素）
+    pub height: u32,
+    /// 平均帧率，容器没有声明时为 `None`
+    pub frame_rate: Option<f64>,
+    /// 编解码器名称，例如 "h264"
+    pub codec: String,
+}
+
+/// 可复用的视频会话
+///
+/// 打开后可以反复调用 `frame_at` 抽帧，用完调用 `close` 或直接丢弃。
+#[wasm_bindgen]
+pub struct VideoSession {
+    state: Option<SessionState>,
+    // 只在WASM接口中使用，关闭会话后仍然保留
+    listeners: Vec<(SessionEvent, SessionListener)>,
+}
+
+struct SessionState {
+    ictx: Input,
+    decoder: ffmpeg::decoder::Video,
+    video_stream_index: usize,
+    options: ExtractOptions,
+    metadata: SessionMetadata,
+    // 内存输入落地的临时文件；放在最后，输入关闭之后才删除
+    temp_file: Option<TempFile>,
+}
+
+// 会话结束时删除的临时文件
+struct TempFile {
+    storage: Arc<dyn TempStorage>,
+    path: PathBuf,
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        self.storage.remove(&self.path);
+    }
+}
+
+impl VideoSession {
+    /// 打开视频文件，使用全局默认选项
+    pub fn open<P: AsRef<Path>>(input_path: P) -> Result<Self, VideoError> {
+        Self::open_with_options(input_path, &config::default_options())
+    }
+
+    /// 使用自定义选项打开视频文件
+    pub fn open_with_options<P: AsRef<Path>>(
+        input_path: P,
+        options: &ExtractOptions,
+    ) -> Result<Self, VideoError> {
+        // 确保FFmpeg已初始化
+        ffmpeg_init::initialize();
+
+        let state = SessionState::open(input_path.as_ref(), options.clone(), None)?;
+        Ok(Self {
+            state: Some(state),
+            listeners: Vec::new(),
+        })
+    }
+
+    /// 打开内存中的视频数据
+    ///
+    /// 数据写入当前的临时存储后端，会话关闭时删除。
+    pub fn open_memory(input_data: &[u8], options: &ExtractOptions) -> Result<Self, VideoError> {
+        // 确保FFmpeg已初始化
+        ffmpeg_init::initialize();
+
+        validation::validate_buffer(input_data)?;
+
+        // 打开失败时临时文件随 `TempFile` 一起删除
+        let storage = temp_storage::current();
+        let path = storage.store(input_data)?;
+        let temp_file = TempFile { storage, path };
+        let state = SessionState::open(&temp_file.path.clone(), options.clone(), Some(temp_file))?;
+        Ok(Self {
+            state: Some(state),
+            listeners: Vec::new(),
+        })
+    }
+
+    /// 提取特定位置的帧，格式与 `video_processor::extract_frame_with_options` 相同
+    pub fn frame_at<T: Into<Position>>(&mut self, position: T) -> Result<RgbFrame, VideoError> {
+        self.state_mut()?.frame_at(position.into())
+    }
+
+    /// 读取一遍视频流，返回所有关键帧的时间（秒），从小到大排列
+    ///
+    /// 只读取数据包，不解码。之后的抽帧会重新定位，不受影响。
+    pub fn keyframes(&mut self) -> Result<Vec<f64>, VideoError> {
+        self.state_mut()?.keyframes()
+    }
+
+    fn state_mut(&mut self) -> Result<&mut SessionState, VideoError> {
+        self.state.as_mut().ok_or(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("会话已关闭".to_string()),
+        ))
+    }
+
+    // 依次调用注册了该事件的回调；回调抛出的异常只记录日志，不影响其他回调
+    fn emit(&self, event: SessionEvent, payload: JsValue) {
+        for (_, listener) in self.listeners.iter().filter(|(e, _)| *e == event) {
+            if let Err(e) = listener.call(&JsValue::UNDEFINED, &payload) {
+                log_error(&VideoError::new(
+                    VideoErrorCode::Unknown,
+                    Some(format!("事件回调抛出异常: {:?}", e)),
+                ));
+            }
+        }
+    }
+
+    fn emit_error(&self, e: &VideoError) {
+        log_error(e);
+        self.emit(SessionEvent::Error, JsError::new(&e.to_string()).into());
+    }
+}
+
+#[wasm_bindgen]
+impl VideoSession {
+    /// 打开内存中的视频数据，使用全局默认选项；失败时抛出异常
+    #[wasm_bindgen(js_name = open)]
+    pub fn open_js(input_data: &[u8]) -> Result<VideoSession, JsError> {
+        Self::open_memory(input_data, &config::default_options()).map_err(|e| {
+            log_error(&e);
+            JsError::new(&e.to_string())
+        })
+    }
+
+    /// 提取特定时间点（秒）的帧
+    #[wasm_bindgen(js_name = getFrame)]
+    pub fn get_frame(&mut self, time_sec: f64) -> VideoResult {
+        match self.frame_at(time_sec) {
+            Ok(frame) => VideoResult::success(frame.data, frame.width, frame.height)
+                .with_warnings(frame.warnings),
+            Err(e) => {
+                log_error(&e);
+                VideoResult::error(e.code, &e.message)
+            }
+        }
+    }
+
+    /// 视频的基本信息；会话关闭后返回 `undefined`
+    pub fn metadata(&self) -> Option<SessionMetadata> {
+        self.state.as_ref().map(|state| state.metadata.clone())
+    }
+
+    /// 关闭会话，释放输入和解码器；之后的抽帧都会返回错误
+    pub fn close(&mut self) {
+        self.state = None;
+    }
+
+    /// 注册事件回调，事件名称为 "progress"、"frame"、"error" 或 "indexed"；
+    /// 名称无效时抛出异常
+    ///
+    /// 回调在批量接口执行期间同步调用，回调中不能再调用同一个会话的方法。
+    pub fn on(&mut self, event: &str, listener: SessionListener) -> Result<(), JsError> {
+        let event = SessionEvent::parse(event)
+            .ok_or_else(|| JsError::new(&format!("未知的事件名称: {}", event)))?;
+        self.listeners.push((event, listener));
+        Ok(())
+    }
+
+    /// 移除用 `on` 注册的回调，同一个回调注册了多次时全部移除
+    pub fn off(&mut self, event: &str, listener: &SessionListener) {
+        if let Some(event) = SessionEvent::parse(event) {
+            let target: &JsValue = listener;
+            self.listeners
+                .retain(|(e, l)| !(*e == event && **l == *target));
+        }
+    }
+
+    /// 依次提取多个时间点（秒）的帧，结果通过事件返回
+    ///
+    /// 每得到一帧触发 "frame"，某一帧失败时触发 "error" 并继续处理后面的时间点，
+    /// 每处理完一个时间点触发 "progress"。返回成功提取的帧数。
+    #[wasm_bindgen(js_name = extractFrames)]
+    pub fn extract_frames(&mut self, times: &[f64]) -> u32 {
+        let total = times.len() as u32;
+        let mut extracted = 0;
+        for (index, &time) in times.iter().enumerate() {
+            match self.frame_at(time) {
+                Ok(frame) => {
+                    extracted += 1;
+                    let payload = SessionFrame {
+                        index: index as u32,
+                        time,
+                        data: frame.data,
+                        width: frame.width,
+                        height: frame.height,
+                    };
+                    self.emit(SessionEvent::Frame, payload.into());
+                }
+                Err(e) => self.emit_error(&e),
+            }
+            let progress = SessionProgress {
+                completed: index as u32 + 1,
+                total,
+            };
+            self.emit(SessionEvent::Progress, progress.into());
+        }
+        extracted
+    }
+
+    /// 建立关键帧索引，完成时触发 "indexed"，失败时触发 "error"
+    #[wasm_bindgen(js_name = buildIndex)]
+    pub fn build_index(&mut self) {
+        match self.keyframes() {
+            Ok(keyframes) => self.emit(SessionEvent::Indexed, SessionIndex { keyframes }.into()),
+            Err(e) => self.emit_error(&e),
+        }
+    }
+}
+
+impl SessionState {
+    fn open(
+        path: &Path,
+        options: ExtractOptions,
+        temp_file: Option<TempFile>,
+    ) -> Result<Self, VideoError> {
+        let mut ictx = video_processor::open_input(path, &options)?;
+        let duration = video_processor::resolve_duration(&mut ictx);
+
+        let (video_stream_index, decoder, frame_rate, codec) = {
+            let video_stream_index = video_processor::select_video_stream(&ictx, &options)?;
+            let stream = ictx
+                .stream(video_stream_index)
+                .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+            let decoder = video_processor::open_decoder(&stream, &options)?;
+            let rate = stream.avg_frame_rate();
+            let frame_rate =
+                (rate.numerator() > 0 && rate.denominator() > 0).then(|| f64::from(rate));
+            let codec = stream.parameters().id().name().to_string();
+            (video_stream_index, decoder, frame_rate, codec)
+        };
+
+        let metadata = SessionMetadata {
+            duration,
+            width: decoder.width(),
+            height: decoder.height(),
+            frame_rate,
+            codec,
+        };
+
+        Ok(Self {
+            ictx,
+            decoder,
+            video_stream_index,
+            options,
+            metadata,
+            temp_file,
+        })
+    }
+
+    fn frame_at(&mut self, position: Position) -> Result<RgbFrame, VideoError> {
+        if let Position::Seconds(time_sec) = position {
+            validation::validate_time(time_sec)?;
+        }
+        let time_sec = video_processor::resolve_position(&mut self.ictx, position)?;
+
+        // 丢弃上一次解码残留的帧；修复模式从头顺序解码，需要回到开头
+        self.decoder.flush();
+        if self.options.repair_timestamps {
+            let _ = self.ictx.seek(0, ..);
+        }
+
+        video_processor::decode_with(
+            &mut self.ictx,
+            &mut self.decoder,
+            self.video_stream_index,
+            time_sec,
+            &self.options,
+            false,
+        )?
+        .to_rgb_frame(&self.options)
+    }
+
+    fn keyframes(&mut self) -> Result<Vec<f64>, VideoError> {
+        let video_index = self.video_stream_index;
+        let time_base = self
+            .ictx
+            .stream(video_index)
+            .map(|stream| f64::from(stream.time_base()))
+            .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+        // 从头读取；之后的抽帧总会重新定位
+        if let Err(e) = self.ictx.seek(0, ..) {
+            return Err(VideoError::new(
+                VideoErrorCode::SeekFailed,
+                Some(format!("无法回到视频开头: {}", e)),
+            ));
+        }
+
+        let mut keyframes: Vec<f64> = video_processor::read_packets(&mut self.ictx)
+            .filter(|(index, packet)| *index == video_index && packet.is_key())
+            .filter_map(|(_, packet)| packet.pts().or(packet.dts()))
+            .map(|ts| ts as f64 * time_base)
+            .collect();
+        keyframes.sort_by(f64::total_cmp);
+        keyframes.dedup();
+        Ok(keyframes)
+    }
+}
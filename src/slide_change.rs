@@ -0,0 +1,108 @@
+// slide_change.rs
+// 屏幕录制和课程录像的换页检测
+//
+// 演示文稿的画面大部分时间完全静止，换页时整屏内容突然改变。
+// 这里按固定间隔取样，与当前页比较变化像素的比例，变化足够大且新画面
+// 随后稳定下来时记为一次换页。鼠标移动、逐条出现的要点等局部变化不会被当成换页。
+
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::config;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::video_processor;
+
+// 比较用的亮度缩略图尺寸
+const THUMB_WIDTH: u32 = 128;
+const THUMB_HEIGHT: u32 = 72;
+
+// 两次取样之间的间隔（秒）
+const SAMPLE_INTERVAL: f64 = 0.5;
+
+// 亮度差超过该值的像素视为发生了变化，低于它的差异来自压缩噪声
+const PIXEL_DELTA: u8 = 24;
+
+// 与当前页相比变化像素超过该比例时视为可能换页
+const CHANGE_RATIO: f64 = 0.3;
+
+// 与上一个样本相比变化像素低于该比例时视为画面已经稳定
+const STABLE_RATIO: f64 = 0.02;
+
+/// 检测演示文稿类视频的换页时刻
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<Vec<f64>, VideoError>` - 成功时返回按时间排序的换页时刻（秒），不包括第一页的开始；
+///   换页带有过渡动画时返回过渡开始的时刻
+pub fn detect_slide_changes<P: AsRef<Path>>(input_path: P) -> Result<Vec<f64>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let options = config::default_options();
+    let mut detector = SlideDetector::default();
+    let mut next_sample = f64::NEG_INFINITY;
+    video_processor::for_each_video_frame(input_path.as_ref(), &options, 0.0, |frame, time| {
+        if time >= next_sample {
+            next_sample = time + SAMPLE_INTERVAL;
+            let luma =
+                video_processor::convert_to_luma(frame, THUMB_WIDTH, THUMB_HEIGHT, &options)?;
+            detector.push(time, luma);
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    Ok(detector.changes)
+}
+
+// 与当前页比较，确认稳定后记录换页
+#[derive(Default)]
+struct SlideDetector {
+    // 当前页的缩略图
+    current: Option<Vec<u8>>,
+    // 正在过渡中的候选页：开始变化的时间和最近一个样本
+    candidate: Option<(f64, Vec<u8>)>,
+    changes: Vec<f64>,
+}
+
+impl SlideDetector {
+    fn push(&mut self, time: f64, luma: Vec<u8>) {
+        let current = match &self.current {
+            Some(current) => current,
+            None => {
+                self.current = Some(luma);
+                return;
+            }
+        };
+
+        if let Some((start, previous)) = self.candidate.take() {
+            if changed_ratio(&previous, &luma) < STABLE_RATIO {
+                // 画面稳定下来；回到原来的页面时不算换页
+                if changed_ratio(current, &luma) >= CHANGE_RATIO {
+                    self.changes.push(start);
+                    self.current = Some(luma);
+                }
+            } else {
+                // 仍在过渡或播放视频，保留开始变化的时间
+                self.candidate = Some((start, luma));
+            }
+            return;
+        }
+
+        if changed_ratio(current, &luma) >= CHANGE_RATIO {
+            self.candidate = Some((time, luma));
+        }
+    }
+}
+
+// 两张缩略图之间发生变化的像素比例
+fn changed_ratio(a: &[u8], b: &[u8]) -> f64 {
+    let changed = a
+        .iter()
+        .zip(b)
+        .filter(|(&x, &y)| x.abs_diff(y) > PIXEL_DELTA)
+        .count();
+    changed as f64 / a.len().max(1) as f64
+}
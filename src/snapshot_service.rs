@@ -0,0 +1,243 @@
+// snapshot_service.rs
+// 定时快照服务（原生平台）
+//
+// 对直播流等持续输入，在后台线程中一直解码，每隔固定的时间输出一帧，
+// 直到被停止。适合做直播预览小窗，不必反复调用一次性的抽帧接口重新打开输入。
+
+use std::ffi::{c_int, c_void};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::options::ExtractOptions;
+use crate::video_processor;
+
+use ffmpeg::{ffi, util::frame::video::Video};
+use ffmpeg_next as ffmpeg;
+
+/// 一次快照
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// 帧的时间（秒），流没有时间戳时为服务启动后经过的时间
+    pub time: f64,
+    /// 输出宽度（像素）
+    pub width: u32,
+    /// 输出高度（像素）
+    pub height: u32,
+    /// 帧数据，格式由 `ExtractOptions::output_format` 决定，默认为RGB24
+    pub data: Vec<u8>,
+}
+
+/// 定时快照服务
+///
+/// 丢弃时自动停止并等待后台线程退出；阻塞中的网络读取会被打断，不会一直等待。
+pub struct SnapshotService {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SnapshotService {
+    /// 启动服务，每隔 `interval_secs` 秒通过回调输出一帧
+    ///
+    /// 打开输入或解码失败时回调收到错误，随后服务结束；输入到达结尾时服务同样结束。
+    ///
+    /// # 参数
+    /// * `input` - 输入文件路径或FFmpeg支持的URL
+    /// * `interval_secs` - 两次快照之间的间隔（秒）
+    /// * `options` - 打开输入和解码器时使用的选项
+    /// * `on_snapshot` - 在后台线程中调用的回调
+    pub fn start<F>(
+        input: impl AsRef<Path>,
+        interval_secs: f64,
+        options: ExtractOptions,
+        mut on_snapshot: F,
+    ) -> Self
+    where
+        F: FnMut(Result<Snapshot, VideoError>) + Send + 'static,
+    {
+        Self::spawn(input, interval_secs, options, move |result| {
+            on_snapshot(result);
+            true
+        })
+    }
+
+    /// 启动服务，通过通道接收快照
+    ///
+    /// 接收端被丢弃后服务自动停止。
+    pub fn start_channel(
+        input: impl AsRef<Path>,
+        interval_secs: f64,
+        options: ExtractOptions,
+    ) -> (Self, Receiver<Result<Snapshot, VideoError>>) {
+        let (sender, receiver) = mpsc::channel();
+        let service = Self::spawn(input, interval_secs, options, move |result| {
+            sender.send(result).is_ok()
+        });
+        (service, receiver)
+    }
+
+    /// 服务是否仍在运行
+    pub fn is_running(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// 停止服务并等待后台线程退出
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn spawn<F>(
+        input: impl AsRef<Path>,
+        interval_secs: f64,
+        options: ExtractOptions,
+        emit: F,
+    ) -> Self
+    where
+        F: FnMut(Result<Snapshot, VideoError>) -> bool + Send + 'static,
+    {
+        let input = input.as_ref().to_path_buf();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let handle = thread::spawn(move || {
+            run(&input, interval_secs, &options, &thread_stop, emit);
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SnapshotService {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+// 后台线程：持续解码并按间隔输出快照
+// `emit` 返回 false 表示接收方已不再需要快照
+fn run<F>(
+    input: &Path,
+    interval_secs: f64,
+    options: &ExtractOptions,
+    stop: &AtomicBool,
+    mut emit: F,
+) where
+    F: FnMut(Result<Snapshot, VideoError>) -> bool,
+{
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    if !interval_secs.is_finite() || interval_secs <= 0.0 {
+        emit(Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的快照间隔: {}", interval_secs)),
+        )));
+        return;
+    }
+
+    // 停止时打断阻塞中的打开和读取，直播流连接很慢或卡住时不会一直等下去
+    // `stop` 比格式上下文活得更久，回调只读取它
+    let interrupt_callback = ffi::AVIOInterruptCB {
+        callback: Some(interrupt),
+        opaque: stop as *const AtomicBool as *mut c_void,
+    };
+    let opened = video_processor::open_interruptible_input(input, options, interrupt_callback);
+    let mut ictx = match opened {
+        Ok(ictx) => ictx,
+        Err(e) => {
+            emit(Err(e));
+            return;
+        }
+    };
+
+    let (video_stream_index, time_base, mut decoder) = {
+        let opened = video_processor::select_video_stream(&ictx, options).and_then(|index| {
+            let stream = ictx
+                .stream(index)
+                .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+            let decoder = video_processor::open_decoder(&stream, options)?;
+            Ok((index, f64::from(stream.time_base()), decoder))
+        });
+        match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                emit(Err(e));
+                return;
+            }
+        }
+    };
+
+    let started = Instant::now();
+    let mut next_time: Option<f64> = None;
+    let mut decoded_frame = Video::empty();
+
+    // 遇到结尾、中断或其他读取错误都结束服务；停止后中断回调让每次读取都失败
+    for (index, packet) in video_processor::read_packets(&mut ictx) {
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        if index != video_stream_index {
+            continue;
+        }
+
+        // 直播流中偶尔出现的损坏数据包直接跳过，不中断服务
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            let time = match decoded_frame.timestamp() {
+                Some(ts) => ts as f64 * time_base,
+                None => started.elapsed().as_secs_f64(),
+            };
+
+            // 时间戳回退（例如直播流重启）时重新开始计时
+            let due = match next_time {
+                Some(next) => time >= next || time + interval_secs < next - interval_secs,
+                None => true,
+            };
+            if !due {
+                continue;
+            }
+            next_time = Some(time + interval_secs);
+
+            if !emit(snapshot(&decoded_frame, time, options)) {
+                return;
+            }
+        }
+    }
+}
+
+// FFmpeg阻塞读取时定期调用，返回非零值表示中止
+unsafe extern "C" fn interrupt(opaque: *mut c_void) -> c_int {
+    let stop = &*(opaque as *const AtomicBool);
+    stop.load(Ordering::SeqCst) as c_int
+}
+
+// 把解码后的帧转换为快照
+fn snapshot(frame: &Video, time: f64, options: &ExtractOptions) -> Result<Snapshot, VideoError> {
+    let rgb = video_processor::to_rgb_frame(frame, options)?;
+    Ok(Snapshot {
+        time,
+        width: rgb.width,
+        height: rgb.height,
+        data: rgb.data,
+    })
+}
@@ -0,0 +1,259 @@
+// sprite_sheet.rs
+// 进度条预览用的雪碧图和WebVTT缩略图轨道
+//
+// HTML5播放器的进度条预览通常使用一张拼好的缩略图大图，加上一个WebVTT文件，
+// 每条cue把一段播放时间映射到大图中的一个区域（`图片地址#xywh=x,y,w,h`）。
+// 逐帧调用抽帧接口需要对每个时间点重新定位和解码，这里在一次顺序解码中取出
+// 所有缩略图，拼图后编码为图片，同时生成对应的WebVTT。
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::encode;
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::memory_watch;
+use crate::options::{ExtractOptions, OutputEncoding, OutputFormat};
+use crate::storyboard;
+use crate::validation;
+use crate::video_processor::{self, ThumbnailSpacing};
+
+use wasm_bindgen::prelude::*;
+
+/// 雪碧图及其WebVTT缩略图轨道
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteSheet {
+    /// 编码后的雪碧图文件内容
+    pub image: Vec<u8>,
+    /// 雪碧图宽度（像素）
+    pub width: u32,
+    /// 雪碧图高度（像素），最后一行未填满的格子为黑色
+    pub height: u32,
+    /// 每格的宽度（像素）
+    pub tile_width: u32,
+    /// 每格的高度（像素），按视频的显示宽高比计算
+    pub tile_height: u32,
+    /// WebVTT文件内容，每条cue对应一格
+    pub vtt: String,
+}
+
+/// 生成进度条预览用的雪碧图和WebVTT
+///
+/// 每隔 `interval_secs` 秒取一帧，只解码一遍。格子按时间从左到右、从上到下排列，
+/// 第 `i` 条cue覆盖从该格的时间到下一格的时间，最后一条延续一个间隔。
+/// cue的时间从视频开头的0秒算起。
+/// 整张图的宽高不能超过 `validation::MAX_DIMENSION`，能确定时长时在解码之前就检查，
+/// 长视频需要相应加大间隔。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `interval_secs` - 相邻两格之间的时间间隔（秒）
+/// * `tile_width` - 每格的宽度（像素），高度按显示宽高比计算
+/// * `columns` - 每行的格数
+/// * `image_url` - WebVTT中引用雪碧图时使用的地址，例如 "sprite.jpg"
+/// * `encoding` - 雪碧图的编码格式，不能为 `Raw`；JPEG质量取自 `options.encoding_quality`
+/// * `options` - 打开输入和解码器时使用的选项，输出尺寸和格式由本函数决定
+///
+/// # 返回
+/// * `Result<SpriteSheet, VideoError>` - 成功时返回编码后的雪碧图和WebVTT
+pub fn generate_sprite_sheet<P: AsRef<Path>>(
+    input_path: P,
+    interval_secs: f64,
+    tile_width: u32,
+    columns: u32,
+    image_url: &str,
+    encoding: OutputEncoding,
+    options: &ExtractOptions,
+) -> Result<SpriteSheet, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    if columns == 0 || encoding == OutputEncoding::Raw {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("列数必须大于0，且雪碧图必须编码为图片".to_string()),
+        ));
+    }
+    validation::validate_dimensions(tile_width as i32, 1)?;
+
+    let mut tile_options = options.for_analysis(OutputFormat::Rgb24);
+    tile_options.output_width = Some(tile_width);
+    tile_options.output_height = None;
+    tile_options.transform = Default::default();
+    tile_options.max_output_bytes = None;
+
+    // 事先确定格子高度并固定下来，中途分辨率变化时每格的尺寸仍然一致；
+    // 能确定时长时按格数检查整张图的尺寸，超出上限时不必解码整个文件才失败
+    let (tile_height, duration) = {
+        let mut ictx = video_processor::open_input(input_path.as_ref(), &tile_options)?;
+        let estimate = video_processor::estimate_input_output(&ictx, &tile_options)?;
+        (
+            estimate.height,
+            video_processor::resolve_duration(&mut ictx),
+        )
+    };
+    tile_options.output_height = Some(tile_height);
+    if let Some(duration) = duration.filter(|_| interval_secs.is_finite() && interval_secs > 0.0) {
+        let count = (duration / interval_secs).ceil() as u32;
+        sheet_size(count.max(1), columns, tile_width, tile_height)?;
+    }
+
+    let thumbnails = video_processor::extract_thumbnails(
+        &input_path,
+        ThumbnailSpacing::Interval(interval_secs),
+        &tile_options,
+    )?;
+    let origin = match thumbnails.first() {
+        Some(first) => first.requested_time,
+        None => {
+            return Err(VideoError::new(
+                VideoErrorCode::FrameNotFound,
+                Some("视频中没有可用的帧".to_string()),
+            ))
+        }
+    };
+
+    // 拼图
+    let count = thumbnails.len() as u32;
+    let (width, height) = sheet_size(count, columns, tile_width, tile_height)?;
+    let canvas_bytes = width as usize * height as usize * 3;
+    memory_watch::reserve(canvas_bytes)?;
+    let mut data = vec![0u8; canvas_bytes];
+
+    for (index, thumbnail) in thumbnails.iter().enumerate() {
+        let x = (index as u32 % columns) * tile_width;
+        let y = (index as u32 / columns) * tile_height;
+        storyboard::blit(&mut data, width, &thumbnail.frame, x, y);
+    }
+    let times: Vec<f64> = thumbnails
+        .iter()
+        .map(|thumbnail| thumbnail.requested_time - origin)
+        .collect();
+    let vtt = build_vtt(
+        &times,
+        interval_secs,
+        columns,
+        tile_width,
+        tile_height,
+        image_url,
+    );
+
+    let image = encode::encode_frame(
+        data,
+        width,
+        height,
+        OutputFormat::Rgb24,
+        encoding,
+        options.encoding_quality,
+    )?;
+
+    Ok(SpriteSheet {
+        image,
+        width,
+        height,
+        tile_width,
+        tile_height,
+        vtt,
+    })
+}
+
+// `count` 格按每行 `columns` 格拼成的整张图的尺寸，超出单张图片的上限时返回错误
+fn sheet_size(
+    count: u32,
+    columns: u32,
+    tile_width: u32,
+    tile_height: u32,
+) -> Result<(u32, u32), VideoError> {
+    let width = u64::from(tile_width) * u64::from(columns.min(count));
+    let height = u64::from(tile_height) * u64::from(count.div_ceil(columns));
+    if width > u64::from(validation::MAX_DIMENSION)
+        || height > u64::from(validation::MAX_DIMENSION)
+        || width * height > validation::MAX_PIXELS
+    {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "雪碧图尺寸 {}x{} 超出上限，请加大间隔或调整列数和格子宽度",
+                width, height
+            )),
+        ));
+    }
+    Ok((width as u32, height as u32))
+}
+
+// 按拼图顺序为每格生成一条cue：`times` 为各格相对第一格的时间，最后一格持续一个间隔
+fn build_vtt(
+    times: &[f64],
+    interval_secs: f64,
+    columns: u32,
+    tile_width: u32,
+    tile_height: u32,
+    image_url: &str,
+) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    for (index, &start) in times.iter().enumerate() {
+        let x = (index as u32 % columns) * tile_width;
+        let y = (index as u32 / columns) * tile_height;
+        let end = times
+            .get(index + 1)
+            .copied()
+            .unwrap_or(start + interval_secs);
+        // 写入String不会失败
+        let _ = write!(
+            vtt,
+            "\n{} --> {}\n{}#xywh={},{},{},{}\n",
+            vtt_timestamp(start),
+            vtt_timestamp(end),
+            image_url,
+            x,
+            y,
+            tile_width,
+            tile_height
+        );
+    }
+    vtt
+}
+
+// WebVTT的时间戳格式：HH:MM:SS.mmm
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试WebVTT的cue：时间段首尾相接，区域按行优先排列
+    #[test]
+    fn test_build_vtt() {
+        let vtt = build_vtt(&[0.0, 2.0, 4.0], 2.0, 2, 160, 90, "sprite.jpg");
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\
+             \n00:00:00.000 --> 00:00:02.000\nsprite.jpg#xywh=0,0,160,90\n\
+             \n00:00:02.000 --> 00:00:04.000\nsprite.jpg#xywh=160,0,160,90\n\
+             \n00:00:04.000 --> 00:00:06.000\nsprite.jpg#xywh=0,90,160,90\n"
+        );
+
+        assert_eq!(vtt_timestamp(3723.4567), "01:02:03.457");
+        assert_eq!(vtt_timestamp(-1.0), "00:00:00.000");
+    }
+
+    // 测试整张图的尺寸：最后一行不满时按整行计算，超出单张图片的上限时返回错误
+    #[test]
+    fn test_sheet_size() {
+        assert_eq!(sheet_size(3, 2, 160, 90).unwrap(), (320, 180));
+        assert_eq!(sheet_size(1, 10, 160, 90).unwrap(), (160, 90));
+        // 一小时的视频每秒一格，10列需要360行
+        assert!(sheet_size(3600, 10, 160, 90).is_err());
+        assert!(sheet_size(u32::MAX, 1, 160, 90).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+// storyboard.rs
+// 跨多个片段的故事板（缩略图拼图）
+//
+// 分集或拼接的内容由多个文件按顺序组成，逐个文件生成的故事板时间轴互不相连。
+// 这里把播放列表当成一条连续的时间轴，按固定间隔取帧，每个片段的时间偏移在内部处理，
+// 所有缩略图拼成一张图，并记录每一格对应的片段和时间。
+
+use std::path::Path;
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::ffmpeg_init;
+use crate::memory_watch;
+use crate::options::{ExtractOptions, OutputFormat};
+use crate::validation;
+use crate::video_processor::{self, RgbFrame};
+
+/// 故事板中的一格
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StoryboardTile {
+    /// 所在片段在播放列表中的序号
+    pub clip: usize,
+    /// 片段内的时间（秒）
+    pub clip_time: f64,
+    /// 整个播放列表时间轴上的时间（秒）
+    pub time: f64,
+    /// 在拼图中的左上角横坐标（像素）
+    pub x: u32,
+    /// 在拼图中的左上角纵坐标（像素）
+    pub y: u32,
+}
+
+/// 拼好的故事板
+#[derive(Debug, Clone, PartialEq)]
+pub struct Storyboard {
+    /// 整张拼图，RGB24格式，最后一行未填满的格子为黑色
+    pub image: RgbFrame,
+    /// 每格的宽度（像素）
+    pub tile_width: u32,
+    /// 每格的高度（像素）
+    pub tile_height: u32,
+    /// 每行的格数
+    pub columns: u32,
+    /// 按时间顺序排列的格子
+    pub tiles: Vec<StoryboardTile>,
+    /// 每个片段在播放列表时间轴上的起始时间（秒）
+    pub clip_offsets: Vec<f64>,
+}
+
+/// 为按顺序播放的多个片段生成一张连续的故事板
+///
+/// 每隔 `interval_secs` 秒取一格，取该时间段中点的帧，跨越片段边界时自动换到下一个片段。
+/// 拼图的总像素数不能超过 `validation::MAX_PIXELS`，超出时在取帧之前返回错误。
+///
+/// # 参数
+/// * `inputs` - 按播放顺序排列的视频文件路径
+/// * `interval_secs` - 相邻两格之间的时间间隔（秒）
+/// * `tile_width` / `tile_height` - 每格的尺寸（像素），帧按该尺寸缩放
+/// * `columns` - 每行的格数
+/// * `options` - 打开输入和解码器时使用的选项，输出尺寸、格式和编码由本函数决定
+///
+/// # 返回
+/// * `Result<Storyboard, VideoError>` - 成功时返回拼图和每格的位置信息，任何片段失败时返回错误
+pub fn playlist_storyboard<P: AsRef<Path>>(
+    inputs: &[P],
+    interval_secs: f64,
+    tile_width: u32,
+    tile_height: u32,
+    columns: u32,
+    options: &ExtractOptions,
+) -> Result<Storyboard, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    if inputs.is_empty() || columns == 0 || !(interval_secs.is_finite() && interval_secs > 0.0) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("播放列表为空，或间隔、列数无效".to_string()),
+        ));
+    }
+    validation::validate_dimensions(tile_width as i32, tile_height as i32)?;
+
+    // 每个片段在整条时间轴上的起始时间；打开的输入留着之后取帧
+    let mut clips = Vec::with_capacity(inputs.len());
+    let mut clip_offsets = Vec::with_capacity(inputs.len());
+    let mut durations = Vec::with_capacity(inputs.len());
+    let mut total = 0.0;
+    for (index, input) in inputs.iter().enumerate() {
+        let mut ictx = video_processor::open_input(input.as_ref(), options)?;
+        let duration = video_processor::resolve_duration(&mut ictx).ok_or(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无法确定第 {} 个片段的时长", index)),
+        ))?;
+        clips.push(ictx);
+        clip_offsets.push(total);
+        durations.push(duration);
+        total += duration;
+    }
+
+    // 把每一格的时间分配到所在的片段
+    let count = (total / interval_secs).ceil().max(1.0) as usize;
+    let mut clip_times: Vec<Vec<f64>> = vec![Vec::new(); inputs.len()];
+    let mut placements = Vec::with_capacity(count);
+    for index in 0..count {
+        // 取这一格时间段的中点，最后一格的时间段在结尾处截断
+        let start = index as f64 * interval_secs;
+        let time = (start + (start + interval_secs).min(total)) / 2.0;
+        let clip = clip_offsets
+            .iter()
+            .rposition(|&offset| offset <= time)
+            .unwrap_or(0);
+        let clip_time = (time - clip_offsets[clip]).clamp(0.0, durations[clip]);
+        placements.push((clip, clip_times[clip].len(), clip_time, time));
+        clip_times[clip].push(clip_time);
+    }
+
+    // 取帧之前先检查拼图尺寸，播放列表很长时不会解码完才发现放不下
+    let rows = (count as u64).div_ceil(u64::from(columns));
+    let width = u64::from(tile_width) * u64::from(columns).min(count as u64);
+    let height = u64::from(tile_height) * rows;
+    if width * height > validation::MAX_PIXELS {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "故事板尺寸 {}x{} 超出上限，请加大间隔或缩小格子",
+                width, height
+            )),
+        ));
+    }
+    let (width, height) = (width as u32, height as u32);
+
+    let mut tile_options = options.for_analysis(OutputFormat::Rgb24);
+    tile_options.output_width = Some(tile_width);
+    tile_options.output_height = Some(tile_height);
+    tile_options.transform = Default::default();
+    tile_options.max_output_bytes = None;
+
+    let mut clip_frames = Vec::with_capacity(inputs.len());
+    for (ictx, times) in clips.iter_mut().zip(&clip_times) {
+        clip_frames.push(video_processor::with_log_capture(&tile_options, || {
+            video_processor::decode_frames(ictx, times, &tile_options)
+        })?);
+    }
+
+    // 拼图
+    let canvas_bytes = width as usize * height as usize * 3;
+    memory_watch::reserve(canvas_bytes)?;
+    let mut data = vec![0u8; canvas_bytes];
+    let mut tiles = Vec::with_capacity(count);
+    for (index, &(clip, frame_index, clip_time, time)) in placements.iter().enumerate() {
+        let x = (index as u32 % columns) * tile_width;
+        let y = (index as u32 / columns) * tile_height;
+        blit(&mut data, width, &clip_frames[clip][frame_index], x, y);
+        tiles.push(StoryboardTile {
+            clip,
+            clip_time,
+            time,
+            x,
+            y,
+        });
+    }
+
+    Ok(Storyboard {
+        image: RgbFrame {
+            width,
+            height,
+            data,
+            info: video_processor::FrameInfo {
+                storage_width: width,
+                storage_height: height,
+                display_width: width,
+                display_height: height,
+            },
+            warnings: Vec::new(),
+        },
+        tile_width,
+        tile_height,
+        columns,
+        tiles,
+        clip_offsets,
+    })
+}
+
+// 把一格RGB24数据复制到拼图的 (x, y) 处
+pub(crate) fn blit(canvas: &mut [u8], canvas_width: u32, tile: &RgbFrame, x: u32, y: u32) {
+    let row_bytes = tile.width as usize * 3;
+    for (row, line) in tile.data.chunks_exact(row_bytes).enumerate() {
+        let offset = ((y as usize + row) * canvas_width as usize + x as usize) * 3;
+        canvas[offset..offset + row_bytes].copy_from_slice(line);
+    }
+}
@@ -0,0 +1,83 @@
+// temp_storage.rs
+// 内存输入落地为临时文件时使用的存储后端
+//
+// FFmpeg目前只能通过路径打开输入，内存中的视频数据需要先写到某个位置。
+// 默认写入 `std::env::temp_dir()`，嵌入方可以注册自己的后端，
+// 例如写到挂载的大容量目录，或者宿主环境提供的虚拟文件系统。
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::error::{VideoError, VideoErrorCode};
+
+/// 临时存储后端
+pub trait TempStorage: Send + Sync {
+    /// 保存数据，返回FFmpeg可以打开的路径
+    fn store(&self, data: &[u8]) -> Result<PathBuf, VideoError>;
+
+    /// 删除之前由 `store` 保存的数据，失败时忽略
+    fn remove(&self, path: &Path);
+}
+
+/// 默认后端：写入系统临时目录
+#[derive(Debug, Default, Clone)]
+pub struct SystemTempStorage;
+
+impl TempStorage for SystemTempStorage {
+    fn store(&self, data: &[u8]) -> Result<PathBuf, VideoError> {
+        let path = std::env::temp_dir().join(unique_file_name());
+        match std::fs::write(&path, data) {
+            Ok(_) => Ok(path),
+            Err(e) => Err(VideoError::new(
+                VideoErrorCode::InvalidInput,
+                Some(format!("无法写入临时文件: {}", e)),
+            )),
+        }
+    }
+
+    fn remove(&self, path: &Path) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+static STORAGE: RwLock<Option<Arc<dyn TempStorage>>> = RwLock::new(None);
+
+/// 注册全局临时存储后端，之后所有内存输入都通过它落地
+pub fn set_temp_storage(storage: Arc<dyn TempStorage>) {
+    if let Ok(mut current) = STORAGE.write() {
+        *current = Some(storage);
+    }
+}
+
+/// 恢复为默认的系统临时目录
+pub fn reset_temp_storage() {
+    if let Ok(mut current) = STORAGE.write() {
+        *current = None;
+    }
+}
+
+// 当前生效的存储后端
+pub(crate) fn current() -> Arc<dyn TempStorage> {
+    match STORAGE.read() {
+        Ok(current) => current
+            .clone()
+            .unwrap_or_else(|| Arc::new(SystemTempStorage)),
+        Err(_) => Arc::new(SystemTempStorage),
+    }
+}
+
+/// 生成不会在并发调用之间冲突的临时文件名
+pub fn unique_file_name() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    format!(
+        "video_capture_temp_{}_{}_{}.mp4",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
@@ -0,0 +1,244 @@
+// test_pattern.rs
+// 片头彩条和纯色画面检测
+//
+// 广播素材的开头通常有一段SMPTE/EBU彩条和纯色（黑场、场记）画面。
+// 这里从头按固定间隔取样，把每个样本缩小后分类，直到遇到正常内容为止，
+// 返回这些片段的范围，供自动缩略图和自动裁剪逻辑跳过。
+
+use std::array;
+use std::ops::{ControlFlow, Range};
+use std::path::Path;
+
+use crate::config;
+use crate::error::VideoError;
+use crate::ffmpeg_init;
+use crate::options::ExtractOptions;
+use crate::video_processor;
+
+use ffmpeg::util::frame::video::Video;
+use ffmpeg_next as ffmpeg;
+
+// 分类用的缩略图尺寸，宽度能被7和8整除，分别对应SMPTE和EBU彩条的条数
+const THUMB_WIDTH: u32 = 56;
+const THUMB_HEIGHT: u32 = 32;
+
+// 两次取样之间的间隔（秒）
+const SAMPLE_INTERVAL: f64 = 0.5;
+
+// 最多扫描片头多长时间（秒）
+const MAX_SCAN: f64 = 120.0;
+
+// 纯色画面每个通道允许的最大标准差
+const SLATE_MAX_DEVIATION: f64 = 8.0;
+
+// 单根彩条内每个通道允许的最大标准差
+const BAR_MAX_DEVIATION: f64 = 12.0;
+
+// 彩条中“有”和“无”的通道电平界限，兼容75%和100%彩条
+const BAR_HIGH: f64 = 128.0;
+const BAR_LOW: f64 = 80.0;
+
+// 彩条从左到右每根的RGB通道是否点亮
+const SMPTE_BARS: [[bool; 3]; 7] = [
+    [true, true, true],   // 白
+    [true, true, false],  // 黄
+    [false, true, true],  // 青
+    [false, true, false], // 绿
+    [true, false, true],  // 品红
+    [true, false, false], // 红
+    [false, false, true], // 蓝
+];
+const EBU_BARS: [[bool; 3]; 8] = [
+    [true, true, true],
+    [true, true, false],
+    [false, true, true],
+    [false, true, false],
+    [true, false, true],
+    [true, false, false],
+    [false, false, true],
+    [false, false, false], // 黑
+];
+
+/// 片头测试画面的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    /// SMPTE或EBU彩条
+    ColorBars,
+    /// 纯色画面，包括黑场
+    Slate,
+}
+
+/// 一段测试画面
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternSegment {
+    /// 种类
+    pub kind: PatternKind,
+    /// 起始时间（秒）
+    pub start: f64,
+    /// 结束时间（秒），下一段或正常内容从这里开始
+    pub end: f64,
+}
+
+/// 检测片头的彩条和纯色画面
+///
+/// 只检测从头开始连续的测试画面，遇到正常内容即停止；片中的黑场不会被返回。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<Vec<PatternSegment>, VideoError>` - 成功时返回按时间排序的片段，片头没有测试画面时为空；
+///   最后一段的 `end` 即正常内容的开始时间
+pub fn detect_head_patterns<P: AsRef<Path>>(
+    input_path: P,
+) -> Result<Vec<PatternSegment>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let options = config::default_options();
+    let mut detector = HeadDetector::default();
+    video_processor::for_each_video_frame(input_path.as_ref(), &options, 0.0, |frame, time| {
+        detector.push(frame, time, &options)?;
+        Ok(if detector.done {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        })
+    })?;
+
+    Ok(detector.finish())
+}
+
+// 逐个样本分类并合并为片段
+#[derive(Default)]
+struct HeadDetector {
+    segments: Vec<PatternSegment>,
+    // 第一帧的时间
+    start: Option<f64>,
+    next_sample: f64,
+    // 最后一个样本的时间，输入在测试画面中结束时作为结尾
+    last_time: f64,
+    // 已经遇到正常内容或超出扫描范围
+    done: bool,
+}
+
+impl HeadDetector {
+    fn push(
+        &mut self,
+        frame: &Video,
+        time: f64,
+        options: &ExtractOptions,
+    ) -> Result<(), VideoError> {
+        let start = *self.start.get_or_insert(time);
+        if time < self.next_sample {
+            return Ok(());
+        }
+        self.next_sample = time + SAMPLE_INTERVAL;
+        self.last_time = time;
+
+        if time - start > MAX_SCAN {
+            self.close(time);
+            return Ok(());
+        }
+
+        let thumbnail = video_processor::convert_to_rgb(frame, THUMB_WIDTH, THUMB_HEIGHT, options)?;
+        match classify(&thumbnail) {
+            Some(kind) => match self.segments.last_mut() {
+                Some(last) if last.kind == kind => last.end = time,
+                _ => {
+                    // 上一段在这一段开始时结束
+                    if let Some(last) = self.segments.last_mut() {
+                        last.end = time;
+                    }
+                    self.segments.push(PatternSegment {
+                        kind,
+                        start: time,
+                        end: time,
+                    });
+                }
+            },
+            None => self.close(time),
+        }
+        Ok(())
+    }
+
+    // 在 `time` 处结束最后一段并停止检测
+    fn close(&mut self, time: f64) {
+        if let Some(last) = self.segments.last_mut() {
+            last.end = time;
+        }
+        self.done = true;
+    }
+
+    fn finish(mut self) -> Vec<PatternSegment> {
+        // 输入在测试画面中结束时，最后一段延续到最后一个样本之后的一个间隔
+        if !self.done {
+            if let Some(last) = self.segments.last_mut() {
+                last.end = self.last_time + SAMPLE_INTERVAL;
+            }
+        }
+        self.segments
+    }
+}
+
+// 对一张RGB24缩略图分类，正常内容返回 None
+fn classify(rgb: &[u8]) -> Option<PatternKind> {
+    let all = region_stats(rgb, 0..THUMB_WIDTH as usize, 0..THUMB_HEIGHT as usize);
+    if all.deviation.iter().all(|&d| d <= SLATE_MAX_DEVIATION) {
+        return Some(PatternKind::Slate);
+    }
+
+    if matches_bars(rgb, &SMPTE_BARS) || matches_bars(rgb, &EBU_BARS) {
+        return Some(PatternKind::ColorBars);
+    }
+
+    None
+}
+
+// 画面上半部分是否为给定顺序的竖直彩条
+// SMPTE彩条的下方还有其他图案，因此只检查上半部分
+fn matches_bars(rgb: &[u8], bars: &[[bool; 3]]) -> bool {
+    let bar_width = THUMB_WIDTH as usize / bars.len();
+    bars.iter().enumerate().all(|(i, lit)| {
+        // 避开彩条边缘缩放产生的过渡像素
+        let columns = i * bar_width + 1..(i + 1) * bar_width - 1;
+        let stats = region_stats(rgb, columns, 0..THUMB_HEIGHT as usize / 2);
+        (0..3).all(|c| {
+            let level_ok = if lit[c] {
+                stats.mean[c] >= BAR_HIGH
+            } else {
+                stats.mean[c] <= BAR_LOW
+            };
+            level_ok && stats.deviation[c] <= BAR_MAX_DEVIATION
+        })
+    })
+}
+
+// 一个区域内每个通道的均值和标准差
+struct RegionStats {
+    mean: [f64; 3],
+    deviation: [f64; 3],
+}
+
+fn region_stats(rgb: &[u8], columns: Range<usize>, rows: Range<usize>) -> RegionStats {
+    let mut sum = [0.0f64; 3];
+    let mut sum_sq = [0.0f64; 3];
+    let mut count = 0usize;
+
+    for y in rows {
+        for x in columns.clone() {
+            let offset = (y * THUMB_WIDTH as usize + x) * 3;
+            for (c, &v) in rgb[offset..offset + 3].iter().enumerate() {
+                let v = f64::from(v);
+                sum[c] += v;
+                sum_sq[c] += v * v;
+            }
+            count += 1;
+        }
+    }
+
+    let n = count.max(1) as f64;
+    let mean = sum.map(|s| s / n);
+    let deviation = array::from_fn(|c| (sum_sq[c] / n - mean[c] * mean[c]).max(0.0).sqrt());
+    RegionStats { mean, deviation }
+}
@@ -0,0 +1,245 @@
+// thumbhash.rs
+// ThumbHash占位图
+//
+// 与BlurHash相比，ThumbHash把宽高比和透明通道也编码进去，占位图的形状与原图一致，
+// 带透明通道的视频（VP9、ProRes 4444等）也能得到正确的占位图，长度通常只有二十几个字节。
+// 这里把解码后的帧缩小到100x100以内后直接编码，算法与 https://github.com/evanw/thumbhash
+// 的参考实现一致。
+
+use std::f64::consts::PI;
+use std::path::Path;
+
+use crate::error::{VideoError, VideoErrorCode};
+use crate::options::{ExtractOptions, OutputFormat, Position};
+use crate::video_processor;
+
+// ThumbHash要求图像的宽高都不超过该值
+const MAX_SIZE: u32 = 100;
+
+/// 计算指定位置帧的ThumbHash
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 帧的位置，可以是秒数或百分比
+/// * `options` - 抽帧选项，输出尺寸、格式和编码由本函数决定
+///
+/// # 返回
+/// * `Result<Vec<u8>, VideoError>` - 成功时返回ThumbHash字节
+pub fn compute_thumbhash<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<Vec<u8>, VideoError> {
+    let mut frame_options = options.for_analysis(OutputFormat::Rgba);
+    frame_options.output_width = Some(MAX_SIZE);
+    frame_options.output_height = None;
+    let frame = video_processor::extract_frame_with_options(input_path, position, &frame_options)?;
+
+    // 竖屏视频按宽度缩小后仍然太高，再按高度缩小一次
+    if frame.height > MAX_SIZE {
+        let (data, width, height) = shrink_to_fit(&frame.data, frame.width, frame.height);
+        encode(&data, width, height)
+    } else {
+        encode(&frame.data, frame.width, frame.height)
+    }
+}
+
+/// 把RGBA图像编码为ThumbHash
+///
+/// # 参数
+/// * `data` - 紧密排列的RGBA像素数据（非预乘透明度）
+/// * `width` - 图像宽度（1 - 100）
+/// * `height` - 图像高度（1 - 100）
+///
+/// # 返回
+/// * `Result<Vec<u8>, VideoError>` - 成功时返回ThumbHash字节
+pub fn encode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, VideoError> {
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || width > MAX_SIZE || height > MAX_SIZE {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "图像尺寸 {}x{} 超出 {}x{} 的范围",
+                width, height, MAX_SIZE, MAX_SIZE
+            )),
+        ));
+    }
+    if data.len() != w * h * 4 {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "图像数据长度 {} 与尺寸 {}x{} 不匹配",
+                data.len(),
+                width,
+                height
+            )),
+        ));
+    }
+
+    // 按透明度加权的平均颜色
+    let (mut avg_r, mut avg_g, mut avg_b, mut avg_a) = (0.0, 0.0, 0.0, 0.0);
+    for pixel in data.chunks_exact(4) {
+        let alpha = f64::from(pixel[3]) / 255.0;
+        avg_r += alpha / 255.0 * f64::from(pixel[0]);
+        avg_g += alpha / 255.0 * f64::from(pixel[1]);
+        avg_b += alpha / 255.0 * f64::from(pixel[2]);
+        avg_a += alpha;
+    }
+    if avg_a > 0.0 {
+        avg_r /= avg_a;
+        avg_g /= avg_a;
+        avg_b /= avg_a;
+    }
+
+    // 有透明通道时亮度少用一些分量
+    let has_alpha = avg_a < (w * h) as f64;
+    let l_limit = if has_alpha { 5.0 } else { 7.0 };
+    let longest = w.max(h) as f64;
+    let lx = (round(l_limit * w as f64 / longest) as usize).max(1);
+    let ly = (round(l_limit * h as f64 / longest) as usize).max(1);
+
+    // 叠加在平均颜色上之后转换为亮度、黄蓝、红绿和透明度四个通道
+    let mut l = Vec::with_capacity(w * h);
+    let mut p = Vec::with_capacity(w * h);
+    let mut q = Vec::with_capacity(w * h);
+    let mut a = Vec::with_capacity(w * h);
+    for pixel in data.chunks_exact(4) {
+        let alpha = f64::from(pixel[3]) / 255.0;
+        let r = avg_r * (1.0 - alpha) + alpha / 255.0 * f64::from(pixel[0]);
+        let g = avg_g * (1.0 - alpha) + alpha / 255.0 * f64::from(pixel[1]);
+        let b = avg_b * (1.0 - alpha) + alpha / 255.0 * f64::from(pixel[2]);
+        l.push((r + g + b) / 3.0);
+        p.push((r + g) / 2.0 - b);
+        q.push(r - g);
+        a.push(alpha);
+    }
+
+    let l_channel = encode_channel(&l, w, h, lx.max(3), ly.max(3));
+    let p_channel = encode_channel(&p, w, h, 3, 3);
+    let q_channel = encode_channel(&q, w, h, 3, 3);
+    let a_channel = has_alpha.then(|| encode_channel(&a, w, h, 5, 5));
+
+    // 固定长度的头部：各通道的平均值、缩放系数和亮度分量数量
+    let is_landscape = w > h;
+    let header24 = round(63.0 * l_channel.dc) as u32
+        | (round(31.5 + 31.5 * p_channel.dc) as u32) << 6
+        | (round(31.5 + 31.5 * q_channel.dc) as u32) << 12
+        | (round(31.0 * l_channel.scale) as u32) << 18
+        | u32::from(has_alpha) << 23;
+    let header16 = (if is_landscape { ly } else { lx }) as u32
+        | (round(63.0 * p_channel.scale) as u32) << 3
+        | (round(63.0 * q_channel.scale) as u32) << 9
+        | u32::from(is_landscape) << 15;
+    let mut hash = vec![
+        header24 as u8,
+        (header24 >> 8) as u8,
+        (header24 >> 16) as u8,
+        header16 as u8,
+        (header16 >> 8) as u8,
+    ];
+    if let Some(a_channel) = &a_channel {
+        hash.push(round(15.0 * a_channel.dc) as u8 | (round(15.0 * a_channel.scale) as u8) << 4);
+    }
+
+    // 交流分量每个占4位，两个拼成一个字节
+    let channels = [
+        Some(&l_channel),
+        Some(&p_channel),
+        Some(&q_channel),
+        a_channel.as_ref(),
+    ];
+    let factors: Vec<u8> = channels
+        .into_iter()
+        .flatten()
+        .flat_map(|channel| channel.ac.iter())
+        .map(|&factor| round(15.0 * factor) as u8)
+        .collect();
+    hash.extend(
+        factors
+            .chunks(2)
+            .map(|pair| pair[0] | pair.get(1).map_or(0, |&high| high << 4)),
+    );
+
+    Ok(hash)
+}
+
+// 一个通道的直流分量、归一化到 0 - 1 的交流分量和归一化前的最大幅度
+struct Channel {
+    dc: f64,
+    ac: Vec<f64>,
+    scale: f64,
+}
+
+// 对一个通道做DCT，只保留左上角三角形范围内的低频分量
+fn encode_channel(values: &[f64], w: usize, h: usize, nx: usize, ny: usize) -> Channel {
+    let mut dc = 0.0;
+    let mut ac = Vec::new();
+    let mut scale = 0.0f64;
+    for cy in 0..ny {
+        let fy: Vec<f64> = (0..h)
+            .map(|y| (PI / h as f64 * cy as f64 * (y as f64 + 0.5)).cos())
+            .collect();
+        let mut cx = 0;
+        while cx * ny < nx * (ny - cy) {
+            let fx: Vec<f64> = (0..w)
+                .map(|x| (PI / w as f64 * cx as f64 * (x as f64 + 0.5)).cos())
+                .collect();
+            let mut f = 0.0;
+            for (y, row) in values.chunks_exact(w).enumerate() {
+                for (x, &value) in row.iter().enumerate() {
+                    f += value * fx[x] * fy[y];
+                }
+            }
+            f /= (w * h) as f64;
+            if cx > 0 || cy > 0 {
+                ac.push(f);
+                scale = scale.max(f.abs());
+            } else {
+                dc = f;
+            }
+            cx += 1;
+        }
+    }
+    if scale > 0.0 {
+        for factor in &mut ac {
+            *factor = 0.5 + 0.5 / scale * *factor;
+        }
+    }
+    Channel { dc, ac, scale }
+}
+
+// 与参考实现的 Math.round 相同，0.5 向上取整
+fn round(value: f64) -> f64 {
+    (value + 0.5).floor()
+}
+
+// 按面积平均把RGBA图像等比缩小到高度为 `MAX_SIZE`
+fn shrink_to_fit(data: &[u8], width: u32, height: u32) -> (Vec<u8>, u32, u32) {
+    let (w, h) = (width as usize, height as usize);
+    let (new_w, new_h) = (((w * MAX_SIZE as usize) / h).max(1), MAX_SIZE as usize);
+
+    let mut shrunk = Vec::with_capacity(new_w * new_h * 4);
+    for ny in 0..new_h {
+        let (y0, y1) = (
+            ny * h / new_h,
+            ((ny + 1) * h / new_h).max(ny * h / new_h + 1),
+        );
+        for nx in 0..new_w {
+            let (x0, x1) = (
+                nx * w / new_w,
+                ((nx + 1) * w / new_w).max(nx * w / new_w + 1),
+            );
+            let mut sum = [0u32; 4];
+            for y in y0..y1 {
+                for pixel in data[(y * w + x0) * 4..(y * w + x1) * 4].chunks_exact(4) {
+                    for (total, &value) in sum.iter_mut().zip(pixel) {
+                        *total += u32::from(value);
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            shrunk.extend(sum.map(|total| ((total + count / 2) / count) as u8));
+        }
+    }
+    (shrunk, new_w as u32, new_h as u32)
+}
@@ -0,0 +1,134 @@
+// transform.rs
+// 输出帧的旋转和翻转
+//
+// 由调用方显式指定，例如修正前置摄像头录制的镜像画面；
+// 容器中的旋转元数据会先被合并进来，见 `Transform::after_rotation`。
+
+/// 顺时针旋转角度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    /// 旋转后宽高是否互换
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(self, Rotation::Rotate90 | Rotation::Rotate270)
+    }
+
+    /// 由顺时针角度构造，只接受90的倍数，其他角度视为不旋转
+    pub fn from_degrees(degrees: u32) -> Self {
+        if degrees % 90 == 0 {
+            Self::from_quarter_turns(degrees / 90)
+        } else {
+            Rotation::None
+        }
+    }
+
+    // 顺时针旋转的四分之一圈数
+    fn quarter_turns(self) -> u32 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Rotate90 => 1,
+            Rotation::Rotate180 => 2,
+            Rotation::Rotate270 => 3,
+        }
+    }
+
+    fn from_quarter_turns(turns: u32) -> Self {
+        match turns % 4 {
+            1 => Rotation::Rotate90,
+            2 => Rotation::Rotate180,
+            3 => Rotation::Rotate270,
+            _ => Rotation::None,
+        }
+    }
+}
+
+/// 输出变换：先按需翻转，再顺时针旋转
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Transform {
+    /// 顺时针旋转角度
+    pub rotation: Rotation,
+    /// 水平翻转（左右镜像）
+    pub flip_horizontal: bool,
+    /// 垂直翻转（上下颠倒）
+    pub flip_vertical: bool,
+}
+
+impl Transform {
+    /// 是否不做任何变换
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::default()
+    }
+
+    /// 先顺时针旋转 `rotation` 再应用本变换，合并为一次变换
+    pub fn after_rotation(&self, rotation: Rotation) -> Transform {
+        // 先旋转再镜像等价于先镜像再反向旋转；同时上下、左右翻转等价于旋转180度，不改变方向
+        let turns = if self.flip_horizontal != self.flip_vertical {
+            4 - rotation.quarter_turns()
+        } else {
+            rotation.quarter_turns()
+        };
+        Transform {
+            rotation: Rotation::from_quarter_turns(self.rotation.quarter_turns() + turns),
+            ..*self
+        }
+    }
+
+    /// 对紧密排列的打包像素数据应用变换
+    ///
+    /// # 参数
+    /// * `data` - 逐行紧密排列的像素数据
+    /// * `width` / `height` - 输入尺寸（像素）
+    /// * `bytes_per_pixel` - 每像素字节数，RGB24为3，灰度为1
+    ///
+    /// # 返回
+    /// * `(Vec<u8>, u32, u32)` - 变换后的数据和新的宽度、高度
+    pub fn apply(
+        &self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        bytes_per_pixel: usize,
+    ) -> (Vec<u8>, u32, u32) {
+        if self.is_identity() {
+            return (data.to_vec(), width, height);
+        }
+
+        let (w, h) = (width as usize, height as usize);
+        let (out_w, out_h) = if self.rotation.swaps_dimensions() {
+            (h, w)
+        } else {
+            (w, h)
+        };
+
+        let mut result = vec![0u8; out_w * out_h * bytes_per_pixel];
+        for y in 0..h {
+            for x in 0..w {
+                // 先翻转
+                let sx = if self.flip_horizontal { w - 1 - x } else { x };
+                let sy = if self.flip_vertical { h - 1 - y } else { y };
+
+                // 再把 (x, y) 顺时针旋转到输出坐标
+                let (dx, dy) = match self.rotation {
+                    Rotation::None => (x, y),
+                    Rotation::Rotate90 => (h - 1 - y, x),
+                    Rotation::Rotate180 => (w - 1 - x, h - 1 - y),
+                    Rotation::Rotate270 => (y, w - 1 - x),
+                };
+
+                let src = (sy * w + sx) * bytes_per_pixel;
+                let dst = (dy * out_w + dx) * bytes_per_pixel;
+                result[dst..dst + bytes_per_pixel]
+                    .copy_from_slice(&data[src..src + bytes_per_pixel]);
+            }
+        }
+
+        (result, out_w as u32, out_h as u32)
+    }
+}
@@ -0,0 +1,156 @@
+// validation.rs
+// 在把数据交给解码器之前，对输入和容器结构做基本的合理性检查
+//
+// 我们处理的是任意用户上传的文件，容器中声明的尺寸、流数量等字段都不可信。
+// 这里的检查都很便宜，只读取解复用后的元数据，不做任何解码。
+
+use crate::config;
+use crate::error::{VideoError, VideoErrorCode};
+
+use ffmpeg::{format::context::Input, media::Type};
+use ffmpeg_next as ffmpeg;
+
+/// 允许的最大输入数据大小（字节），可以通过 `config::configure` 调低
+pub const MAX_INPUT_BYTES: usize = 2 * 1024 * 1024 * 1024;
+
+/// 容器中允许的最大流数量
+pub const MAX_STREAMS: u32 = 64;
+
+/// 视频宽或高允许的最大值（像素）
+pub const MAX_DIMENSION: u32 = 16384;
+
+/// 单帧允许的最大像素数（约 8K x 8K）
+pub const MAX_PIXELS: u64 = 8192 * 8192;
+
+/// 输出行对齐允许的最大值（字节）
+pub const MAX_ROW_ALIGNMENT: u32 = 4096;
+
+/// 检查内存中的输入数据
+pub fn validate_buffer(input_data: &[u8]) -> Result<(), VideoError> {
+    if input_data.is_empty() {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("输入数据为空".to_string()),
+        ));
+    }
+
+    let max_input_bytes = config::max_input_bytes();
+    if input_data.len() > max_input_bytes {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "输入数据过大: {} 字节，上限 {} 字节",
+                input_data.len(),
+                max_input_bytes
+            )),
+        ));
+    }
+
+    Ok(())
+}
+
+/// 检查请求的时间点
+pub fn validate_time(time_sec: f64) -> Result<(), VideoError> {
+    if !time_sec.is_finite() || time_sec < 0.0 {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的时间点: {}", time_sec)),
+        ));
+    }
+
+    Ok(())
+}
+
+/// 检查按百分比指定的位置
+pub fn validate_percent(percent: f64) -> Result<(), VideoError> {
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的百分比位置: {}", percent)),
+        ));
+    }
+
+    Ok(())
+}
+
+/// 检查输出行对齐的字节数
+pub fn validate_row_alignment(alignment: u32) -> Result<(), VideoError> {
+    if !alignment.is_power_of_two() || alignment > MAX_ROW_ALIGNMENT {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "行对齐必须是不超过 {} 的2的幂: {}",
+                MAX_ROW_ALIGNMENT, alignment
+            )),
+        ));
+    }
+
+    Ok(())
+}
+
+/// 检查已打开的容器结构
+///
+/// 包括流数量上限，以及每个视频流声明的尺寸是否超出上限。
+/// 尺寸未知（为0）的流在这里放行，实际使用的流会在创建解码器后
+/// 通过 `validate_dimensions` 再检查一次。
+pub fn validate_input(ictx: &Input) -> Result<(), VideoError> {
+    let stream_count = ictx.nb_streams();
+    if stream_count > MAX_STREAMS {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!(
+                "流数量过多: {}，上限 {}",
+                stream_count, MAX_STREAMS
+            )),
+        ));
+    }
+
+    for stream in ictx.streams() {
+        let parameters = stream.parameters();
+        if parameters.medium() != Type::Video {
+            continue;
+        }
+
+        // 安全性：codecpar 在流的生命周期内始终有效
+        let (width, height) = unsafe {
+            let ptr = parameters.as_ptr();
+            ((*ptr).width, (*ptr).height)
+        };
+
+        if width == 0 || height == 0 {
+            continue;
+        }
+
+        if let Err(e) = validate_dimensions(width, height) {
+            return Err(VideoError::new(
+                e.code,
+                Some(format!("视频流 #{}: {}", stream.index(), e.message)),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// 检查视频尺寸是否在合理范围内
+pub fn validate_dimensions(width: i32, height: i32) -> Result<(), VideoError> {
+    if width <= 0 || height <= 0 {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无效的视频尺寸: {}x{}", width, height)),
+        ));
+    }
+
+    let (width, height) = (width as u32, height as u32);
+    if width > MAX_DIMENSION
+        || height > MAX_DIMENSION
+        || u64::from(width) * u64::from(height) > MAX_PIXELS
+    {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("视频尺寸超出上限: {}x{}", width, height)),
+        ));
+    }
+
+    Ok(())
+}
@@ -1,221 +1,2899 @@
 // video_processor.rs
 // 处理视频帧提取的核心功能
 
+use std::ffi::{c_int, c_void, CStr, CString};
+use std::io::{Read, Seek};
+use std::iter;
+use std::ops::{ControlFlow, DerefMut};
 use std::path::Path;
+use std::ptr;
+use std::sync::OnceLock;
 
+use crate::buffer_pool;
+use crate::config;
+use crate::custom_io;
+use crate::encode;
 use crate::error::{VideoError, VideoErrorCode};
 use crate::ffmpeg_init;
+use crate::log_capture::{self, LogCapture};
+use crate::memory_watch;
+use crate::options::{
+    Dither, ExtractOptions, FieldSelection, OutputEncoding, OutputFormat, Position, SeekMode,
+};
+use crate::temp_storage;
+use crate::transform::{Rotation, Transform};
+use crate::validation;
+
+// 使用更简洁的导入方式
+// 能避免代码中根据路径找不到模块的问题
+use ffmpeg::{
+    ffi,
+    format::context::Input,
+    media::Type,
+    software::scaling::{context::Context, flag::Flags},
+    util::{
+        format::Pixel,
+        frame::{audio::Audio, side_data, video::Video},
+    },
+    Packet,
+};
+use ffmpeg_next as ffmpeg;
+use wasm_bindgen::prelude::*;
+
+/// 提取出的帧
+///
+/// 默认为RGB24格式，其他格式见 `ExtractOptions::output_format`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbFrame {
+    /// 宽度（像素），已包含缩小和旋转的影响
+    pub width: u32,
+    /// 高度（像素）
+    pub height: u32,
+    /// 像素数据，逐行紧密排列，`data.len() == output_format.frame_bytes(width, height)`；
+    /// 设置了 `ExtractOptions::output_row_alignment` 时每行末尾有填充，
+    /// 长度为 `output_format.aligned_frame_bytes(width, height, alignment)`；
+    /// 设置了 `ExtractOptions::bottom_up_rows` 时从最后一行开始排列；
+    /// 设置了 `ExtractOptions::output_encoding` 时为编码后的图片文件内容
+    pub data: Vec<u8>,
+    /// 源帧的存储尺寸和显示尺寸
+    pub info: FrameInfo,
+    /// 抽帧成功但结果可能与预期不符时的警告，例如视频参数在流中发生变化、
+    /// 视频流只有一帧而忽略了请求的时间点
+    pub warnings: Vec<String>,
+}
+
+/// 源帧的尺寸信息
+///
+/// 变形（anamorphic）视频的像素不是正方形，存储尺寸和显示尺寸不同，
+/// 例如PAL DV存储为720x576、按16:9显示时为1050x576。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// 解码后的存储宽度（像素）
+    pub storage_width: u32,
+    /// 解码后的存储高度（像素）
+    pub storage_height: u32,
+    /// 按采样宽高比（SAR）换算为正方形像素后的显示宽度（像素）
+    pub display_width: u32,
+    /// 显示高度（像素），与存储高度相同
+    pub display_height: u32,
+}
+
+impl FrameInfo {
+    // 根据存储尺寸和采样宽高比计算显示尺寸；SAR未知或为1:1时两者相同
+    pub(crate) fn new(width: u32, height: u32, sample_aspect_ratio: ffmpeg::Rational) -> Self {
+        let (num, den) = (
+            sample_aspect_ratio.numerator(),
+            sample_aspect_ratio.denominator(),
+        );
+        let display_width = if num > 0 && den > 0 && num != den {
+            ((f64::from(width) * f64::from(num) / f64::from(den)).round() as u32).max(1)
+        } else {
+            width
+        };
+        Self {
+            storage_width: width,
+            storage_height: height,
+            display_width,
+            display_height: height,
+        }
+    }
+
+    // 缩放前的基准尺寸：默认按显示尺寸输出正方形像素
+    pub(crate) fn base_size(&self, options: &ExtractOptions) -> (u32, u32) {
+        if options.keep_non_square_pixels {
+            (self.storage_width, self.storage_height)
+        } else {
+            (self.display_width, self.display_height)
+        }
+    }
+}
+
+/// 从视频文件中提取特定时间点的帧
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `time_sec` - 要提取的帧所在的时间点（秒）
+///
+/// # 返回
+/// * `Result<RgbFrame, VideoError>` - 成功时返回RGB格式的帧数据及其尺寸，失败时返回错误
+pub fn extract_frame<P: AsRef<Path>>(input_path: P, time_sec: f64) -> Result<RgbFrame, VideoError> {
+    extract_frame_with_options(input_path, time_sec, &config::default_options())
+}
+
+/// 使用自定义选项从视频文件中提取特定位置的帧
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 要提取的帧所在的位置，可以是秒数或 `Position::Percent`
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<RgbFrame, VideoError>` - 成功时返回RGB格式的帧数据及其尺寸，失败时返回错误
+pub fn extract_frame_with_options<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<RgbFrame, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let position = position.into();
+    with_log_capture(options, || {
+        if let Position::Seconds(time_sec) = position {
+            validation::validate_time(time_sec)?;
+        }
+
+        // 打开输入视频文件
+        let mut ictx = open_input(input_path.as_ref(), options)?;
+        decode_frame_at(&mut ictx, position, options)
+    })
+}
+
+/// 一次提取多个时间点的帧
+///
+/// 只打开和探测一次输入，按时间顺序依次定位和解码，比多次调用 `extract_frame` 快得多，
+/// 适合生成缩略图条。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `times` - 要提取的帧所在的时间点（秒），不要求有序
+///
+/// # 返回
+/// * `Result<Vec<RgbFrame>, VideoError>` - 成功时按 `times` 的顺序返回每个时间点的帧，任何一帧失败时返回错误
+pub fn extract_frames<P: AsRef<Path>>(
+    input_path: P,
+    times: &[f64],
+) -> Result<Vec<RgbFrame>, VideoError> {
+    extract_frames_with_options(input_path, times, &config::default_options())
+}
+
+/// 使用自定义选项一次提取多个时间点的帧
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `times` - 要提取的帧所在的时间点（秒），不要求有序
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<Vec<RgbFrame>, VideoError>` - 成功时按 `times` 的顺序返回每个时间点的帧，任何一帧失败时返回错误
+pub fn extract_frames_with_options<P: AsRef<Path>>(
+    input_path: P,
+    times: &[f64],
+    options: &ExtractOptions,
+) -> Result<Vec<RgbFrame>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    with_log_capture(options, || {
+        for &time_sec in times {
+            validation::validate_time(time_sec)?;
+        }
+
+        let mut ictx = open_input(input_path.as_ref(), options)?;
+        decode_frames(&mut ictx, times, options)
+    })
+}
+
+// 从已打开的输入中提取多个时间点（秒）的帧，按 `times` 的顺序返回
+pub(crate) fn decode_frames(
+    ictx: &mut Input,
+    times: &[f64],
+    options: &ExtractOptions,
+) -> Result<Vec<RgbFrame>, VideoError> {
+    // 按时间顺序解码，每次定位都向前移动
+    let mut order: Vec<usize> = (0..times.len()).collect();
+    order.sort_by(|&a, &b| times[a].total_cmp(&times[b]));
+
+    let mut frames: Vec<Option<RgbFrame>> = vec![None; times.len()];
+    let mut previous: Option<usize> = None;
+    for index in order {
+        // 重复的时间点直接复用上一帧
+        if let Some(previous) = previous.filter(|&p| times[p] == times[index]) {
+            frames[index] = frames[previous].clone();
+            continue;
+        }
+
+        // 修复模式从头顺序解码，每一帧都要回到开头
+        if options.repair_timestamps {
+            let _ = ictx.seek(0, ..);
+        }
+        frames[index] = Some(decode_frame_at(
+            ictx,
+            Position::Seconds(times[index]),
+            options,
+        )?);
+        previous = Some(index);
+    }
+
+    Ok(frames.into_iter().flatten().collect())
+}
+
+/// 从可随机读取的数据源中提取特定位置的帧
+///
+/// FFmpeg通过自定义AVIO按需读取数据，不需要把整个文件读进内存，
+/// 也不会写临时文件，适合处理很大的本地文件或浏览器中的OPFS文件。
+///
+/// # 参数
+/// * `reader` - 实现了 `Read + Seek` 的数据源
+/// * `position` - 要提取的帧所在的位置，可以是秒数或 `Position::Percent`
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<RgbFrame, VideoError>` - 成功时返回RGB格式的帧数据及其尺寸，失败时返回错误
+pub fn extract_frame_from_reader<R: Read + Seek + 'static, T: Into<Position>>(
+    reader: R,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<RgbFrame, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let position = position.into();
+    with_log_capture(options, || {
+        if let Position::Seconds(time_sec) = position {
+            validation::validate_time(time_sec)?;
+        }
+
+        let mut ictx = custom_io::open_reader(Box::new(reader), options)?;
+        decode_frame_at(&mut ictx, position, options)
+    })
+}
+
+/// 只提取特定位置帧的亮度（灰度）数据
+///
+/// 适合清晰度、运动、直方图等不需要颜色的分析。解码器支持时会跳过色度解码，
+/// 否则至少跳过色度的颜色转换，8位YUV帧的亮度平面直接复制。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 要提取的帧所在的位置，可以是秒数或 `Position::Percent`
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<Vec<u8>, VideoError>` - 成功时返回每像素一字节的灰度数据
+pub fn extract_luma_frame<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<Vec<u8>, VideoError> {
+    decode_luma(input_path.as_ref(), position.into(), options, |_| {}).map(|frame| frame.data)
+}
+
+/// 灰度帧
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LumaFrame {
+    /// 宽度（像素）
+    pub width: u32,
+    /// 高度（像素）
+    pub height: u32,
+    /// 像素数据，每像素一字节，逐行紧密排列
+    pub data: Vec<u8>,
+}
+
+/// 提取适合后续OCR的帧
+///
+/// 按原始分辨率输出灰度数据并拉伸对比度，没有色度子采样和有损重新编码带来的文字边缘失真，
+/// 适合为课程录像、屏幕录制中的文字建立索引。输出尺寸和 `max_output_bytes` 在这里不生效。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 要提取的帧所在的位置，可以是秒数或 `Position::Percent`
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<LumaFrame, VideoError>` - 成功时返回拉伸对比度后的灰度帧及其尺寸
+pub fn extract_text_frame<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<LumaFrame, VideoError> {
+    // 文字识别需要原始分辨率
+    let mut options = options.clone();
+    options.output_width = None;
+    options.output_height = None;
+    options.max_output_bytes = None;
+
+    decode_luma(
+        input_path.as_ref(),
+        position.into(),
+        &options,
+        stretch_contrast,
+    )
+}
+
+// 解码指定位置帧的亮度数据，在旋转或翻转之前先交给 `process` 处理
+fn decode_luma<F>(
+    input_path: &Path,
+    position: Position,
+    options: &ExtractOptions,
+    process: F,
+) -> Result<LumaFrame, VideoError>
+where
+    F: FnOnce(&mut [u8]),
+{
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    // 请求解码器只输出亮度（AV_CODEC_FLAG_GRAY），不支持的解码器会忽略该标志；
+    // 放在最前面，调用方自己设置的 flags 优先
+    let mut options = options.clone();
+    options
+        .decoder_options
+        .insert(0, ("flags".to_string(), "+gray".to_string()));
+
+    with_log_capture(&options, || {
+        if let Position::Seconds(time_sec) = position {
+            validation::validate_time(time_sec)?;
+        }
+
+        let mut ictx = open_input(input_path, &options)?;
+        let frame = decode_video_frame(&mut ictx, position, &options)?.frame;
+        let info = FrameInfo::new(frame.width(), frame.height(), frame.aspect_ratio());
+        let rotation = frame_rotation(&frame, &options);
+        let (base_width, base_height) = info.base_size(&options);
+        let (width, height) = rotated_output_size(base_width, base_height, 1, rotation, &options)?;
+        let mut luma = convert_to_luma(&frame, width, height, &options)?;
+        process(&mut luma);
+        let transform = options.transform.after_rotation(rotation);
+        let (data, width, height) = apply_transform(luma, width, height, 1, &transform);
+        Ok(LumaFrame {
+            width,
+            height,
+            data,
+        })
+    })
+}
+
+// 把亮度的第1和第99百分位拉伸到完整的0-255范围，忽略少量极端像素
+fn stretch_contrast(luma: &mut [u8]) {
+    let mut histogram = [0usize; 256];
+    for &v in luma.iter() {
+        histogram[v as usize] += 1;
+    }
+
+    let percentile = |fraction: f64| -> u8 {
+        let target = (luma.len() as f64 * fraction) as usize;
+        let mut count = 0;
+        for (value, &n) in histogram.iter().enumerate() {
+            count += n;
+            if count > target {
+                return value as u8;
+            }
+        }
+        u8::MAX
+    };
+    let low = percentile(0.01);
+    let high = percentile(0.99);
+    if high <= low {
+        return;
+    }
+
+    let range = u32::from(high - low);
+    let table: Vec<u8> = (0..=255u8)
+        .map(|v| (u32::from(v.clamp(low, high) - low) * 255 / range) as u8)
+        .collect();
+    for v in luma.iter_mut() {
+        *v = table[*v as usize];
+    }
+}
+
+/// 目标帧及其之前的关键帧
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameWithKeyframe {
+    /// 目标帧，格式与 `extract_frame_with_options` 的输出相同
+    pub frame: RgbFrame,
+    /// 目标帧之前最近的关键帧；目标帧本身就是关键帧时为 `None`
+    pub keyframe: Option<RgbFrame>,
+    /// 关键帧的时间（秒）
+    pub keyframe_time: Option<f64>,
+}
+
+/// 提取特定位置的帧，同时返回它之前的关键帧
+///
+/// 关键帧在解码目标帧的途中本来就会被解码，额外成本只有一次颜色转换。
+/// 播放器可以先显示关键帧预加载定位目标，再换成精确的帧。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 要提取的帧所在的位置，可以是秒数或 `Position::Percent`
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<FrameWithKeyframe, VideoError>` - 成功时返回RGB格式的目标帧和关键帧
+pub fn extract_frame_with_keyframe<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<FrameWithKeyframe, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let position = position.into();
+    with_log_capture(options, || {
+        if let Position::Seconds(time_sec) = position {
+            validation::validate_time(time_sec)?;
+        }
+
+        let mut ictx = open_input(input_path.as_ref(), options)?;
+        let time_base = ictx
+            .stream(select_video_stream(&ictx, options)?)
+            .map(|stream| f64::from(stream.time_base()))
+            .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+        let decoded = decode_frame_and_keyframe(&mut ictx, position, options, true)?;
+        let keyframe = decoded
+            .keyframe
+            .as_ref()
+            .filter(|_| !decoded.frame.is_key());
+
+        // 两帧使用相同的尺寸预算和变换规则
+        let to_rgb = |frame: &Video| to_rgb_frame(frame, options);
+
+        Ok(FrameWithKeyframe {
+            frame: decoded.to_rgb_frame(options)?,
+            keyframe_time: keyframe
+                .and_then(|keyframe| keyframe.timestamp())
+                .map(|ts| ts as f64 * time_base),
+            keyframe: keyframe.map(to_rgb).transpose()?,
+        })
+    })
+}
+
+/// 隔行扫描帧的两场
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPair {
+    /// 顶场（偶数行）
+    pub top: RgbFrame,
+    /// 底场（奇数行）
+    pub bottom: RgbFrame,
+    /// 顶场在时间上先于底场；逐行扫描的帧没有先后之分，按解码器标记返回
+    pub top_field_first: bool,
+}
+
+/// 提取特定位置的帧，并把两场分别输出为图片
+///
+/// 只解码一次，两场使用相同的尺寸和变换规则，`options.field` 在这里不生效。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 要提取的帧所在的位置，可以是秒数或 `Position::Percent`
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<FieldPair, VideoError>` - 成功时返回顶场和底场
+pub fn extract_fields<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<FieldPair, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let position = position.into();
+    with_log_capture(options, || {
+        if let Position::Seconds(time_sec) = position {
+            validation::validate_time(time_sec)?;
+        }
+
+        let mut ictx = open_input(input_path.as_ref(), options)?;
+        let decoded = decode_video_frame(&mut ictx, position, options)?;
+
+        let to_field = |field: FieldSelection| {
+            let mut options = options.clone();
+            options.field = field;
+            decoded.to_rgb_frame(&options)
+        };
+        Ok(FieldPair {
+            top: to_field(FieldSelection::Top)?,
+            bottom: to_field(FieldSelection::Bottom)?,
+            top_field_first: decoded.frame.is_top_first(),
+        })
+    })
+}
+
+/// 关键帧及其时间
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    /// 关键帧的显示时间（秒）
+    pub time: f64,
+    /// 关键帧，格式与 `extract_frame_with_options` 的输出相同
+    pub frame: RgbFrame,
+}
+
+/// 只解码关键帧，得到均匀分布在整个视频中的一组画面
+///
+/// 只把关键帧的数据包交给解码器，不需要解码其他帧，适合为很长的视频快速生成故事板。
+/// 能确定时长时，相邻两个结果至少相隔 `时长 / max_count`；否则返回最前面的关键帧。
+/// 需要缩小时设置 `options.output_width` 等输出选项。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `max_count` - 最多返回多少帧
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<Vec<Keyframe>, VideoError>` - 成功时按时间顺序返回关键帧
+pub fn extract_keyframes<P: AsRef<Path>>(
+    input_path: P,
+    max_count: usize,
+    options: &ExtractOptions,
+) -> Result<Vec<Keyframe>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    with_log_capture(options, || {
+        let mut keyframes = Vec::new();
+        if max_count == 0 {
+            return Ok(keyframes);
+        }
+
+        let mut ictx = open_input(input_path.as_ref(), options)?;
+        let duration = resolve_duration(&mut ictx);
+        let video_stream_index = select_video_stream(&ictx, options)?;
+        discard_other_streams(&mut ictx, video_stream_index);
+
+        let (time_base, mut decoder) = {
+            let video_stream = ictx
+                .stream(video_stream_index)
+                .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+            let decoder = open_decoder(&video_stream, options)?;
+            (f64::from(video_stream.time_base()), decoder)
+        };
+        // 个别关键帧数据包中混有非关键帧时，让解码器也跳过它们
+        // 安全性：只修改自己打开的解码器上下文的字段
+        unsafe { (*decoder.as_mut_ptr()).skip_frame = ffi::AVDiscard::AVDISCARD_NONKEY };
+
+        // 与上一个选中的关键帧太近时跳过，让结果分布在整个视频中
+        let spacing = duration.map_or(0.0, |duration| duration / max_count as f64);
+        let mut next_time = f64::NEG_INFINITY;
+        let mut frame = Video::empty();
+
+        let push = |frame: &Video, keyframes: &mut Vec<Keyframe>| -> Result<(), VideoError> {
+            keyframes.push(Keyframe {
+                time: frame.timestamp().map_or(0.0, |ts| ts as f64 * time_base),
+                frame: to_rgb_frame(frame, options)?,
+            });
+            Ok(())
+        };
+
+        for (index, packet) in read_packets(&mut ictx) {
+            if index != video_stream_index || !packet.is_key() {
+                continue;
+            }
+            if let Some(ts) = packet.pts().or(packet.dts()) {
+                let time = ts as f64 * time_base;
+                if time < next_time {
+                    continue;
+                }
+                next_time = time + spacing;
+            }
+
+            // 个别损坏的关键帧不影响其他结果
+            if decoder.send_packet(&packet).is_err() {
+                continue;
+            }
+            while keyframes.len() < max_count && decoder.receive_frame(&mut frame).is_ok() {
+                push(&frame, &mut keyframes)?;
+            }
+            if keyframes.len() >= max_count {
+                return Ok(keyframes);
+            }
+        }
+        if decoder.send_eof().is_ok() {
+            while keyframes.len() < max_count && decoder.receive_frame(&mut frame).is_ok() {
+                push(&frame, &mut keyframes)?;
+            }
+        }
+
+        Ok(keyframes)
+    })
+}
+
+/// 均匀取帧的间隔
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThumbnailSpacing {
+    /// 每隔固定秒数取一帧，从视频开头开始
+    Interval(f64),
+    /// 在整个视频中均匀取固定数量的帧，每帧取所在时间段的中点，需要能确定时长
+    Count(usize),
+}
+
+/// 均匀取出的一帧
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+    /// 请求的时间点（秒）
+    pub requested_time: f64,
+    /// 实际取到的帧的显示时间（秒），不早于请求的时间点
+    pub time: f64,
+    /// 帧数据，格式与 `extract_frame_with_options` 的输出相同
+    pub frame: RgbFrame,
+}
+
+/// 在一次顺序解码中按固定间隔或固定数量取帧
+///
+/// 进度条预览条的基础操作。只解码一遍，不做任何定位，只有选中的帧才会做颜色转换。
+/// 帧率很低、一帧覆盖多个时间点时只返回一次，因此结果可能少于请求的数量。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `spacing` - 取帧的间隔或数量
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<Vec<Thumbnail>, VideoError>` - 成功时按时间顺序返回取到的帧及其实际时间
+pub fn extract_thumbnails<P: AsRef<Path>>(
+    input_path: P,
+    spacing: ThumbnailSpacing,
+    options: &ExtractOptions,
+) -> Result<Vec<Thumbnail>, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    with_log_capture(options, || {
+        let mut ictx = open_input(input_path.as_ref(), options)?;
+        let start = start_time_secs(&ictx);
+        let duration = resolve_duration(&mut ictx);
+
+        // 第一个时间点和相邻时间点的间隔
+        let (first, step, count) = match spacing {
+            ThumbnailSpacing::Interval(interval) if interval.is_finite() && interval > 0.0 => {
+                let count = duration.map(|duration| (duration / interval).ceil() as usize);
+                (start, interval, count)
+            }
+            ThumbnailSpacing::Count(count) if count > 0 => {
+                let duration = duration.ok_or(VideoError::new(
+                    VideoErrorCode::InvalidInput,
+                    Some("无法确定视频时长，不能按数量均匀取帧".to_string()),
+                ))?;
+                let step = duration / count as f64;
+                (start + step / 2.0, step, Some(count))
+            }
+            _ => {
+                return Err(VideoError::new(
+                    VideoErrorCode::InvalidInput,
+                    Some(format!("无效的取帧间隔: {:?}", spacing)),
+                ))
+            }
+        };
+
+        let video_stream_index = select_video_stream(&ictx, options)?;
+        discard_other_streams(&mut ictx, video_stream_index);
+        let (time_base, mut decoder) = {
+            let video_stream = ictx
+                .stream(video_stream_index)
+                .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+            let decoder = open_decoder(&video_stream, options)?;
+            (f64::from(video_stream.time_base()), decoder)
+        };
+
+        let mut thumbnails = Vec::new();
+        let mut index = 0usize;
+        let done = |index: usize| count.is_some_and(|count| index >= count);
+        if done(index) {
+            return Ok(thumbnails);
+        }
+        let mut frame = Video::empty();
+
+        // 帧到达下一个时间点时取下，并跳过这一帧已经覆盖的时间点，取完后停止
+        decode_packets(
+            &mut ictx,
+            video_stream_index,
+            &mut decoder,
+            &mut frame,
+            |frame| {
+                let time = match frame.timestamp() {
+                    Some(ts) => ts as f64 * time_base,
+                    None => return Ok(ControlFlow::Continue(())),
+                };
+                let requested_time = first + index as f64 * step;
+                if time < requested_time {
+                    return Ok(ControlFlow::Continue(()));
+                }
+
+                thumbnails.push(Thumbnail {
+                    requested_time,
+                    time,
+                    frame: to_rgb_frame(frame, options)?,
+                });
+                while !done(index) && first + index as f64 * step <= time {
+                    index += 1;
+                }
+                Ok(if done(index) {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                })
+            },
+        )?;
+
+        Ok(thumbnails)
+    })
+}
+
+// 按需捕获本次调用的FFmpeg日志，失败时随错误一起返回
+pub(crate) fn with_log_capture<T, F>(options: &ExtractOptions, f: F) -> Result<T, VideoError>
+where
+    F: FnOnce() -> Result<T, VideoError>,
+{
+    let capture = options.capture_log.map(LogCapture::start);
+    let result = f();
+
+    match (capture, result) {
+        (Some(capture), Err(e)) => Err(e.with_log(capture.finish())),
+        (_, result) => result,
+    }
+}
+
+// 从已打开的输入中解码指定位置的帧，并转换为输出格式
+fn decode_frame_at(
+    ictx: &mut Input,
+    position: Position,
+    options: &ExtractOptions,
+) -> Result<RgbFrame, VideoError> {
+    decode_video_frame(ictx, position, options)?.to_rgb_frame(options)
+}
+
+// 按选项缩小、转换为输出格式并旋转或翻转解码后的帧
+pub(crate) fn to_rgb_frame(
+    frame: &Video,
+    options: &ExtractOptions,
+) -> Result<RgbFrame, VideoError> {
+    let format = options.output_format;
+    if let Some(alignment) = options.output_row_alignment {
+        validation::validate_row_alignment(alignment)?;
+    }
+    let info = FrameInfo::new(frame.width(), frame.height(), frame.aspect_ratio());
+    let rotation = frame_rotation(frame, options);
+    let transform = options.transform.after_rotation(rotation);
+
+    let (base_width, base_height) = info.base_size(options);
+    let (width, height) = rotated_output_size(
+        base_width,
+        base_height,
+        format.bytes_per_pixel(),
+        rotation,
+        options,
+    )?;
+
+    // 拆场和转换之前检查临时内存，超出上限或宿主拒绝内存增长时不再分配
+    let decoded_bytes = (0..frame.planes())
+        .map(|i| frame.data(i).len())
+        .sum::<usize>();
+    let field = field_rows(options.field, frame.height());
+    let needed = temp_bytes(
+        decoded_bytes,
+        field.map(|(_, rows)| (frame.format(), frame.width(), rows)),
+        (frame.width(), frame.height()),
+        (width, height),
+        &transform,
+        options,
+    );
+    check_temp_bytes(needed, options)?;
+    memory_watch::reserve(needed)?;
+
+    // 单场按整帧的尺寸输出，缩放时在垂直方向上拉伸
+    let field_frame = match field {
+        Some((parity, _)) => Some(extract_field(frame, parity)?),
+        None => None,
+    };
+    let frame = field_frame.as_ref().unwrap_or(frame);
+
+    let (data, width, height) = match format {
+        OutputFormat::Rgb24 => {
+            let rgb = convert_to_rgb(frame, width, height, options)?;
+            apply_transform(rgb, width, height, 3, &transform)
+        }
+        OutputFormat::Gray8 => {
+            let luma = convert_to_luma(frame, width, height, options)?;
+            apply_transform(luma, width, height, 1, &transform)
+        }
+        OutputFormat::Rgba | OutputFormat::Bgra | OutputFormat::Bgr24 => {
+            let bytes_per_pixel = format.bytes_per_pixel();
+            let packed = convert_packed(
+                frame,
+                format.pixel(),
+                bytes_per_pixel,
+                width,
+                height,
+                options.dither,
+            )?;
+            apply_transform(packed, width, height, bytes_per_pixel, &transform)
+        }
+        OutputFormat::Yuv420p => {
+            convert_to_yuv420p(frame, width, height, &transform, options.dither)?
+        }
+    };
+    let data = match options.output_encoding {
+        OutputEncoding::Raw if options.output_row_alignment.is_some() || options.bottom_up_rows => {
+            arrange_rows(
+                data,
+                width,
+                height,
+                format,
+                options.output_row_alignment,
+                options.bottom_up_rows,
+            )
+        }
+        _ => encode::encode_frame(
+            data,
+            width,
+            height,
+            format,
+            options.output_encoding,
+            options.encoding_quality,
+        )?,
+    };
+    Ok(RgbFrame {
+        width,
+        height,
+        data,
+        info,
+        warnings: Vec::new(),
+    })
+}
+
+// 按输出的行布局重新排列紧密排列的各平面：每行末尾补0使行字节数对齐，需要时从最后一行开始
+fn arrange_rows(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    format: OutputFormat,
+    alignment: Option<u32>,
+    bottom_up: bool,
+) -> Vec<u8> {
+    let arranged_bytes = format.aligned_frame_bytes(width, height, alignment);
+    if arranged_bytes == data.len() && !bottom_up {
+        return data;
+    }
+
+    let mut arranged = Vec::with_capacity(arranged_bytes);
+    let mut offset = 0;
+    for (row_bytes, rows) in format.planes(width, height) {
+        let stride = alignment.map_or(row_bytes, |alignment| {
+            row_bytes.next_multiple_of(alignment as usize)
+        });
+        let mut plane: Vec<&[u8]> = data[offset..offset + row_bytes * rows]
+            .chunks_exact(row_bytes)
+            .collect();
+        if bottom_up {
+            plane.reverse();
+        }
+        for row in plane {
+            arranged.extend_from_slice(row);
+            arranged.resize(arranged.len() + stride - row_bytes, 0);
+        }
+        offset += row_bytes * rows;
+    }
+    arranged
+}
+
+// 估算一次抽帧同时占用的临时内存：解码帧、拆出的单场、缩放器输出和去掉填充后的副本、
+// 线性光缩放的16位中间帧、旋转或翻转后的副本、按行布局重新排列后的副本以及编码后的图片（按不超过原始数据估算）
+// `field` 为拆出的单场的像素格式和尺寸，`source` 为解码帧的尺寸，`output` 为缩放后的尺寸
+fn temp_bytes(
+    decoded_bytes: usize,
+    field: Option<(Pixel, u32, u32)>,
+    source: (u32, u32),
+    output: (u32, u32),
+    transform: &Transform,
+    options: &ExtractOptions,
+) -> usize {
+    let (width, height) = output;
+    let output = options.output_format.frame_bytes(width, height);
+
+    let mut total: usize = decoded_bytes
+        + field.map_or(0, |(format, width, height)| {
+            image_bytes(format, width, height)
+        })
+        + output * 2;
+    let (source_width, source_height) = field.map_or(source, |(_, width, height)| (width, height));
+    if options.output_format == OutputFormat::Rgb24
+        && options.linear_light_scaling
+        && (width, height) != (source_width, source_height)
+    {
+        let pixels = source_width as usize * source_height as usize;
+        total += (pixels + width as usize * height as usize) * 6; // RGB48每像素六字节
+    }
+    if !transform.is_identity() {
+        total += output;
+    }
+    if options.output_encoding != OutputEncoding::Raw {
+        total += output;
+    } else if options.output_row_alignment.is_some() || options.bottom_up_rows {
+        total +=
+            options
+                .output_format
+                .aligned_frame_bytes(width, height, options.output_row_alignment);
+    }
+    total
+}
+
+// 临时内存超出 `max_temp_bytes` 时返回 MemoryLimit 错误
+fn check_temp_bytes(needed: usize, options: &ExtractOptions) -> Result<(), VideoError> {
+    match options.max_temp_bytes {
+        Some(limit) if needed > limit => Err(VideoError::new(
+            VideoErrorCode::MemoryLimit,
+            Some(format!(
+                "抽帧需要约 {} 字节临时内存，超出上限 {} 字节",
+                needed, limit
+            )),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// 指定像素格式和尺寸的一帧紧密排列时的字节数，格式未知时为0
+fn image_bytes(format: Pixel, width: u32, height: u32) -> usize {
+    // 安全性：只根据参数计算大小，不访问内存
+    let size =
+        unsafe { ffi::av_image_get_buffer_size(format.into(), width as i32, height as i32, 1) };
+    size.max(0) as usize
+}
+
+// 选择的场对应的奇偶（0为顶场，1为底场）和单场的行数，处理整帧时返回 None
+fn field_rows(field: FieldSelection, height: u32) -> Option<(usize, u32)> {
+    let parity = match field {
+        FieldSelection::Frame => return None,
+        FieldSelection::Top => 0,
+        FieldSelection::Bottom => 1,
+    };
+    Some((parity, (height + 1 - parity as u32) / 2))
+}
+
+// 取出隔行扫描帧的一场：`parity` 为0时取偶数行（顶场），为1时取奇数行（底场）
+fn extract_field(frame: &Video, parity: usize) -> Result<Video, VideoError> {
+    let height = (frame.height() as usize + 1 - parity) / 2;
+    if height == 0 {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("帧高度不足以拆分为两场".to_string()),
+        ));
+    }
+
+    let mut field = Video::new(frame.format(), frame.width(), height as u32);
+    for index in 0..frame.planes() {
+        let src_stride = frame.stride(index);
+        let dst_stride = field.stride(index);
+        let row_bytes = src_stride.min(dst_stride);
+        let src_rows = frame.plane_height(index) as usize;
+        let dst_rows = field.plane_height(index) as usize;
+
+        let src = frame.data(index);
+        let dst = field.data_mut(index);
+        for row in 0..dst_rows {
+            // 色度平面的行数为奇数时，最后一行可能没有同场的源行，沿用最后一行
+            let src_row = (row * 2 + parity).min(src_rows - 1);
+            dst[row * dst_stride..row * dst_stride + row_bytes]
+                .copy_from_slice(&src[src_row * src_stride..src_row * src_stride + row_bytes]);
+        }
+    }
+
+    Ok(field)
+}
+
+// 旋转或翻转已转换的打包像素数据，返回变换后的数据和尺寸
+pub(crate) fn apply_transform(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    bytes_per_pixel: usize,
+    transform: &Transform,
+) -> (Vec<u8>, u32, u32) {
+    if transform.is_identity() {
+        return (data, width, height);
+    }
+    transform.apply(&data, width, height, bytes_per_pixel)
+}
+
+// 帧需要自动旋转的角度，来自帧携带的显示矩阵（解码器从容器的流信息中带过来）
+pub(crate) fn frame_rotation(frame: &Video, options: &ExtractOptions) -> Rotation {
+    if options.ignore_rotation {
+        return Rotation::None;
+    }
+    match frame.side_data(side_data::Type::DisplayMatrix) {
+        Some(matrix) if matrix.data().len() >= 9 * std::mem::size_of::<i32>() => {
+            // 安全性：显示矩阵固定为9个i32，长度已经检查
+            Rotation::from_degrees(unsafe {
+                display_rotation(matrix.data().as_ptr() as *const i32)
+            })
+        }
+        _ => Rotation::None,
+    }
+}
+
+// 按旋转之后的方向计算输出尺寸，使要求的输出宽高对应旋转后的画面，再换回旋转前的方向
+fn rotated_output_size(
+    width: u32,
+    height: u32,
+    bytes_per_pixel: usize,
+    rotation: Rotation,
+    options: &ExtractOptions,
+) -> Result<(u32, u32), VideoError> {
+    if rotation.swaps_dimensions() {
+        let (height, width) = output_size(height, width, bytes_per_pixel, options)?;
+        Ok((width, height))
+    } else {
+        output_size(width, height, bytes_per_pixel, options)
+    }
+}
+
+// 计算输出尺寸：先缩放到要求的输出尺寸，超出 `max_output_bytes` 时再等比缩小到预算之内
+pub(crate) fn output_size(
+    width: u32,
+    height: u32,
+    bytes_per_pixel: usize,
+    options: &ExtractOptions,
+) -> Result<(u32, u32), VideoError> {
+    let (width, height) = requested_size(width, height, options)?;
+
+    let budget = match options.max_output_bytes {
+        Some(budget) => budget,
+        None => return Ok((width, height)),
+    };
+
+    let bytes = width as usize * height as usize * bytes_per_pixel;
+    if bytes <= budget {
+        return Ok((width, height));
+    }
+
+    // 连一个像素都放不下时无法缩放
+    if budget < bytes_per_pixel {
+        return Err(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("输出大小预算过小: {} 字节", budget)),
+        ));
+    }
+
+    // 宽高按相同比例缩小，向下取整保证不超出预算
+    let scale = (budget as f64 / bytes as f64).sqrt();
+    let scaled_width = ((width as f64 * scale) as u32).max(1);
+    let scaled_height = ((height as f64 * scale) as u32).max(1);
+    Ok((scaled_width, scaled_height))
+}
+
+// 调用方要求的输出尺寸，只指定一边时另一边按原始宽高比计算
+fn requested_size(
+    width: u32,
+    height: u32,
+    options: &ExtractOptions,
+) -> Result<(u32, u32), VideoError> {
+    let scale = |length: u32, from: u32, to: u32| -> u32 {
+        ((f64::from(length) * f64::from(to) / f64::from(from.max(1))).round() as u32).max(1)
+    };
+    let size = match (options.output_width, options.output_height) {
+        (None, None) => return Ok((width, height)),
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => (w, scale(height, width, w)),
+        (None, Some(h)) => (scale(width, height, h), h),
+    };
+
+    validation::validate_dimensions(size.0 as i32, size.1 as i32)?;
+    Ok(size)
+}
+
+// 为视频流创建并打开解码器，同时应用解码器选项
+pub(crate) fn open_decoder(
+    video_stream: &ffmpeg::format::stream::Stream,
+    options: &ExtractOptions,
+) -> Result<ffmpeg::decoder::Video, VideoError> {
+    // 获取解码器
+    // 使用parameters方法获取流参数，然后创建解码器上下文
+    let context_decoder =
+        match ffmpeg::codec::context::Context::from_parameters(video_stream.parameters()) {
+            Ok(context) => context,
+            Err(e) => {
+                return Err(VideoError::new(
+                    VideoErrorCode::DecoderFailed,
+                    Some(format!("无法创建解码器上下文: {}", e)),
+                ))
+            }
+        };
+
+    // 从上下文创建视频解码器，同时应用解码器选项
+    let codec = ffmpeg::decoder::find(context_decoder.id());
+    let decoder = match context_decoder
+        .decoder()
+        .open_as_with(codec, options.decoder_dictionary())
+        .and_then(|opened| opened.video())
+    {
+        Ok(dec) => dec,
+        Err(e) => {
+            return Err(VideoError::new(
+                VideoErrorCode::DecoderFailed,
+                Some(format!("无法创建解码器: {}", e)),
+            ))
+        }
+    };
+
+    // 检查解码器报告的实际尺寸
+    validation::validate_dimensions(decoder.width() as i32, decoder.height() as i32)?;
+
+    // 解码之前按流参数估算临时内存，超出上限时不必等到整帧解码出来才失败
+    memory_watch::reserve(image_bytes(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+    ))?;
+    if options.max_temp_bytes.is_some() {
+        check_temp_bytes(
+            planned_temp_bytes(&decoder, video_stream, options)?,
+            options,
+        )?;
+    }
+
+    Ok(decoder)
+}
+
+// 按解码器报告的尺寸和像素格式估算一次抽帧的临时内存，计算方式与转换时相同
+fn planned_temp_bytes(
+    decoder: &ffmpeg::decoder::Video,
+    video_stream: &ffmpeg::format::stream::Stream,
+    options: &ExtractOptions,
+) -> Result<usize, VideoError> {
+    let (width, height) = (decoder.width(), decoder.height());
+    // 与 decode_with 一致，容器中声明的采样宽高比优先
+    // 安全性：只读取流的字段
+    let sample_aspect_ratio = Some(ffmpeg::Rational::from(unsafe {
+        (*video_stream.as_ptr()).sample_aspect_ratio
+    }))
+    .filter(|sar| sar.numerator() > 0 && sar.denominator() > 0)
+    .unwrap_or_else(|| decoder.aspect_ratio());
+    let info = FrameInfo::new(width, height, sample_aspect_ratio);
+    let rotation = if options.ignore_rotation {
+        Rotation::None
+    } else {
+        Rotation::from_degrees(stream_rotation(video_stream))
+    };
+
+    let (base_width, base_height) = info.base_size(options);
+    let output = rotated_output_size(
+        base_width,
+        base_height,
+        options.output_format.bytes_per_pixel(),
+        rotation,
+        options,
+    )?;
+    Ok(temp_bytes(
+        image_bytes(decoder.format(), width, height),
+        field_rows(options.field, height).map(|(_, rows)| (decoder.format(), width, rows)),
+        (width, height),
+        output,
+        &options.transform.after_rotation(rotation),
+        options,
+    ))
+}
+
+// 为音频流创建解码器
+pub(crate) fn open_audio_decoder(
+    stream: &ffmpeg::format::stream::Stream,
+) -> Result<ffmpeg::decoder::Audio, VideoError> {
+    ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+        .and_then(|context| context.decoder().audio())
+        .map_err(|e| {
+            VideoError::new(
+                VideoErrorCode::DecoderFailed,
+                Some(format!("无法创建音频解码器: {}", e)),
+            )
+        })
+}
+
+// 从已打开的输入中解码指定位置的帧，保持解码器输出的像素格式
+fn decode_video_frame(
+    ictx: &mut Input,
+    position: Position,
+    options: &ExtractOptions,
+) -> Result<DecodedFrame, VideoError> {
+    decode_frame_and_keyframe(ictx, position, options, false)
+}
+
+// 解码指定位置的帧；`keep_keyframe` 为 true 时同时返回途中解码的最后一个关键帧
+fn decode_frame_and_keyframe(
+    ictx: &mut Input,
+    position: Position,
+    options: &ExtractOptions,
+    keep_keyframe: bool,
+) -> Result<DecodedFrame, VideoError> {
+    // 把位置换算成时间点（秒）
+    let time_sec = resolve_position(ictx, position)?;
+
+    // 查找要解码的视频流
+    let video_stream_index = select_video_stream(ictx, options)?;
+
+    // 指定了目标高度时，丢弃其他流，HLS等多码率输入就不会下载其他码率的数据
+    if options.target_height.is_some() {
+        discard_other_streams(ictx, video_stream_index);
+    }
+
+    let video_stream = ictx
+        .stream(video_stream_index)
+        .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+    let mut decoder = open_decoder(&video_stream, options)?;
+
+    decode_with(
+        ictx,
+        &mut decoder,
+        video_stream_index,
+        time_sec,
+        options,
+        keep_keyframe,
+    )
+}
+
+// 用已打开的解码器定位并解码指定时间点（秒）的帧
+// 解码器中残留的帧由调用方在再次调用前清空（`flush`）
+pub(crate) fn decode_with(
+    ictx: &mut Input,
+    decoder: &mut ffmpeg::decoder::Video,
+    video_stream_index: usize,
+    time_sec: f64,
+    options: &ExtractOptions,
+    keep_keyframe: bool,
+) -> Result<DecodedFrame, VideoError> {
+    let video_stream = ictx
+        .stream(video_stream_index)
+        .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+    // 计算目标时间戳
+    let time_base = video_stream.time_base();
+    let target_ts =
+        (time_sec * f64::from(time_base.denominator()) / f64::from(time_base.numerator())) as i64;
+
+    // 修复模式下按帧率推算时间戳，需要知道一帧对应的时间戳增量
+    // 一帧对应的时间戳增量，帧率未知时为1
+    let frame_step = {
+        let rate = video_stream.avg_frame_rate();
+        if rate.numerator() > 0 && rate.denominator() > 0 {
+            (f64::from(rate.invert()) / f64::from(time_base)).round() as i64
+        } else {
+            1
+        }
+    };
+
+    // 修复模式下按帧率推算时间戳
+    let mut repair = if options.repair_timestamps {
+        Some(TimestampRepair::new(frame_step))
+    } else {
+        None
+    };
+
+    // 与 av_guess_sample_aspect_ratio 一致，容器中声明的采样宽高比优先于码流中的值；
+    // 不少DV、DVB转封装的文件只在容器层标注了变形比例
+    // 安全性：只读取流的字段
+    let sample_aspect_ratio = Some(ffmpeg::Rational::from(unsafe {
+        (*video_stream.as_ptr()).sample_aspect_ratio
+    }))
+    .filter(|sar| sar.numerator() > 0 && sar.denominator() > 0);
+
+    // 部分播客MP4把封面JPEG作为只有一帧的普通视频流（没有标记为附加图片），
+    // 按时间点定位永远找不到帧，此时无论请求哪个时间点都返回这一帧
+    let single_frame = video_stream.frames() == 1;
+    let mut warnings = Vec::new();
+    if single_frame {
+        warn(
+            &mut warnings,
+            "视频流只有一帧（可能是封面图），忽略请求的时间点，返回该帧".to_string(),
+        );
+        let _ = ictx.seek(0, ..);
+    }
+
+    // 修复模式从头顺序解码，只能精确定位
+    let fast = options.seek_mode == SeekMode::Fast && repair.is_none();
+
+    // 时间戳损坏时定位结果不可信，修复模式从头顺序解码；否则定位到目标时间点之前的关键帧
+    if repair.is_none() && !single_frame {
+        // Input::seek 的时间戳以 AV_TIME_BASE（微秒）为单位，不是流的时间基
+        let seek_ts = (time_sec * f64::from(ffi::AV_TIME_BASE)) as i64;
+        if let Err(e) = ictx.seek(seek_ts, ..seek_ts) {
+            return Err(VideoError::new(
+                VideoErrorCode::SeekFailed,
+                Some(format!("无法定位到目标时间点: {}", e)),
+            ));
+        }
+    }
+
+    // 判断帧相对于目标的位置
+    let mut locate = |frame: &Video| -> Target {
+        // 快速模式直接返回定位到的关键帧
+        if single_frame || fast {
+            return Target::Take;
+        }
+        let ts = match repair.as_mut() {
+            Some(repair) => Some(repair.fix(frame.timestamp())),
+            None => frame.timestamp(),
+        };
+        match ts {
+            // 没有时间戳时无法比较，直接返回该帧
+            None => Target::Take,
+            Some(ts) if ts == target_ts => Target::Take,
+            Some(ts) if ts > target_ts => Target::Past,
+            // 帧没有记录时长时按帧率估算
+            Some(ts) => {
+                let duration = frame.packet().duration;
+                Target::Before(ts + if duration > 0 { duration } else { frame_step })
+            }
+        }
+    };
+
+    // 读取帧
+    let mut decoded_frame = Video::empty();
+    let mut picker = FramePicker {
+        keep_keyframe,
+        sample_aspect_ratio,
+        previous: None,
+        previous_end: i64::MIN,
+        keyframe: None,
+        warnings,
+    };
+
+    // 解码器当前输出的参数，流中途变化时（例如插播广告的TS）需要重新检查
+    let mut parameters = FrameParameters::of_decoder(decoder);
+
+    // 处理包起来，直到我们发现一个帧或数据包结束
+    for (index, packet) in read_packets(ictx) {
+        if index == video_stream_index {
+            // 将包发送给解码器
+            if let Err(e) = decoder.send_packet(&packet) {
+                return Err(VideoError::new(
+                    VideoErrorCode::DecoderFailed,
+                    Some(format!("发送数据包失败: {}", e)),
+                ));
+            }
+
+            // 从解码器中接收帧
+            while decoder.receive_frame(&mut decoded_frame).is_ok() {
+                parameters.update(&decoded_frame, &mut picker.warnings)?;
+                let target = locate(&decoded_frame);
+                if let Some(found) = picker.offer(&mut decoded_frame, target) {
+                    return Ok(found);
+                }
+            }
+        }
+    }
+
+    // 告诉解码器没有更多数据包，并尝试从中获取最后的帧
+    if let Err(e) = decoder.send_eof() {
+        return Err(VideoError::new(
+            VideoErrorCode::DecoderFailed,
+            Some(format!("发送EOF失败: {}", e)),
+        ));
+    }
+
+    // 接收解码器中的任何剩余帧
+    while decoder.receive_frame(&mut decoded_frame).is_ok() {
+        parameters.update(&decoded_frame, &mut picker.warnings)?;
+        let target = locate(&decoded_frame);
+        if let Some(found) = picker.offer(&mut decoded_frame, target) {
+            return Ok(found);
+        }
+    }
+
+    // 目标落在最后一帧的显示时长内时返回这一帧；目标越过视频结尾时仍然找不到
+    if let Some(found) = picker.finish(target_ts) {
+        return Ok(found);
+    }
+
+    // 没有找到合适的帧，返回特定错误
+    Err(VideoError::new(VideoErrorCode::FrameNotFound, None))
+}
+
+// 解码输出的尺寸和像素格式
+#[derive(Clone, Copy, PartialEq)]
+struct FrameParameters {
+    format: Pixel,
+    width: u32,
+    height: u32,
+}
+
+impl FrameParameters {
+    fn of_decoder(decoder: &ffmpeg::decoder::Video) -> Self {
+        Self {
+            format: decoder.format(),
+            width: decoder.width(),
+            height: decoder.height(),
+        }
+    }
+
+    // 帧参数与之前不同时重新检查尺寸，并记录一条警告
+    // 颜色转换总是按帧自身的参数创建缩放器，因此变化后的帧也能正确转换
+    fn update(&mut self, frame: &Video, warnings: &mut Vec<String>) -> Result<(), VideoError> {
+        let next = Self {
+            format: frame.format(),
+            width: frame.width(),
+            height: frame.height(),
+        };
+        if next == *self {
+            return Ok(());
+        }
+
+        validation::validate_dimensions(next.width as i32, next.height as i32)?;
+
+        // 部分解码器在输出第一帧之前不知道像素格式，这不算参数变化
+        if self.format != Pixel::None {
+            warn(
+                warnings,
+                format!(
+                    "视频参数在流中发生变化: {} -> {}",
+                    self.describe(),
+                    next.describe()
+                ),
+            );
+        }
+        *self = next;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        let name = self.format.descriptor().map_or("unknown", |d| d.name());
+        format!("{}x{} {}", self.width, self.height, name)
+    }
+}
+
+// 输出一条警告，同时记录下来随抽帧结果返回，调用成功时调用方也能看到
+fn warn(warnings: &mut Vec<String>, message: String) {
+    log_capture::warn(&message);
+    warnings.push(message);
+}
+
+// 解码得到的目标帧
+pub(crate) struct DecodedFrame {
+    pub(crate) frame: Video,
+    // 目标之前的最后一个关键帧，只在要求保留时返回
+    pub(crate) keyframe: Option<Video>,
+    // 解码过程中记录的警告
+    pub(crate) warnings: Vec<String>,
+}
+
+impl DecodedFrame {
+    // 按选项转换目标帧，并带上解码过程中记录的警告
+    pub(crate) fn to_rgb_frame(&self, options: &ExtractOptions) -> Result<RgbFrame, VideoError> {
+        let mut rgb = to_rgb_frame(&self.frame, options)?;
+        rgb.warnings = self.warnings.clone();
+        Ok(rgb)
+    }
+}
+
+// 解码出的帧相对于目标时间点的位置
+enum Target {
+    // 目标之前的帧，附带它的显示结束时间戳
+    Before(i64),
+    // 正好是目标帧
+    Take,
+    // 已经越过目标，目标时间点显示的是上一帧
+    Past,
+}
+
+// 保留目标之前的最后一帧，找到目标帧时连同之前的关键帧一起返回
+struct FramePicker {
+    keep_keyframe: bool,
+    // 容器中声明的采样宽高比，未声明时为 `None`
+    sample_aspect_ratio: Option<ffmpeg::Rational>,
+    previous: Option<Video>,
+    // 上一帧的显示结束时间戳
+    previous_end: i64,
+    keyframe: Option<Video>,
+    warnings: Vec<String>,
+}
+
+impl FramePicker {
+    // 处理一帧解码结果，找到目标帧时返回它和之前的关键帧
+    // 帧的所有权被取走，`frame` 换成新的空帧供解码器继续使用
+    fn offer(&mut self, frame: &mut Video, target: Target) -> Option<DecodedFrame> {
+        let frame = std::mem::replace(frame, Video::empty());
+        match target {
+            Target::Before(end) => {
+                self.retire_previous();
+                self.previous = Some(frame);
+                self.previous_end = end;
+                None
+            }
+            Target::Take => {
+                self.retire_previous();
+                Some(self.found(frame))
+            }
+            // 目标之前没有帧（例如定位到的第一帧就越过了目标）时返回越过目标的这一帧
+            Target::Past => {
+                let frame = self.previous.take().unwrap_or(frame);
+                Some(self.found(frame))
+            }
+        }
+    }
+
+    // 输入结束时调用：目标时间戳在上一帧的显示时长内时返回上一帧
+    fn finish(&mut self, target_ts: i64) -> Option<DecodedFrame> {
+        if target_ts >= self.previous_end {
+            return None;
+        }
+        self.previous.take().map(|frame| self.found(frame))
+    }
+
+    // 返回目标帧、之前的关键帧和记录的警告，容器声明了采样宽高比时覆盖帧上的值
+    fn found(&mut self, mut frame: Video) -> DecodedFrame {
+        let mut keyframe = self.keyframe.take();
+        if let Some(sample_aspect_ratio) = self.sample_aspect_ratio {
+            for frame in std::iter::once(&mut frame).chain(keyframe.as_mut()) {
+                // 安全性：只修改自己持有的帧的字段
+                unsafe { (*frame.as_mut_ptr()).sample_aspect_ratio = sample_aspect_ratio.into() };
+            }
+        }
+        DecodedFrame {
+            frame,
+            keyframe,
+            warnings: std::mem::take(&mut self.warnings),
+        }
+    }
+
+    // 被新帧取代的上一帧如果是关键帧，留作目标之前的关键帧
+    fn retire_previous(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            if self.keep_keyframe && previous.is_key() {
+                self.keyframe = Some(previous);
+            }
+        }
+    }
+}
+
+// 把损坏或非单调的帧时间戳修复为单调递增的序列
+struct TimestampRepair {
+    // 一帧对应的时间戳增量
+    step: i64,
+    last: Option<i64>,
+}
+
+impl TimestampRepair {
+    fn new(step: i64) -> Self {
+        Self {
+            step: step.max(1),
+            last: None,
+        }
+    }
+
+    // 缺失或不大于上一帧的时间戳按上一帧加一帧间隔推算
+    fn fix(&mut self, timestamp: Option<i64>) -> i64 {
+        let fixed = match (timestamp, self.last) {
+            (Some(ts), Some(last)) if ts > last => ts,
+            (_, Some(last)) => last + self.step,
+            (Some(ts), None) => ts,
+            (None, None) => 0,
+        };
+        self.last = Some(fixed);
+        fixed
+    }
+}
+
+// 将解码后的帧转换为紧密排列的RGB24数据
+pub(crate) fn convert_to_rgb(
+    frame: &Video,
+    width: u32,
+    height: u32,
+    options: &ExtractOptions,
+) -> Result<Vec<u8>, VideoError> {
+    // 需要缩小且开启了线性光缩放时走高质量路径
+    if options.linear_light_scaling && (width, height) != (frame.width(), frame.height()) {
+        return convert_to_rgb_linear(frame, width, height);
+    }
+    convert_packed(frame, Pixel::RGB24, 3, width, height, options.dither) // RGB每像素三字节
+}
+
+// 在线性光空间中缩放：先转换为16位RGB并解码sRGB伽马，缩放后再编码回8位sRGB
+// 直接在伽马空间中缩小会让细节变暗，这条路径更慢但结果更准确
+fn convert_to_rgb_linear(frame: &Video, width: u32, height: u32) -> Result<Vec<u8>, VideoError> {
+    let (to_linear, to_srgb) = srgb_tables();
+
+    // 原始尺寸的16位RGB，转换为线性光
+    let mut linear = scale_frame(
+        frame,
+        Pixel::RGB48,
+        frame.width(),
+        frame.height(),
+        Flags::BILINEAR,
+        Dither::Auto,
+    )?;
+    let row_bytes = linear.width() as usize * 6;
+    let stride = linear.stride(0);
+    let rows = linear.height() as usize;
+    let data = linear.data_mut(0);
+    for row in 0..rows {
+        let line = &mut data[row * stride..row * stride + row_bytes];
+        for sample in line.chunks_exact_mut(2) {
+            let value = to_linear[u16::from_ne_bytes([sample[0], sample[1]]) as usize];
+            sample.copy_from_slice(&value.to_ne_bytes());
+        }
+    }
+
+    // 在线性空间中缩小
+    let scaled = scale_frame(
+        &linear,
+        Pixel::RGB48,
+        width,
+        height,
+        Flags::AREA,
+        Dither::Auto,
+    )?;
+
+    // 编码回8位sRGB，同时去掉行对齐填充
+    let data = scaled.data(0);
+    let stride = scaled.stride(0);
+    let row_bytes = width as usize * 6;
+    let mut result = buffer_pool::take(width as usize * height as usize * 3);
+    for row in 0..height as usize {
+        let line = &data[row * stride..row * stride + row_bytes];
+        for sample in line.chunks_exact(2) {
+            result.push(to_srgb[u16::from_ne_bytes([sample[0], sample[1]]) as usize]);
+        }
+    }
+
+    Ok(result)
+}
+
+// sRGB 16位编码值到16位线性值、16位线性值到8位编码值的查找表
+fn srgb_tables() -> &'static (Vec<u16>, Vec<u8>) {
+    static TABLES: OnceLock<(Vec<u16>, Vec<u8>)> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let to_linear = (0..=u16::MAX)
+            .map(|v| {
+                let c = f64::from(v) / 65535.0;
+                let linear = if c <= 0.04045 {
+                    c / 12.92
+                } else {
+                    ((c + 0.055) / 1.055).powf(2.4)
+                };
+                (linear * 65535.0).round() as u16
+            })
+            .collect();
+        let to_srgb = (0..=u16::MAX)
+            .map(|v| {
+                let linear = f64::from(v) / 65535.0;
+                let c = if linear <= 0.0031308 {
+                    linear * 12.92
+                } else {
+                    1.055 * linear.powf(1.0 / 2.4) - 0.055
+                };
+                (c * 255.0).round() as u8
+            })
+            .collect();
+        (to_linear, to_srgb)
+    })
+}
+
+// 使用缩放器转换像素格式和尺寸，返回新的帧
+fn scale_frame(
+    frame: &Video,
+    format: Pixel,
+    width: u32,
+    height: u32,
+    flags: Flags,
+    dither: Dither,
+) -> Result<Video, VideoError> {
+    // 抖动方式只能在初始化缩放器之前设置，Context::get 无法指定
+    if let Some(dither) = dither.sws_name() {
+        return scale_frame_dithered(frame, format, width, height, flags, dither);
+    }
+
+    let mut scaler = match Context::get(
+        frame.format(),
+        frame.width(),
+        frame.height(),
+        format,
+        width,
+        height,
+        flags,
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(VideoError::new(
+                VideoErrorCode::FFmpegError,
+                Some(format!("创建缩放器失败: {}", e)),
+            ))
+        }
+    };
+
+    let mut scaled = Video::empty();
+    if let Err(e) = scaler.run(frame, &mut scaled) {
+        return Err(VideoError::new(
+            VideoErrorCode::FFmpegError,
+            Some(format!("颜色转换失败: {}", e)),
+        ));
+    }
+    Ok(scaled)
+}
+
+// 手动创建缩放器并指定抖动方式（sws_dither）后转换
+fn scale_frame_dithered(
+    frame: &Video,
+    format: Pixel,
+    width: u32,
+    height: u32,
+    flags: Flags,
+    dither: &CStr,
+) -> Result<Video, VideoError> {
+    let mut scaled = Video::new(format, width, height);
+
+    // 安全性：上下文在本函数内创建和释放；源帧和目标帧的平面尺寸与上下文参数一致
+    unsafe {
+        let context = ffi::sws_alloc_context();
+        if context.is_null() {
+            return Err(VideoError::new(
+                VideoErrorCode::FFmpegError,
+                Some("创建缩放器失败: 内存不足".to_string()),
+            ));
+        }
+
+        let obj = context as *mut c_void;
+        let src_format: ffi::AVPixelFormat = frame.format().into();
+        let dst_format: ffi::AVPixelFormat = format.into();
+        ffi::av_opt_set_int(obj, c"srcw".as_ptr(), i64::from(frame.width()), 0);
+        ffi::av_opt_set_int(obj, c"srch".as_ptr(), i64::from(frame.height()), 0);
+        ffi::av_opt_set_int(obj, c"src_format".as_ptr(), src_format as i64, 0);
+        ffi::av_opt_set_int(obj, c"dstw".as_ptr(), i64::from(width), 0);
+        ffi::av_opt_set_int(obj, c"dsth".as_ptr(), i64::from(height), 0);
+        ffi::av_opt_set_int(obj, c"dst_format".as_ptr(), dst_format as i64, 0);
+        ffi::av_opt_set_int(obj, c"sws_flags".as_ptr(), i64::from(flags.bits()), 0);
+        ffi::av_opt_set(obj, c"sws_dither".as_ptr(), dither.as_ptr(), 0);
+
+        let ret = ffi::sws_init_context(context, ptr::null_mut(), ptr::null_mut());
+        if ret < 0 {
+            ffi::sws_freeContext(context);
+            return Err(VideoError::new(
+                VideoErrorCode::FFmpegError,
+                Some(format!("创建缩放器失败: {}", ffmpeg::Error::from(ret))),
+            ));
+        }
+
+        let src = &*frame.as_ptr();
+        let dst = &mut *scaled.as_mut_ptr();
+        let ret = ffi::sws_scale(
+            context,
+            src.data.as_ptr() as *const *const u8,
+            src.linesize.as_ptr(),
+            0,
+            frame.height() as c_int,
+            dst.data.as_ptr(),
+            dst.linesize.as_ptr(),
+        );
+        ffi::sws_freeContext(context);
+        if ret < 0 {
+            return Err(VideoError::new(
+                VideoErrorCode::FFmpegError,
+                Some(format!("颜色转换失败: {}", ffmpeg::Error::from(ret))),
+            ));
+        }
+    }
+
+    Ok(scaled)
+}
+
+// 取出解码后帧的亮度平面，得到紧密排列的8位灰度数据
+pub(crate) fn convert_to_luma(
+    frame: &Video,
+    width: u32,
+    height: u32,
+    options: &ExtractOptions,
+) -> Result<Vec<u8>, VideoError> {
+    // 需要缩小时交给缩放器
+    if (width, height) != (frame.width(), frame.height()) {
+        return convert_packed(frame, Pixel::GRAY8, 1, width, height, options.dither);
+    }
+
+    // 8位平面YUV和半平面格式的第一个平面就是亮度，直接复制即可
+    match frame.format() {
+        Pixel::YUV420P
+        | Pixel::YUVJ420P
+        | Pixel::YUV422P
+        | Pixel::YUVJ422P
+        | Pixel::YUV444P
+        | Pixel::YUVJ444P
+        | Pixel::YUV440P
+        | Pixel::YUVJ440P
+        | Pixel::YUV411P
+        | Pixel::YUV410P
+        | Pixel::YUVA420P
+        | Pixel::NV12
+        | Pixel::NV21
+        | Pixel::GRAY8 => {
+            let data = frame.data(0);
+            let stride = frame.stride(0);
+            let width = frame.width() as usize;
+
+            let mut result = buffer_pool::take(width * frame.height() as usize);
+            for i in 0..frame.height() as usize {
+                let line_start = i * stride;
+                result.extend_from_slice(&data[line_start..line_start + width]);
+            }
+            Ok(result)
+        }
+        // 其他格式（高位深、RGB等）交给缩放器转换
+        _ => convert_packed(frame, Pixel::GRAY8, 1, width, height, options.dither),
+    }
+}
+
+// 转换为YUV420P，三个平面依次紧密排列；旋转和翻转分别作用于每个平面
+fn convert_to_yuv420p(
+    frame: &Video,
+    width: u32,
+    height: u32,
+    transform: &Transform,
+    dither: Dither,
+) -> Result<(Vec<u8>, u32, u32), VideoError> {
+    let converted = scale_frame(
+        frame,
+        Pixel::YUV420P,
+        width,
+        height,
+        Flags::BILINEAR,
+        dither,
+    )?;
+    let raw = copy_planes(&converted)?;
+
+    let mut result = buffer_pool::take(OutputFormat::Yuv420p.frame_bytes(raw.width, raw.height));
+    let (mut out_width, mut out_height) = (raw.width, raw.height);
+    for (index, plane) in raw.planes.into_iter().enumerate() {
+        let (data, plane_width, plane_height) =
+            apply_transform(plane.data, plane.width, plane.height, 1, transform);
+        if index == 0 {
+            (out_width, out_height) = (plane_width, plane_height);
+        }
+        result.extend_from_slice(&data);
+    }
+    Ok((result, out_width, out_height))
+}
+
+// 使用缩放器把帧转换为指定尺寸的单平面打包格式，并去掉行对齐填充
+fn convert_packed(
+    frame: &Video,
+    format: Pixel,
+    bytes_per_pixel: usize,
+    width: u32,
+    height: u32,
+    dither: Dither,
+) -> Result<Vec<u8>, VideoError> {
+    // 转换像素格式，必要时同时缩小尺寸
+    let converted = scale_frame(frame, format, width, height, Flags::BILINEAR, dither)?;
+
+    // 提取像素数据
+    let data = converted.data(0);
+    let stride = converted.stride(0);
+    let height = converted.height();
+
+    // 缓存通常包含项对齐字节，因此我们需要通过展平行数据来清除它们
+    let row_bytes = converted.width() as usize * bytes_per_pixel;
+    let mut result = buffer_pool::take(row_bytes * height as usize);
+    for i in 0..height {
+        let line_start = i as usize * stride;
+        let line_end = line_start + row_bytes;
+        result.extend_from_slice(&data[line_start..line_end]);
+    }
+
+    Ok(result)
+}
+
+/// 解码器输出的一个平面
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plane {
+    /// 平面数据，逐行紧密排列，不含对齐填充
+    pub data: Vec<u8>,
+    /// 每行的字节数，`data.len() == row_bytes * height`
+    pub row_bytes: usize,
+    /// 平面宽度（采样点），色度平面按色度子采样缩小
+    pub width: u32,
+    /// 平面行数，色度平面按色度子采样缩小
+    pub height: u32,
+}
+
+/// 未经缩放和颜色转换的原始帧
+///
+/// 用于在GPU着色器或Worker中自行完成缩放和颜色转换的调用方。
+/// 布局保证（后续版本保持不变）：
+/// * `planes` 的顺序与FFmpeg `AVFrame::data` 一致，例如 `yuv420p` 依次为Y、U、V；
+/// * 每个平面逐行紧密排列，行与行之间没有填充，行字节数见 `Plane::row_bytes`；
+/// * 像素格式不做任何转换，`pixel_format` 为FFmpeg的像素格式名称（如 `"yuv420p"`、`"nv12"`），
+///   调用方需要自行处理不支持的格式。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawFrame {
+    /// 帧宽度（像素）
+    pub width: u32,
+    /// 帧高度（像素）
+    pub height: u32,
+    /// FFmpeg像素格式名称
+    pub pixel_format: String,
+    /// 帧的显示时间（秒），解码器没有给出时间戳时为 `None`
+    pub timestamp: Option<f64>,
+    /// 各个平面的数据
+    pub planes: Vec<Plane>,
+}
+
+/// 提取特定位置的原始帧，跳过缩放和颜色转换
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `position` - 要提取的帧所在的位置，可以是秒数或 `Position::Percent`
+/// * `options` - 打开输入和解码器时使用的选项
+///
+/// # 返回
+/// * `Result<RawFrame, VideoError>` - 成功时返回解码器输出的原始平面数据
+pub fn extract_raw_frame<P: AsRef<Path>, T: Into<Position>>(
+    input_path: P,
+    position: T,
+    options: &ExtractOptions,
+) -> Result<RawFrame, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let position = position.into();
+    with_log_capture(options, || {
+        if let Position::Seconds(time_sec) = position {
+            validation::validate_time(time_sec)?;
+        }
+
+        let mut ictx = open_input(input_path.as_ref(), options)?;
+        let time_base = ictx
+            .stream(select_video_stream(&ictx, options)?)
+            .map(|stream| f64::from(stream.time_base()))
+            .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+        let frame = decode_video_frame(&mut ictx, position, options)?.frame;
+        let mut raw = copy_planes(&frame)?;
+        raw.timestamp = frame.timestamp().map(|ts| ts as f64 * time_base);
+        Ok(raw)
+    })
+}
+
+// 按平面复制帧数据，去掉每行末尾的对齐填充
+fn copy_planes(frame: &Video) -> Result<RawFrame, VideoError> {
+    // 对应 libavutil/pixdesc.h 中的 AV_PIX_FMT_FLAG_PAL 和 AV_PIX_FMT_FLAG_HWACCEL
+    const FLAG_PAL: u64 = 1 << 1;
+    const FLAG_HWACCEL: u64 = 1 << 3;
+    // 调色板固定为256项，每项4字节
+    const PALETTE_BYTES: usize = 256 * 4;
+
+    let format = frame.format();
+    let descriptor = format.descriptor().ok_or(VideoError::new(
+        VideoErrorCode::FFmpegError,
+        Some("未知的像素格式".to_string()),
+    ))?;
+    // 安全性：描述符指向FFmpeg内部的静态表
+    let flags = unsafe { (*descriptor.as_ptr()).flags };
 
-// 使用更简洁的导入方式
-// 能避免代码中根据路径找不到模块的问题
-use ffmpeg::{
-    format::input,
-    media::Type,
-    software::scaling::{context::Context, flag::Flags},
-    util::frame::video::Video,
-};
-use ffmpeg_next as ffmpeg;
+    // 硬件帧的数据不在内存中，无法直接复制
+    if flags & FLAG_HWACCEL != 0 {
+        return Err(VideoError::new(
+            VideoErrorCode::FFmpegError,
+            Some(format!("不支持硬件像素格式: {}", descriptor.name())),
+        ));
+    }
 
-/// 从视频文件中提取特定时间点的帧
+    let mut planes = Vec::with_capacity(frame.planes());
+    for index in 0..frame.planes() {
+        // 调色板格式的第二个平面是调色板，按一行256项返回
+        if flags & FLAG_PAL != 0 && index == 1 {
+            // 安全性：调色板格式的 data[1] 总是指向完整的调色板
+            let palette =
+                unsafe { std::slice::from_raw_parts((*frame.as_ptr()).data[1], PALETTE_BYTES) };
+            planes.push(Plane {
+                data: palette.to_vec(),
+                row_bytes: PALETTE_BYTES,
+                width: 256,
+                height: 1,
+            });
+            continue;
+        }
+
+        // 安全性：只根据像素格式和宽度计算行字节数
+        let row_bytes = unsafe {
+            ffi::av_image_get_linesize(format.into(), frame.width() as i32, index as i32)
+        };
+        if row_bytes < 0 {
+            return Err(VideoError::new(
+                VideoErrorCode::FFmpegError,
+                Some(format!("无法计算平面 {} 的行字节数", index)),
+            ));
+        }
+        let row_bytes = row_bytes as usize;
+
+        let height = frame.plane_height(index);
+        let data = frame.data(index);
+        let stride = frame.stride(index);
+
+        let mut plane = Vec::with_capacity(row_bytes * height as usize);
+        for row in 0..height as usize {
+            let line_start = row * stride;
+            plane.extend_from_slice(&data[line_start..line_start + row_bytes]);
+        }
+
+        planes.push(Plane {
+            data: plane,
+            row_bytes,
+            width: frame.plane_width(index),
+            height,
+        });
+    }
+
+    Ok(RawFrame {
+        width: frame.width(),
+        height: frame.height(),
+        pixel_format: descriptor.name().to_string(),
+        timestamp: None,
+        planes,
+    })
+}
+
+/// 获取视频时长（秒）
+///
+/// 优先使用容器和各个流声明的时长；MPEG-TS、FLV等文件经常不声明时长，
+/// 此时会定位到文件末尾附近，读取最后的时间戳来估算。
 ///
 /// # 参数
 /// * `input_path` - 输入视频文件的路径
-/// * `time_sec` - 要提取的帧所在的时间点（秒）
 ///
 /// # 返回
-/// * `Result<Vec<u8>, VideoError>` - 成功时返回RGB格式的帧数据，失败时返回错误
-pub fn extract_frame<P: AsRef<Path>>(input_path: P, time_sec: f64) -> Result<Vec<u8>, VideoError> {
+/// * `Result<f64, VideoError>` - 成功时返回时长（秒），无法确定时长时返回错误
+pub fn duration<P: AsRef<Path>>(input_path: P) -> Result<f64, VideoError> {
     // 确保FFmpeg已初始化
     ffmpeg_init::initialize();
 
-    // 打开输入视频文件
-    let mut ictx = match input(&input_path) {
-        Ok(ctx) => ctx,
-        Err(e) => {
-            return Err(VideoError::new(
-                VideoErrorCode::InvalidInput,
-                Some(format!("无法打开视频文件: {}", e)),
-            ))
+    let mut ictx = open_input(input_path.as_ref(), &config::default_options())?;
+    resolve_duration(&mut ictx).ok_or(VideoError::new(
+        VideoErrorCode::InvalidInput,
+        Some("无法确定视频时长".to_string()),
+    ))
+}
+
+/// 探测得到的视频信息
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoInfo {
+    /// 时长（秒），无法确定时为 `None`
+    pub duration: Option<f64>,
+    /// 编码宽度（像素），不考虑旋转
+    pub width: u32,
+    /// 编码高度（像素），不考虑旋转
+    pub height: u32,
+    /// 平均帧率，容器没有声明时为 `None`
+    pub fps: Option<f64>,
+    /// 编解码器名称，例如 "h264"
+    pub codec: String,
+    /// 码率（比特每秒），容器和流都没有声明时为 `None`
+    pub bit_rate: Option<u64>,
+    /// 解码器输出的像素格式，例如 "yuv420p"
+    pub pixel_format: String,
+    /// 容器中的流数量，包括音频和字幕
+    pub stream_count: u32,
+    /// 显示时需要顺时针旋转的角度（0、90、180或270），来自容器中的显示矩阵
+    pub rotation: u32,
+}
+
+/// 探测视频的基本信息，不解码任何帧
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+///
+/// # 返回
+/// * `Result<VideoInfo, VideoError>` - 成功时返回视频信息
+pub fn probe<P: AsRef<Path>>(input_path: P) -> Result<VideoInfo, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let options = config::default_options();
+    let mut ictx = open_input(input_path.as_ref(), &options)?;
+    let duration = resolve_duration(&mut ictx);
+
+    let stream = ictx
+        .stream(select_video_stream(&ictx, &options)?)
+        .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+    // 只打开解码器读取参数，不发送任何数据包
+    let decoder = open_decoder(&stream, &options)?;
+
+    let rate = stream.avg_frame_rate();
+    let fps = (rate.numerator() > 0 && rate.denominator() > 0).then(|| f64::from(rate));
+
+    // 容器的总码率优先，否则使用视频流的码率
+    let bit_rate = Some(ictx.bit_rate())
+        .filter(|&rate| rate > 0)
+        .or_else(|| Some(decoder.bit_rate() as i64).filter(|&rate| rate > 0))
+        .map(|rate| rate as u64);
+
+    Ok(VideoInfo {
+        duration,
+        width: decoder.width(),
+        height: decoder.height(),
+        fps,
+        codec: stream.parameters().id().name().to_string(),
+        bit_rate,
+        pixel_format: decoder
+            .format()
+            .descriptor()
+            .map_or("unknown", |descriptor| descriptor.name())
+            .to_string(),
+        stream_count: ictx.nb_streams(),
+        rotation: stream_rotation(&stream),
+    })
+}
+
+/// 探测内存中视频数据的基本信息
+pub fn probe_memory(input_data: &[u8]) -> Result<VideoInfo, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    with_temp_file(input_data, |path| probe(path))
+}
+
+// 流的显示旋转角度（顺时针，0、90、180或270），来自容器中的显示矩阵
+pub(crate) fn stream_rotation(stream: &ffmpeg::format::stream::Stream) -> u32 {
+    // 安全性：只读取流参数中的附加数据，显示矩阵固定为9个i32
+    unsafe {
+        let parameters = (*stream.as_ptr()).codecpar;
+        if parameters.is_null() {
+            return 0;
         }
-    };
+        let side_data = ffi::av_packet_side_data_get(
+            (*parameters).coded_side_data,
+            (*parameters).nb_coded_side_data,
+            ffi::AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+        );
+        if side_data.is_null() || (*side_data).size < 9 * std::mem::size_of::<i32>() {
+            return 0;
+        }
+
+        display_rotation((*side_data).data as *const i32)
+    }
+}
+
+// 显示矩阵对应的顺时针旋转角度（0、90、180或270）
+// 安全性：`matrix` 必须指向9个i32
+unsafe fn display_rotation(matrix: *const i32) -> u32 {
+    // av_display_rotation_get 返回逆时针角度
+    let angle = ffi::av_display_rotation_get(matrix);
+    if angle.is_nan() {
+        return 0;
+    }
+    ((-angle / 90.0).round() as i64).rem_euclid(4) as u32 * 90
+}
+
+/// 抽帧输出的预估结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputEstimate {
+    /// 输出宽度（像素）
+    pub width: u32,
+    /// 输出高度（像素）
+    pub height: u32,
+    /// 输出数据的字节数
+    pub bytes: usize,
+}
 
-    // 查找最佳视频流
+impl OutputEstimate {
+    /// 根据帧的尺寸信息和旋转元数据计算输出的尺寸和大小，与抽帧时的计算方式相同
+    ///
+    /// # 参数
+    /// * `info` - 帧的存储尺寸和显示尺寸
+    /// * `rotation` - 容器旋转元数据对应的顺时针角度，`ignore_rotation` 时应传入 `Rotation::None`
+    /// * `options` - 之后抽帧时将使用的选项
+    ///
+    /// # 返回
+    /// * `Result<OutputEstimate, VideoError>` - 成功时返回预估结果
+    pub fn new(
+        info: &FrameInfo,
+        rotation: Rotation,
+        options: &ExtractOptions,
+    ) -> Result<Self, VideoError> {
+        // 非正方形像素换算为显示尺寸；要求的输出宽高对应旋转后的画面，超出输出大小预算时会等比缩小
+        let (width, height) = info.base_size(options);
+        let (width, height) = rotated_output_size(
+            width,
+            height,
+            options.output_format.bytes_per_pixel(),
+            rotation,
+            options,
+        )?;
+
+        // 按旋转元数据自动旋转，再应用调用方的变换；旋转90/270度时输出的宽高互换
+        let (width, height) = if options
+            .transform
+            .after_rotation(rotation)
+            .rotation
+            .swaps_dimensions()
+        {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        // 行对齐只作用于原始像素输出
+        let alignment = options
+            .output_row_alignment
+            .filter(|_| options.output_encoding == OutputEncoding::Raw);
+        Ok(OutputEstimate {
+            width,
+            height,
+            bytes: options
+                .output_format
+                .aligned_frame_bytes(width, height, alignment),
+        })
+    }
+}
+
+/// 只根据探测信息预估抽帧输出的尺寸和大小，不解码任何帧
+///
+/// 调用方可以据此预先分配界面占位，或在抽帧前检查大小预算。
+///
+/// # 参数
+/// * `input_path` - 输入视频文件的路径
+/// * `options` - 之后抽帧时将使用的选项
+///
+/// # 返回
+/// * `Result<OutputEstimate, VideoError>` - 成功时返回预估结果
+pub fn estimate_output<P: AsRef<Path>>(
+    input_path: P,
+    options: &ExtractOptions,
+) -> Result<OutputEstimate, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let ictx = open_input(input_path.as_ref(), options)?;
+    estimate_input_output(&ictx, options)
+}
+
+// 根据已打开的输入中选中的视频流预估抽帧输出，不解码任何帧
+pub(crate) fn estimate_input_output(
+    ictx: &Input,
+    options: &ExtractOptions,
+) -> Result<OutputEstimate, VideoError> {
     let video_stream = ictx
-        .streams()
-        .best(Type::Video)
+        .stream(select_video_stream(ictx, options)?)
         .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
 
-    let video_stream_index = video_stream.index();
+    // 安全性：codecpar 在流的生命周期内始终有效
+    let (width, height, sample_aspect_ratio) = unsafe {
+        let ptr = video_stream.parameters().as_ptr();
+        ((*ptr).width, (*ptr).height, (*ptr).sample_aspect_ratio)
+    };
+    validation::validate_dimensions(width, height)?;
 
-    // 获取解码器
-    // 使用parameters方法获取流参数，然后创建解码器上下文
-    let context_decoder = match ffmpeg::codec::context::Context::from_parameters(video_stream.parameters()) {
-        Ok(context) => context,
-        Err(e) => {
-            return Err(VideoError::new(
-                VideoErrorCode::DecoderFailed,
-                Some(format!("无法创建解码器上下文: {}", e)),
-            ))
-        }
+    let info = FrameInfo::new(width as u32, height as u32, sample_aspect_ratio.into());
+    let rotation = if options.ignore_rotation {
+        Rotation::None
+    } else {
+        Rotation::from_degrees(stream_rotation(&video_stream))
     };
-    
-    // 从上下文创建视频解码器
-    let mut decoder = match context_decoder.decoder().video() {
-        Ok(dec) => dec,
-        Err(e) => {
-            return Err(VideoError::new(
-                VideoErrorCode::DecoderFailed,
-                Some(format!("无法创建解码器: {}", e)),
-            ))
-        }
+    OutputEstimate::new(&info, rotation, options)
+}
+
+// 打开输入文件并检查容器结构
+pub(crate) fn open_input(input_path: &Path, options: &ExtractOptions) -> Result<Input, VideoError> {
+    let interrupt = ffi::AVIOInterruptCB {
+        callback: None,
+        opaque: ptr::null_mut(),
     };
+    open_interruptible_input(input_path, options, interrupt)
+}
 
-    // 计算目标时间戳
-    let time_base = video_stream.time_base();
-    let target_ts =
-        (time_sec * f64::from(time_base.denominator()) / f64::from(time_base.numerator())) as i64;
+// 打开输入文件，`interrupt` 在 avformat_open_input 之前安装，
+// 打开、探测和之后的每次读取都会调用它，返回非零值时阻塞中的操作立即失败
+pub(crate) fn open_interruptible_input(
+    input_path: &Path,
+    options: &ExtractOptions,
+    interrupt: ffi::AVIOInterruptCB,
+) -> Result<Input, VideoError> {
+    // 重试时使用的探测数据量（字节）和探测时长（微秒）
+    const RETRY_PROBESIZE: &str = "50000000";
+    const RETRY_ANALYZEDURATION: &str = "30000000";
 
-    // 定位到目标时间戳
-    // 注意: 我们使用的是进行时间定位的优化方法
-    if let Err(e) = ictx.seek(
-        target_ts,
-        std::ops::Range {
-            start: 0,
-            end: target_ts,
-        },
-    ) {
-        return Err(VideoError::new(
-            VideoErrorCode::SeekFailed,
-            Some(format!("无法定位到目标时间点: {}", e)),
-        ));
-    }
+    let ictx = open_input_with(input_path, options.format_dictionary(), interrupt)?;
 
-    // 创建缩放器，将帧转换为 RGB24 格式
-    let mut scaler = match Context::get(
-        decoder.format(),
-        decoder.width(),
-        decoder.height(),
-        ffmpeg::util::format::Pixel::RGB24,
-        decoder.width(),
-        decoder.height(),
-        Flags::BILINEAR,
-    ) {
-        Ok(s) => s,
-        Err(e) => {
-            return Err(VideoError::new(
-                VideoErrorCode::FFmpegError,
-                Some(format!("创建缩放器失败: {}", e)),
-            ))
+    // 没有找到视频流，但容器允许流在后面才出现（例如PAT/PMT出现得很晚的TS）时，
+    // 加大探测范围重试一次，仍然没有时按原结果返回
+    if ictx.streams().best(Type::Video).is_none() && may_have_late_streams(&ictx) {
+        log_capture::warn("初次探测没有找到视频流，加大探测范围重试");
+        let mut dictionary = options.format_dictionary();
+        dictionary.set("probesize", RETRY_PROBESIZE);
+        dictionary.set("analyzeduration", RETRY_ANALYZEDURATION);
+        if let Ok(retried) = open_input_with(input_path, dictionary, interrupt) {
+            if retried.streams().best(Type::Video).is_some() {
+                return Ok(retried);
+            }
         }
+    }
+
+    Ok(ictx)
+}
+
+// 容器是否可能在探测结束之后才出现新的流
+fn may_have_late_streams(ictx: &Input) -> bool {
+    // 安全性：只读取已打开的格式上下文中的字段
+    let ctx_flags = unsafe { (*ictx.as_ptr()).ctx_flags };
+    ictx.nb_streams() == 0 || ctx_flags & ffi::AVFMTCTX_NOHEADER as i32 != 0
+}
+
+// 使用给定的解复用器选项打开输入文件并检查容器结构
+fn open_input_with(
+    input_path: &Path,
+    dictionary: ffmpeg::Dictionary<'static>,
+    interrupt: ffi::AVIOInterruptCB,
+) -> Result<Input, VideoError> {
+    let open_error = |e: ffmpeg::Error| {
+        VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("无法打开视频文件: {}", e)),
+        )
     };
+    let path = CString::new(input_path.to_string_lossy().as_bytes()).map_err(|_| {
+        VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some(format!("路径中包含空字符: {}", input_path.display())),
+        )
+    })?;
 
-    // 函数：处理已解码的帧
-    let mut process_frame = |frame: &Video| -> Result<Vec<u8>, VideoError> {
-        // 将帧数据转换为 RGB 格式
-        let mut rgb_frame = Video::empty();
-        if let Err(e) = scaler.run(frame, &mut rgb_frame) {
+    // 与 ffmpeg::format::input_with_dictionary 相同，只是先分配格式上下文，以便在打开之前安装中断回调
+    // 安全性：打开失败时 avformat_open_input 会释放 ps，成功后格式上下文由 Input 持有
+    let ictx = unsafe {
+        let mut ps = ffi::avformat_alloc_context();
+        if ps.is_null() {
             return Err(VideoError::new(
-                VideoErrorCode::FFmpegError,
-                Some(format!("颜色转换失败: {}", e)),
+                VideoErrorCode::InitFailed,
+                Some("无法创建格式上下文".to_string()),
             ));
         }
+        (*ps).interrupt_callback = interrupt;
 
-        // 提取RGB数据
-        let data = rgb_frame.data(0);
-        let stride = rgb_frame.stride(0);
-        let height = rgb_frame.height();
+        let mut opts = dictionary.disown();
+        let res = ffi::avformat_open_input(&mut ps, path.as_ptr(), ptr::null(), &mut opts);
+        ffmpeg::Dictionary::own(opts);
+        if res < 0 {
+            return Err(open_error(ffmpeg::Error::from(res)));
+        }
 
-        // 缓存通常包含项对齐字节，因此我们需要通过展平行数据来清除它们
-        let mut result = Vec::with_capacity(stride * height as usize);
-        for i in 0..height {
-            let line_start = i as usize * stride;
-            let line_end = line_start + rgb_frame.width() as usize * 3; // RGB每像素三字节
-            result.extend_from_slice(&data[line_start..line_end]);
+        let res = ffi::avformat_find_stream_info(ps, ptr::null_mut());
+        let ictx = Input::wrap(ps);
+        if res < 0 {
+            return Err(open_error(ffmpeg::Error::from(res)));
         }
+        ictx
+    };
+
+    // 在交给解码器之前检查容器结构
+    validation::validate_input(&ictx)?;
 
-        Ok(result)
+    Ok(ictx)
+}
+
+// 选择要解码的视频流
+// 未指定目标高度时使用FFmpeg认为最佳的视频流；
+// 否则在多个码率/多条视频轨中选择不低于目标高度的最小一条，都低于目标时选最高的一条
+pub(crate) fn select_video_stream(
+    ictx: &Input,
+    options: &ExtractOptions,
+) -> Result<usize, VideoError> {
+    let best = ictx
+        .streams()
+        .best(Type::Video)
+        .map(|stream| stream.index())
+        .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+
+    let target = match options.target_height {
+        Some(target) => target as i32,
+        None => return Ok(best),
     };
 
-    // 读取并处理帧
-    let mut decoded_frame = Video::empty();
+    let heights: Vec<(usize, i32)> = ictx
+        .streams()
+        .filter(|stream| stream.parameters().medium() == Type::Video)
+        // 封面图片不是真正的视频轨
+        .filter(|stream| {
+            !stream
+                .disposition()
+                .contains(ffmpeg::format::stream::Disposition::ATTACHED_PIC)
+        })
+        .map(|stream| {
+            // 安全性：codecpar 在流的生命周期内始终有效
+            let height = unsafe { (*stream.parameters().as_ptr()).height };
+            (stream.index(), height)
+        })
+        .filter(|(_, height)| *height > 0)
+        .collect();
 
-    // 处理包起来，直到我们发现一个帧或数据包结束
-    for (stream, packet) in ictx.packets() {
-        if stream.index() == video_stream_index {
-            // 将包发送给解码器
-            if let Err(e) = decoder.send_packet(&packet) {
-                return Err(VideoError::new(
-                    VideoErrorCode::DecoderFailed,
-                    Some(format!("发送数据包失败: {}", e)),
-                ));
+    let selected = heights
+        .iter()
+        .filter(|(_, height)| *height >= target)
+        .min_by_key(|(_, height)| *height)
+        .or_else(|| heights.iter().max_by_key(|(_, height)| *height))
+        .map(|(index, _)| *index);
+
+    Ok(selected.unwrap_or(best))
+}
+
+// 丢弃除指定视频流之外的所有流，解复用器不再读取它们的数据
+pub(crate) fn discard_other_streams(ictx: &mut Input, keep: usize) {
+    // 安全性：只修改已打开的格式上下文中各个流的 discard 字段
+    unsafe {
+        let ctx = ictx.as_mut_ptr();
+        for index in 0..(*ctx).nb_streams as usize {
+            if index != keep {
+                (**(*ctx).streams.add(index)).discard = ffi::AVDiscard::AVDISCARD_ALL;
             }
+        }
+    }
+}
 
-            // 从解码器中接收帧
-            while decoder.receive_frame(&mut decoded_frame).is_ok() {
-                // 获取帧的时间戳
-                let timestamp = decoded_frame.timestamp();
+// 按顺序读取数据包及其所属流的索引，到达结尾或读取出错时结束
+// 不使用 `Input::packets`：它在读取出错时不断重试，截断或损坏的文件会让循环永远不结束
+pub(crate) fn read_packets(ictx: &mut Input) -> impl Iterator<Item = (usize, Packet)> + '_ {
+    iter::from_fn(move || {
+        let mut packet = Packet::empty();
+        packet.read(ictx).ok().map(|()| (packet.stream(), packet))
+    })
+}
 
-                // 如果帧的时间戳等于或大于目标时间戳，或者没有时间戳，则处理该帧
-                if timestamp.is_none() || timestamp.unwrap() >= target_ts {
-                    // 找到目标帧，直接返回处理结果
-                    return process_frame(&decoded_frame);
-                }
+// 定位到指定时间点（秒）之前的关键帧，时间点不大于0时不定位
+pub(crate) fn seek_before(ictx: &mut Input, time_sec: f64) -> Result<(), VideoError> {
+    if time_sec <= 0.0 {
+        return Ok(());
+    }
+    // Input::seek 的时间戳以 AV_TIME_BASE（微秒）为单位，不是流的时间基
+    let ts = (time_sec * f64::from(ffi::AV_TIME_BASE)) as i64;
+    ictx.seek(ts, ..ts).map_err(|e| {
+        VideoError::new(
+            VideoErrorCode::SeekFailed,
+            Some(format!("无法定位到目标时间点: {}", e)),
+        )
+    })
+}
+
+// 把指定流的数据包送入解码器，每解码出一帧调用一次 `on_frame`，直到读完或回调要求停止
+// 读完后送入EOF，取出解码器中剩余的帧
+fn decode_packets<T, F>(
+    ictx: &mut Input,
+    stream_index: usize,
+    decoder: &mut ffmpeg::decoder::Opened,
+    frame: &mut T,
+    mut on_frame: F,
+) -> Result<(), VideoError>
+where
+    T: DerefMut<Target = ffmpeg::Frame>,
+    F: FnMut(&mut T) -> Result<ControlFlow<()>, VideoError>,
+{
+    for (index, packet) in read_packets(ictx) {
+        if index != stream_index {
+            continue;
+        }
+        // 个别损坏的数据包不影响整体结果
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(frame).is_ok() {
+            if on_frame(frame)?.is_break() {
+                return Ok(());
             }
         }
     }
 
-    // 告诉解码器没有更多数据包，并尝试从中获取最后的帧
-    if let Err(e) = decoder.send_eof() {
-        return Err(VideoError::new(
-            VideoErrorCode::DecoderFailed,
-            Some(format!("发送EOF失败: {}", e)),
-        ));
+    if decoder.send_eof().is_ok() {
+        while decoder.receive_frame(frame).is_ok() {
+            if on_frame(frame)?.is_break() {
+                break;
+            }
+        }
     }
+    Ok(())
+}
 
-    // 接收解码器中的任何剩余帧
-    while decoder.receive_frame(&mut decoded_frame).is_ok() {
-        let timestamp = decoded_frame.timestamp();
-        if timestamp.is_none() || timestamp.unwrap() >= target_ts {
-            // 找到合适的帧，返回处理结果
-            return process_frame(&decoded_frame);
+// 打开输入中选中的视频流，可以多次定位并顺序解码
+pub(crate) struct VideoScan {
+    ictx: Input,
+    stream_index: usize,
+    time_base: f64,
+    decoder: ffmpeg::decoder::Video,
+}
+
+impl VideoScan {
+    // 打开输入、选择视频流并创建解码器，其他流的数据不再读取
+    pub(crate) fn open(input_path: &Path, options: &ExtractOptions) -> Result<Self, VideoError> {
+        let mut ictx = open_input(input_path, options)?;
+        let stream_index = select_video_stream(&ictx, options)?;
+        discard_other_streams(&mut ictx, stream_index);
+        let (time_base, decoder) = {
+            let stream = ictx
+                .stream(stream_index)
+                .ok_or(VideoError::new(VideoErrorCode::NoVideoStream, None))?;
+            let decoder = open_decoder(&stream, options)?;
+            (f64::from(stream.time_base()), decoder)
+        };
+        Ok(VideoScan {
+            ictx,
+            stream_index,
+            time_base,
+            decoder,
+        })
+    }
+
+    // 已打开的输入，用于读取时长等容器信息
+    pub(crate) fn input(&mut self) -> &mut Input {
+        &mut self.ictx
+    }
+
+    // 定位到指定时间点（秒）之前的关键帧，并清空解码器中上一次留下的帧
+    pub(crate) fn seek(&mut self, time_sec: f64) -> Result<(), VideoError> {
+        seek_before(&mut self.ictx, time_sec)?;
+        self.decoder.flush();
+        Ok(())
+    }
+
+    // 从当前位置顺序解码，回调收到每一帧和它的显示时间（秒），没有时间戳的帧被跳过；
+    // 回调可以取走帧的内容（例如 `mem::replace`）
+    pub(crate) fn for_each_frame<F>(&mut self, mut f: F) -> Result<(), VideoError>
+    where
+        F: FnMut(&mut Video, f64) -> Result<ControlFlow<()>, VideoError>,
+    {
+        let time_base = self.time_base;
+        let mut frame = Video::empty();
+        decode_packets(
+            &mut self.ictx,
+            self.stream_index,
+            &mut self.decoder,
+            &mut frame,
+            |frame| match frame.timestamp() {
+                Some(ts) => f(frame, ts as f64 * time_base),
+                None => Ok(ControlFlow::Continue(())),
+            },
+        )
+    }
+}
+
+// 从指定时间点（秒）之前的关键帧开始顺序解码选中的视频流，见 `VideoScan::for_each_frame`
+pub(crate) fn for_each_video_frame<F>(
+    input_path: &Path,
+    options: &ExtractOptions,
+    start: f64,
+    f: F,
+) -> Result<(), VideoError>
+where
+    F: FnMut(&mut Video, f64) -> Result<ControlFlow<()>, VideoError>,
+{
+    let mut scan = VideoScan::open(input_path, options)?;
+    scan.seek(start)?;
+    scan.for_each_frame(f)
+}
+
+// 从指定时间点（秒）之前的关键帧开始顺序解码主音频流，没有音频流时返回错误
+// 回调收到每一帧和它的开始时间（秒），帧没有时间戳时为 `None`
+pub(crate) fn for_each_audio_frame<F>(
+    input_path: &Path,
+    options: &ExtractOptions,
+    start: f64,
+    mut f: F,
+) -> Result<(), VideoError>
+where
+    F: FnMut(&Audio, Option<f64>) -> Result<ControlFlow<()>, VideoError>,
+{
+    let mut ictx = open_input(input_path, options)?;
+    let (stream_index, time_base, mut decoder) = {
+        let stream = ictx.streams().best(Type::Audio).ok_or(VideoError::new(
+            VideoErrorCode::InvalidInput,
+            Some("未找到音频流".to_string()),
+        ))?;
+        let decoder = open_audio_decoder(&stream)?;
+        (stream.index(), f64::from(stream.time_base()), decoder)
+    };
+    discard_other_streams(&mut ictx, stream_index);
+    seek_before(&mut ictx, start)?;
+
+    let mut frame = Audio::empty();
+    decode_packets(&mut ictx, stream_index, &mut decoder, &mut frame, |frame| {
+        let time = frame.timestamp().map(|ts| ts as f64 * time_base);
+        f(frame, time)
+    })
+}
+
+// 把抽帧位置换算成时间点（秒）
+pub(crate) fn resolve_position(ictx: &mut Input, position: Position) -> Result<f64, VideoError> {
+    // 无法从流信息得到帧间隔时使用的默认值
+    const DEFAULT_FRAME_INTERVAL: f64 = 0.1;
+
+    let percent = match position {
+        Position::Seconds(time_sec) => return Ok(time_sec),
+        Position::Percent(percent) => percent,
+    };
+
+    validation::validate_percent(percent)?;
+
+    let duration = resolve_duration(ictx).ok_or(VideoError::new(
+        VideoErrorCode::InvalidInput,
+        Some("无法确定视频时长，不能按百分比定位".to_string()),
+    ))?;
+
+    // 100% 应该对应最后一帧，而不是结尾之后，否则找不到任何帧
+    let frame_interval = ictx
+        .streams()
+        .best(Type::Video)
+        .map(|stream| stream.avg_frame_rate())
+        .filter(|rate| rate.numerator() > 0 && rate.denominator() > 0)
+        .map(|rate| f64::from(rate.invert()))
+        .unwrap_or(DEFAULT_FRAME_INTERVAL);
+    let offset = (duration * percent / 100.0).min((duration - frame_interval).max(0.0));
+
+    Ok(start_time_secs(ictx) + offset)
+}
+
+// 容器的起始时间（秒），未声明时为0
+pub(crate) fn start_time_secs(ictx: &Input) -> f64 {
+    // 安全性：只读取已打开的格式上下文中的字段
+    let start_time = unsafe { (*ictx.as_ptr()).start_time };
+    if start_time == ffi::AV_NOPTS_VALUE {
+        0.0
+    } else {
+        start_time as f64 * f64::from(ffmpeg::rescale::TIME_BASE)
+    }
+}
+
+// 解析时长（秒），声明的时长缺失时回退到读取末尾时间戳
+// 注意：回退路径会移动读取位置，结束后尝试回到文件开头
+pub(crate) fn resolve_duration(ictx: &mut Input) -> Option<f64> {
+    let time_base = f64::from(ffmpeg::rescale::TIME_BASE);
+
+    // 容器声明的时长
+    if ictx.duration() > 0 {
+        return Some(ictx.duration() as f64 * time_base);
+    }
+
+    // 各个流声明的时长，取最长的一个
+    let declared = ictx
+        .streams()
+        .filter(|stream| stream.duration() > 0)
+        .map(|stream| stream.duration() as f64 * f64::from(stream.time_base()))
+        .fold(None, |max: Option<f64>, d| {
+            Some(max.map_or(d, |m| m.max(d)))
+        });
+    if declared.is_some() {
+        return declared;
+    }
+
+    estimate_duration_from_tail(ictx)
+}
+
+// 定位到文件末尾附近，用最后一个数据包的结束时间减去起始时间估算时长
+fn estimate_duration_from_tail(ictx: &mut Input) -> Option<f64> {
+    // 只读取末尾这么多字节的数据
+    const TAIL_BYTES: i64 = 4 * 1024 * 1024;
+
+    // 安全性：只读取已打开的格式上下文中的字段
+    let file_size = unsafe {
+        let pb = (*ictx.as_ptr()).pb;
+        if pb.is_null() {
+            -1
+        } else {
+            ffi::avio_size(pb)
+        }
+    };
+
+    // 按字节定位；不支持时从当前位置一直读到结尾
+    if file_size > TAIL_BYTES {
+        unsafe {
+            ffi::avformat_seek_file(
+                ictx.as_mut_ptr(),
+                -1,
+                i64::MIN,
+                file_size - TAIL_BYTES,
+                i64::MAX,
+                ffi::AVSEEK_FLAG_BYTE as i32,
+            );
         }
     }
 
-    // 没有找到合适的帧，返回特定错误
-    Err(VideoError::new(VideoErrorCode::FrameNotFound, None))
+    let time_bases: Vec<f64> = ictx
+        .streams()
+        .map(|stream| f64::from(stream.time_base()))
+        .collect();
+    let mut end: Option<f64> = None;
+    for (index, packet) in read_packets(ictx) {
+        if let (Some(pts), Some(time_base)) = (packet.pts(), time_bases.get(index)) {
+            let packet_end = (pts + packet.duration().max(0)) as f64 * time_base;
+            end = Some(end.map_or(packet_end, |e| e.max(packet_end)));
+        }
+    }
+
+    // 回到文件开头，方便调用方继续使用同一个上下文
+    let _ = ictx.seek(0, ..);
+
+    let start = start_time_secs(ictx);
+    end.map(|end| end - start).filter(|d| *d > 0.0)
 }
 
 // 从内存中的视频数据提取帧
-// 这个函数将数据写入临时文件，然后使用文件路径版的extract_frame函数
+// 这个函数将数据写入临时存储，然后使用文件路径版的extract_frame函数
 // 这是为了保持与原有二进制数据接口的兼容性
-pub fn extract_frame_from_memory(input_data: &[u8], time_sec: f64) -> Result<Vec<u8>, VideoError> {
+pub fn extract_frame_from_memory(input_data: &[u8], time_sec: f64) -> Result<RgbFrame, VideoError> {
+    extract_frame_from_memory_with_options(input_data, time_sec, &config::default_options())
+}
+
+// 使用自定义选项从内存中的视频数据提取特定位置的帧
+pub fn extract_frame_from_memory_with_options<T: Into<Position>>(
+    input_data: &[u8],
+    position: T,
+    options: &ExtractOptions,
+) -> Result<RgbFrame, VideoError> {
+    // 确保FFmpeg已初始化
+    ffmpeg_init::initialize();
+
+    let position = position.into();
+    with_temp_file(input_data, |path| {
+        extract_frame_with_options(path, position, options)
+    })
+}
+
+// 使用自定义选项从内存中的视频数据一次提取多个时间点的帧
+pub fn extract_frames_from_memory(
+    input_data: &[u8],
+    times: &[f64],
+    options: &ExtractOptions,
+) -> Result<Vec<RgbFrame>, VideoError> {
     // 确保FFmpeg已初始化
     ffmpeg_init::initialize();
 
-    // 创建一个临时文件来存储数据
-    let temp_dir = std::env::temp_dir();
-    let temp_file_path = temp_dir.join(format!(
-        "video_capture_temp_{}.mp4",
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis()
-    ));
+    with_temp_file(input_data, |path| {
+        extract_frames_with_options(path, times, options)
+    })
+}
 
-    // 将数据写入临时文件
-    match std::fs::write(&temp_file_path, input_data) {
-        Ok(_) => {
-            // 文件写入成功，调用文件路径版的函数
-            let result = extract_frame(&temp_file_path, time_sec);
+// 把内存中的数据写入临时存储，用文件路径调用 `f`，结束后删除临时文件
+pub(crate) fn with_temp_file<T, F>(input_data: &[u8], f: F) -> Result<T, VideoError>
+where
+    F: FnOnce(&Path) -> Result<T, VideoError>,
+{
+    validation::validate_buffer(input_data)?;
 
-            // 删除临时文件
-            let _ = std::fs::remove_file(&temp_file_path); // 忽略清理错误
+    // 通过当前的临时存储后端落地数据
+    let storage = temp_storage::current();
+    let temp_file_path = storage.store(input_data)?;
 
-            result
-        }
-        Err(e) => Err(VideoError::new(
-            VideoErrorCode::InvalidInput,
-            Some(format!("无法写入临时文件: {}", e)),
-        )),
+    // 文件写入成功，调用文件路径版的函数
+    let result = f(&temp_file_path);
+
+    // 删除临时文件，忽略清理错误
+    storage.remove(&temp_file_path);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试按行布局重新排列：行尾补0对齐，从最后一行开始时每个平面各自倒序
+    #[test]
+    fn test_arrange_rows() {
+        // 2x2的RGB24，每行6字节
+        let rgb: Vec<u8> = (1..=12).collect();
+        let aligned = arrange_rows(rgb.clone(), 2, 2, OutputFormat::Rgb24, Some(4), false);
+        assert_eq!(aligned, [1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12, 0, 0]);
+        let aligned = arrange_rows(rgb.clone(), 2, 2, OutputFormat::Rgb24, Some(256), false);
+        assert_eq!(aligned.len(), 512);
+        assert_eq!(aligned[256..262], rgb[6..]);
+        assert_eq!(
+            arrange_rows(rgb.clone(), 2, 2, OutputFormat::Rgb24, None, true),
+            [7, 8, 9, 10, 11, 12, 1, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            arrange_rows(rgb.clone(), 2, 2, OutputFormat::Rgb24, None, false),
+            rgb
+        );
+
+        // 3x3的YUV420P：Y平面3x3，U、V平面各2x2
+        let yuv: Vec<u8> = (1..=17).collect();
+        let arranged = arrange_rows(yuv, 3, 3, OutputFormat::Yuv420p, Some(4), true);
+        assert_eq!(
+            arranged.len(),
+            OutputFormat::Yuv420p.aligned_frame_bytes(3, 3, Some(4))
+        );
+        assert_eq!(
+            arranged,
+            [
+                7, 8, 9, 0, 4, 5, 6, 0, 1, 2, 3, 0, // Y
+                12, 13, 0, 0, 10, 11, 0, 0, // U
+                16, 17, 0, 0, 14, 15, 0, 0, // V
+            ]
+        );
+    }
+
+    // 测试取帧规则：越过目标时返回上一帧，正好是目标时返回该帧，
+    // 输入结束时只在目标落在上一帧的显示时长内时返回上一帧
+    #[test]
+    fn test_frame_picker() {
+        let frame = |pts: i64, key: bool| {
+            let mut frame = Video::new(Pixel::GRAY8, 2, 2);
+            frame.set_pts(Some(pts));
+            // 安全性：只修改测试自己创建的帧
+            unsafe { (*frame.as_mut_ptr()).key_frame = i32::from(key) };
+            frame
+        };
+        let picker = || FramePicker {
+            keep_keyframe: true,
+            sample_aspect_ratio: None,
+            previous: None,
+            previous_end: i64::MIN,
+            keyframe: None,
+            warnings: Vec::new(),
+        };
+
+        // 越过目标：返回上一帧，以及它之前的关键帧
+        let mut p = picker();
+        assert!(p.offer(&mut frame(0, true), Target::Before(10)).is_none());
+        assert!(p.offer(&mut frame(10, false), Target::Before(20)).is_none());
+        let found = p.offer(&mut frame(20, false), Target::Past).unwrap();
+        assert_eq!(found.frame.pts(), Some(10));
+        assert_eq!(found.keyframe.map(|keyframe| keyframe.pts()), Some(Some(0)));
+
+        // 正好是目标
+        let mut p = picker();
+        assert!(p.offer(&mut frame(0, true), Target::Before(10)).is_none());
+        let found = p.offer(&mut frame(10, false), Target::Take).unwrap();
+        assert_eq!(found.frame.pts(), Some(10));
+        assert_eq!(found.keyframe.map(|keyframe| keyframe.pts()), Some(Some(0)));
+
+        // 第一帧就越过目标时返回这一帧
+        let mut p = picker();
+        let found = p.offer(&mut frame(30, true), Target::Past).unwrap();
+        assert_eq!(found.frame.pts(), Some(30));
+        assert!(found.keyframe.is_none());
+
+        // 输入结束
+        let mut p = picker();
+        assert!(p.offer(&mut frame(0, true), Target::Before(10)).is_none());
+        assert_eq!(p.finish(9).map(|found| found.frame.pts()), Some(Some(0)));
+        let mut p = picker();
+        assert!(p.offer(&mut frame(0, true), Target::Before(10)).is_none());
+        assert!(p.finish(10).is_none());
+    }
+
+    // 测试临时内存的估算：单场按一半的行数计入，超出上限时在分配之前返回错误
+    #[test]
+    fn test_temp_bytes() {
+        assert_eq!(field_rows(FieldSelection::Frame, 5), None);
+        assert_eq!(field_rows(FieldSelection::Top, 5), Some((0, 3)));
+        assert_eq!(field_rows(FieldSelection::Bottom, 5), Some((1, 2)));
+        assert_eq!(image_bytes(Pixel::YUV420P, 4, 4), 24);
+        assert_eq!(image_bytes(Pixel::None, 4, 4), 0);
+
+        // 解码帧24字节，缩放器输出和去掉填充后的副本各12字节
+        let options = ExtractOptions::default();
+        let identity = Transform::default();
+        let frame_only = temp_bytes(24, None, (4, 4), (2, 2), &identity, &options);
+        assert_eq!(frame_only, 24 + 12 * 2);
+        let with_field = temp_bytes(
+            24,
+            Some((Pixel::YUV420P, 4, 2)),
+            (4, 4),
+            (2, 2),
+            &identity,
+            &options,
+        );
+        assert_eq!(with_field, frame_only + 12);
+
+        let limited = ExtractOptions {
+            max_temp_bytes: Some(frame_only - 1),
+            ..ExtractOptions::default()
+        };
+        assert!(check_temp_bytes(frame_only - 1, &limited).is_ok());
+        assert_eq!(
+            check_temp_bytes(frame_only, &limited).unwrap_err().code,
+            VideoErrorCode::MemoryLimit
+        );
+        assert!(check_temp_bytes(usize::MAX, &options).is_ok());
     }
 }
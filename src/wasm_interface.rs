@@ -1,11 +1,79 @@
 // wasm_interface.rs
 // 提供WASM接口，处理与JavaScript的交互
 
-use crate::error::{log_error, VideoResult};
-use crate::video_processor;
+use crate::audio::{self, AudioClip, PcmFormat};
+use crate::best_frame::{self, PosterFrame};
+use crate::blurhash;
+use crate::build_info::BuildInfo;
+use crate::config;
+use crate::error::{log_error, VideoError, VideoErrorCode, VideoResult};
+use crate::frame_hash;
+use crate::histogram::{self, FrameHistogram};
+use crate::log_capture::LogLevel;
+use crate::loudness::{self, Loudness};
+use crate::memory_watch;
+use crate::options::{OutputEncoding, OutputFormat, Position};
+use crate::palette::{self, ColorPalette};
+use crate::preview::{self, PreviewFormat, PreviewOptions};
+use crate::remux;
+use crate::self_test::SelfTestReport;
+use crate::sprite_sheet::{self, SpriteSheet};
+use crate::thumbhash;
+use crate::video_processor::{self, RgbFrame, VideoInfo};
+use std::io::{Read, Seek, SeekFrom};
 use std::slice;
 use wasm_bindgen::prelude::*;
 
+#[wasm_bindgen(typescript_custom_section)]
+const RANDOM_ACCESS_SOURCE_TS: &'static str = r#"
+/**
+ * 可随机读取的数据源，例如基于OPFS FileSystemSyncAccessHandle的封装
+ */
+export interface RandomAccessSource {
+  /** 数据总字节数 */
+  size(): number;
+  /** 从 offset 处读取最多 length 个字节，读到结尾时返回空数组 */
+  readAt(offset: number, length: number): Uint8Array;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const MEMORY_GROWTH_HANDLER_TS: &'static str = r#"
+/**
+ * 线性内存即将越过阈值时的回调
+ */
+export interface MemoryGrowthHandler {
+  /** 当前大小、预估增长之后的大小和即将越过的阈值（字节），返回 false 时拒绝这次抽帧 */
+  onGrow(currentBytes: number, projectedBytes: number, threshold: number): boolean;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    /// JavaScript侧提供的可随机读取数据源
+    #[wasm_bindgen(typescript_type = "RandomAccessSource")]
+    pub type RandomAccessSource;
+
+    #[wasm_bindgen(method)]
+    fn size(this: &RandomAccessSource) -> f64;
+
+    #[wasm_bindgen(method, js_name = readAt)]
+    fn read_at(this: &RandomAccessSource, offset: f64, length: u32) -> Vec<u8>;
+
+    /// JavaScript侧提供的内存增长回调
+    #[wasm_bindgen(typescript_type = "MemoryGrowthHandler")]
+    pub type MemoryGrowthHandler;
+
+    // 回调抛出的异常不会穿过WASM
+    #[wasm_bindgen(method, catch, js_name = onGrow)]
+    fn on_grow(
+        this: &MemoryGrowthHandler,
+        current_bytes: f64,
+        projected_bytes: f64,
+        threshold: f64,
+    ) -> Result<bool, JsValue>;
+}
+
 /**
  * 从视频提取帧 - WebAssembly导出函数
  *
@@ -25,10 +93,694 @@ pub fn extract_video_frame(input_ptr: *const u8, input_len: usize, time_sec: f64
     let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
 
     // 调用视频处理器提取帧 - 使用内存数据版的函数
-    match video_processor::extract_frame_from_memory(input_data, time_sec) {
-        Ok(buffer) => {
+    into_video_result(video_processor::extract_frame_from_memory(
+        input_data, time_sec,
+    ))
+}
+
+/**
+ * 按视频时长的百分比提取帧 - WebAssembly导出函数
+ *
+ * 时长在WASM内部解析，前端无需先探测视频再换算时间点。
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param percent - 占视频时长的百分比(0 - 100)
+ * @returns 包含结果或错误信息的VideoResult对象
+ */
+#[wasm_bindgen(js_name = extractVideoFrameAtPercent)]
+pub fn extract_video_frame_at_percent(
+    input_ptr: *const u8,
+    input_len: usize,
+    percent: f64,
+) -> VideoResult {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    into_video_result(video_processor::extract_frame_from_memory_with_options(
+        input_data,
+        Position::Percent(percent),
+        &config::default_options(),
+    ))
+}
+
+/**
+ * 在时间点附近挑选最适合做封面的帧 - WebAssembly导出函数
+ *
+ * 在窗口内取若干候选帧，避开黑场、模糊和低对比度的画面
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param around_sec - 窗口中心的时间点(秒)
+ * @param window_sec - 窗口的长度(秒)
+ * @returns 包含结果或错误信息的VideoResult对象
+ */
+#[wasm_bindgen(js_name = selectBestFrame)]
+pub fn select_best_frame(
+    input_ptr: *const u8,
+    input_len: usize,
+    around_sec: f64,
+    window_sec: f64,
+) -> VideoResult {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    into_video_result(video_processor::with_temp_file(input_data, |path| {
+        best_frame::select_best_frame(path, around_sec, window_sec).map(|best| best.frame)
+    }))
+}
+
+/**
+ * 生成封面并分析叠加文字的位置 - WebAssembly导出函数
+ *
+ * 与selectBestFrame相同地挑选封面，另外返回画面各区域的繁杂程度，
+ * 界面可以把标题放在最安静的边缘带上；失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param around_sec - 窗口中心的时间点(秒)
+ * @param window_sec - 窗口的长度(秒)
+ * @param encoding - 封面的编码格式，Raw时为RGB24像素数据
+ * @returns 封面、尺寸和繁杂区域分析
+ */
+#[wasm_bindgen(js_name = generatePoster)]
+pub fn generate_poster(
+    input_ptr: *const u8,
+    input_len: usize,
+    around_sec: f64,
+    window_sec: f64,
+    encoding: OutputEncoding,
+) -> Result<PosterFrame, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    let mut options = config::default_options();
+    options.output_encoding = encoding;
+
+    video_processor::with_temp_file(input_data, |path| {
+        let best = best_frame::select_poster_frame(path, around_sec, window_sec, &options)?;
+        Ok(PosterFrame {
+            image: best.frame.data,
+            width: best.frame.width,
+            height: best.frame.height,
+            time: best.time,
+            busy_regions: best.busy_regions.unwrap_or_default(),
+        })
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 提取缩放到指定尺寸的帧 - WebAssembly导出函数
+ *
+ * 在WASM内部完成缩放，只需要显示缩略图时不必把全尺寸的RGB数据传回JavaScript。
+ * 只传宽或高时另一边按原始宽高比计算。
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param time_sec - 提取帧的时间点(秒)
+ * @param width - 输出宽度(像素)，可选
+ * @param height - 输出高度(像素)，可选
+ * @returns 包含结果或错误信息的VideoResult对象
+ */
+#[wasm_bindgen(js_name = extractVideoFrameScaled)]
+pub fn extract_video_frame_scaled(
+    input_ptr: *const u8,
+    input_len: usize,
+    time_sec: f64,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> VideoResult {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    let mut options = config::default_options();
+    options.output_width = width;
+    options.output_height = height;
+    into_video_result(video_processor::extract_frame_from_memory_with_options(
+        input_data, time_sec, &options,
+    ))
+}
+
+/**
+ * 提取指定像素格式的帧 - WebAssembly导出函数
+ *
+ * 例如Canvas需要RGBA，可以直接放入 ImageData，不必在JavaScript中逐像素重新打包。
+ * 作为WebGPU/WebGL纹理上传时可以指定行对齐，例如WebGPU的 bytesPerRow 要求256字节对齐。
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param time_sec - 提取帧的时间点(秒)
+ * @param format - 输出像素格式
+ * @param row_alignment - 每行字节数对齐到的倍数(2的幂)，可选；行尾用0填充
+ * @param bottom_up - 是否从最后一行开始排列(OpenGL纹理的约定)，可选，默认false
+ * @returns 包含结果或错误信息的VideoResult对象
+ */
+#[wasm_bindgen(js_name = extractVideoFrameAs)]
+pub fn extract_video_frame_as(
+    input_ptr: *const u8,
+    input_len: usize,
+    time_sec: f64,
+    format: OutputFormat,
+    row_alignment: Option<u32>,
+    bottom_up: Option<bool>,
+) -> VideoResult {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    let mut options = config::default_options();
+    options.output_format = format;
+    options.output_row_alignment = row_alignment;
+    options.bottom_up_rows = bottom_up.unwrap_or(false);
+    into_video_result(video_processor::extract_frame_from_memory_with_options(
+        input_data, time_sec, &options,
+    ))
+}
+
+/**
+ * 提取帧并编码为图片 - WebAssembly导出函数
+ *
+ * 返回的数据是完整的PNG/JPEG/WebP文件内容，可以直接构造 Blob 显示或上传，
+ * 比传回原始RGB数据小得多。
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param time_sec - 提取帧的时间点(秒)
+ * @param encoding - 图片编码方式
+ * @param quality - JPEG编码质量(1 - 100)，可选；WebP只支持无损编码，设置时返回错误
+ * @returns 包含结果或错误信息的VideoResult对象
+ */
+#[wasm_bindgen(js_name = extractVideoFrameEncoded)]
+pub fn extract_video_frame_encoded(
+    input_ptr: *const u8,
+    input_len: usize,
+    time_sec: f64,
+    encoding: OutputEncoding,
+    quality: Option<u8>,
+) -> VideoResult {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    let mut options = config::default_options();
+    options.output_encoding = encoding;
+    options.encoding_quality = quality;
+    into_video_result(video_processor::extract_frame_from_memory_with_options(
+        input_data, time_sec, &options,
+    ))
+}
+
+/**
+ * 从可随机读取的数据源提取帧 - WebAssembly导出函数
+ *
+ * 数据按需通过 source.readAt 读取，不需要把整个文件复制进WASM内存，
+ * 适合在Worker中处理OPFS里的超大本地文件。
+ *
+ * @param source - 实现了 RandomAccessSource 接口的数据源
+ * @param time_sec - 提取帧的时间点(秒)
+ * @returns 包含结果或错误信息的VideoResult对象
+ */
+#[wasm_bindgen(js_name = extractVideoFrameFromSource)]
+pub fn extract_video_frame_from_source(source: RandomAccessSource, time_sec: f64) -> VideoResult {
+    let reader = SourceReader {
+        size: source.size().max(0.0) as u64,
+        source,
+        position: 0,
+    };
+
+    into_video_result(video_processor::extract_frame_from_reader(
+        reader,
+        time_sec,
+        &config::default_options(),
+    ))
+}
+
+/**
+ * 一次提取多个时间点的帧 - WebAssembly导出函数
+ *
+ * 只打开和探测一次视频，比多次调用 extractVideoFrame 快得多，适合生成缩略图条。
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param times - 提取帧的时间点(秒)，不要求有序
+ * @returns 与 times 一一对应的VideoResult数组；任何一帧失败时每一项都是同一个错误
+ */
+#[wasm_bindgen(js_name = extractVideoFrames)]
+pub fn extract_video_frames(
+    input_ptr: *const u8,
+    input_len: usize,
+    times: &[f64],
+) -> Vec<VideoResult> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    match video_processor::extract_frames_from_memory(input_data, times, &config::default_options())
+    {
+        Ok(frames) => frames
+            .into_iter()
+            .map(|frame| into_video_result(Ok(frame)))
+            .collect(),
+        Err(e) => {
+            log_error(&e);
+            times
+                .iter()
+                .map(|_| VideoResult::error(e.code, &e.message))
+                .collect()
+        }
+    }
+}
+
+/**
+ * 生成进度条预览用的雪碧图和WebVTT - WebAssembly导出函数
+ *
+ * 一次顺序解码取出所有缩略图，失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param interval_sec - 相邻两格之间的时间间隔(秒)
+ * @param tile_width - 每格的宽度(像素)，高度按显示宽高比计算
+ * @param columns - 每行的格数
+ * @param image_url - WebVTT中引用雪碧图时使用的地址
+ * @param encoding - 雪碧图的编码格式，不能为Raw
+ * @returns 编码后的雪碧图、尺寸和WebVTT文件内容
+ */
+#[wasm_bindgen(js_name = generateSpriteSheet)]
+pub fn generate_sprite_sheet(
+    input_ptr: *const u8,
+    input_len: usize,
+    interval_sec: f64,
+    tile_width: u32,
+    columns: u32,
+    image_url: &str,
+    encoding: OutputEncoding,
+) -> Result<SpriteSheet, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        sprite_sheet::generate_sprite_sheet(
+            path,
+            interval_sec,
+            tile_width,
+            columns,
+            image_url,
+            encoding,
+            &config::default_options(),
+        )
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 生成鼠标悬停用的GIF动画预览 - WebAssembly导出函数
+ *
+ * 失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param start_sec - 预览的起点(秒)
+ * @param duration_sec - 预览的时长(秒)
+ * @param fps - 动画的帧率，不超过50
+ * @param width - 动画的宽度(像素)，高度按显示宽高比计算
+ * @returns 无限循环的GIF文件内容
+ */
+#[wasm_bindgen(js_name = generateGifPreview)]
+pub fn generate_gif_preview(
+    input_ptr: *const u8,
+    input_len: usize,
+    start_sec: f64,
+    duration_sec: f64,
+    fps: f64,
+    width: u32,
+) -> Result<Vec<u8>, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        preview::generate_gif_preview(path, start_sec, duration_sec, fps, width)
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 生成指定格式的动态预览 - WebAssembly导出函数
+ *
+ * 与 `generateGifPreview` 相同，可以选择无损全彩色的动画WebP或APNG（视频内容的文件通常比GIF大）；失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param start_sec - 预览的起点(秒)
+ * @param duration_sec - 预览的时长(秒)
+ * @param fps - 动画的帧率，GIF不超过50，其他格式不超过60
+ * @param width - 动画的宽度(像素)，高度按显示宽高比计算
+ * @param format - 动画的容器格式
+ * @returns 无限循环的动画文件内容
+ */
+#[wasm_bindgen(js_name = generatePreview)]
+pub fn generate_preview(
+    input_ptr: *const u8,
+    input_len: usize,
+    start_sec: f64,
+    duration_sec: f64,
+    fps: f64,
+    width: u32,
+    format: PreviewFormat,
+) -> Result<Vec<u8>, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    let preview = PreviewOptions {
+        start: start_sec,
+        duration: duration_sec,
+        fps,
+        width,
+        format,
+    };
+    video_processor::with_temp_file(input_data, |path| {
+        preview::generate_preview(path, &preview, &config::default_options())
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 截取一段MP4预览 - WebAssembly导出函数
+ *
+ * 从起点之前最近的关键帧开始原样复制数据包，不重新编码；失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param start_sec - 片段的起点(秒)
+ * @param duration_sec - 片段的时长(秒)，不超过60秒
+ * @returns 可以直接交给 video 元素播放的MP4文件内容
+ */
+#[wasm_bindgen(js_name = clipPreview)]
+pub fn clip_preview(
+    input_ptr: *const u8,
+    input_len: usize,
+    start_sec: f64,
+    duration_sec: f64,
+) -> Result<Vec<u8>, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        remux::clip_preview(path, start_sec, duration_sec)
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 计算指定时间点帧的BlurHash - WebAssembly导出函数
+ *
+ * 只需要占位图时使用，不必把整帧像素传回JavaScript；失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param time_sec - 提取帧的时间点(秒)
+ * @param components_x - 水平方向的分量数量(1 - 9)，常用4
+ * @param components_y - 垂直方向的分量数量(1 - 9)，常用3
+ * @returns BlurHash字符串
+ */
+#[wasm_bindgen(js_name = computeBlurHash)]
+pub fn compute_blur_hash(
+    input_ptr: *const u8,
+    input_len: usize,
+    time_sec: f64,
+    components_x: u32,
+    components_y: u32,
+) -> Result<String, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        blurhash::compute_blurhash(
+            path,
+            time_sec,
+            components_x,
+            components_y,
+            &config::default_options(),
+        )
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 计算指定时间点帧的ThumbHash - WebAssembly导出函数
+ *
+ * 与BlurHash相比还保留了宽高比和透明通道，解码时用 `thumbHashToDataURL` 等参考实现；失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param time_sec - 提取帧的时间点(秒)
+ * @returns ThumbHash字节
+ */
+#[wasm_bindgen(js_name = computeThumbHash)]
+pub fn compute_thumb_hash(
+    input_ptr: *const u8,
+    input_len: usize,
+    time_sec: f64,
+) -> Result<Vec<u8>, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        thumbhash::compute_thumbhash(path, time_sec, &config::default_options())
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 计算指定时间点帧的感知哈希(pHash) - WebAssembly导出函数
+ *
+ * 用于视频去重和识别几乎相同的重复上传，画面数据不离开WASM；失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param time_sec - 帧的时间点(秒)
+ * @returns 64位哈希(BigInt)，两个哈希异或后为1的位数不超过10左右时通常是同一画面
+ */
+#[wasm_bindgen(js_name = framePHash)]
+pub fn frame_phash(input_ptr: *const u8, input_len: usize, time_sec: f64) -> Result<u64, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| frame_hash::frame_phash(path, time_sec))
+        .map_err(|e| {
+            log_error(&e);
+            JsError::new(&e.to_string())
+        })
+}
+
+/**
+ * 提取平均颜色和调色板 - WebAssembly导出函数
+ *
+ * 用于播放器的氛围背景，在WASM内部缩小后聚类，不必把整帧像素传回JavaScript；失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param times - 采样帧的时间点(秒)，只取一帧时传一个时间点
+ * @param colors - 调色板颜色数量(1 - 16)，常用5
+ * @returns 平均颜色、调色板颜色及其所占比例
+ */
+#[wasm_bindgen(js_name = extractPalette)]
+pub fn extract_palette(
+    input_ptr: *const u8,
+    input_len: usize,
+    times: &[f64],
+    colors: u32,
+) -> Result<ColorPalette, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        palette::extract_palette(path, times, colors)
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 解码音频片段为PCM - WebAssembly导出函数
+ *
+ * 用于在页面上试听，浏览器不支持的音频编码也能播放；需要WAV文件时调用返回值的 toWav()。
+ * 没有音频流或参数无效时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param start_sec - 片段的开始时间(秒)
+ * @param duration - 片段的时长(秒)，不超过600秒，PCM数据不超过512MB
+ * @param sample_rate - 输出采样率(8000 - 192000 Hz)
+ * @param channels - 输出声道数(1 - 8)
+ * @param format - 输出采样格式
+ * @returns 交错排列的PCM数据及其采样率、声道数
+ */
+#[wasm_bindgen(js_name = extractAudio)]
+pub fn extract_audio(
+    input_ptr: *const u8,
+    input_len: usize,
+    start_sec: f64,
+    duration: f64,
+    sample_rate: u32,
+    channels: u32,
+    format: PcmFormat,
+) -> Result<AudioClip, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        audio::extract_audio(path, start_sec, duration, sample_rate, channels, format)
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 统计指定时间点帧的直方图 - WebAssembly导出函数
+ *
+ * 返回R、G、B和亮度各256档的直方图，可以用 shadowRatio/highlightRatio 剔除欠曝和过曝的缩略图；
+ * 失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @param time_sec - 帧的时间点(秒)
+ * @returns 各通道的直方图
+ */
+#[wasm_bindgen(js_name = analyzeFrame)]
+pub fn analyze_frame(
+    input_ptr: *const u8,
+    input_len: usize,
+    time_sec: f64,
+) -> Result<FrameHistogram, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| {
+        histogram::analyze_frame(path, time_sec, &config::default_options())
+    })
+    .map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+/**
+ * 测量音频响度 - WebAssembly导出函数
+ *
+ * 按EBU R128测量主音频流的综合响度、响度范围和真峰值，可以用 normalizationGain 得到上传前
+ * 归一化需要的增益；需要解码整条音轨，没有音频流时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @returns 综合响度(LUFS)、响度范围(LU)和真峰值(dBTP)
+ */
+#[wasm_bindgen(js_name = measureLoudness)]
+pub fn measure_loudness(input_ptr: *const u8, input_len: usize) -> Result<Loudness, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::with_temp_file(input_data, |path| loudness::measure_loudness(path)).map_err(
+        |e| {
+            log_error(&e);
+            JsError::new(&e.to_string())
+        },
+    )
+}
+
+/**
+ * 探测视频信息 - WebAssembly导出函数
+ *
+ * 只读取容器和流的信息，不解码任何帧，失败时抛出异常
+ *
+ * @param input_ptr - 输入视频数据的指针
+ * @param input_len - 输入视频数据的长度
+ * @returns 时长、尺寸、帧率、编解码器等视频信息
+ */
+#[wasm_bindgen(js_name = probeVideo)]
+pub fn probe_video(input_ptr: *const u8, input_len: usize) -> Result<VideoInfo, JsError> {
+    // 从指针创建安全的切片引用
+    let input_data = unsafe { slice::from_raw_parts(input_ptr, input_len) };
+
+    video_processor::probe_memory(input_data).map_err(|e| {
+        log_error(&e);
+        JsError::new(&e.to_string())
+    })
+}
+
+// 把JavaScript数据源适配为 Read + Seek
+struct SourceReader {
+    source: RandomAccessSource,
+    size: u64,
+    position: u64,
+}
+
+impl Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let length = buf.len().min((self.size - self.position) as usize) as u32;
+        let chunk = self.source.read_at(self.position as f64, length);
+        let n = chunk.len().min(buf.len());
+        buf[..n].copy_from_slice(&chunk[..n]);
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl Seek for SourceReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+        };
+
+        match target {
+            Some(target) => {
+                self.position = target;
+                Ok(target)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "定位到数据源开头之前",
+            )),
+        }
+    }
+}
+
+// 把处理结果转换为返回给JavaScript的VideoResult
+fn into_video_result(result: Result<RgbFrame, VideoError>) -> VideoResult {
+    match result {
+        Ok(frame) => {
             // 处理成功，返回结果
-            VideoResult::success(buffer)
+            VideoResult::success(frame.data, frame.width, frame.height)
+                .with_warnings(frame.warnings)
         }
         Err(e) => {
             // 处理失败，记录错误并返回错误结果
@@ -52,16 +804,169 @@ pub fn get_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
 
+/**
+ * 设置所有调用共用的默认值
+ *
+ * 未传入（undefined）的参数保持当前设置不变
+ *
+ * @param fflags - 解复用器标志，例如 "+genpts+igndts"
+ * @param error_concealment - 解码器错误隐藏标志，例如 "guess_mvs+deblock"
+ * @param max_input_bytes - 允许的最大输入数据大小（字节）
+ * @param max_temp_bytes - 单次抽帧的临时内存上限（字节），超出时返回错误代码 8；
+ *   不计入解码器的参考帧、解封装器的缓冲和输入数据的临时文件
+ */
+#[wasm_bindgen(js_name = setDefaults)]
+pub fn set_defaults(
+    fflags: Option<String>,
+    error_concealment: Option<String>,
+    max_input_bytes: Option<u32>,
+    max_temp_bytes: Option<u32>,
+) {
+    let mut global = config::global_config();
+    if fflags.is_some() {
+        global.default_options.fflags = fflags;
+    }
+    if error_concealment.is_some() {
+        global.default_options.error_concealment = error_concealment;
+    }
+    if let Some(max_input_bytes) = max_input_bytes {
+        global.max_input_bytes = max_input_bytes as usize;
+    }
+    if let Some(max_temp_bytes) = max_temp_bytes {
+        global.default_options.max_temp_bytes = Some(max_temp_bytes as usize);
+    }
+    config::configure(global);
+}
+
+/**
+ * 设置所有调用共用的默认输出格式和尺寸限制
+ *
+ * 未传入（undefined）的参数保持当前设置不变，宽高或字节上限传入0时取消该项限制
+ *
+ * @param output_format - 默认输出像素格式
+ * @param output_width - 默认输出宽度（像素），只设置宽高之一时另一个按宽高比计算
+ * @param output_height - 默认输出高度（像素）
+ * @param max_output_bytes - 单帧输出的字节上限，超出时自动等比缩小
+ */
+#[wasm_bindgen(js_name = setDefaultOutput)]
+pub fn set_default_output(
+    output_format: Option<OutputFormat>,
+    output_width: Option<u32>,
+    output_height: Option<u32>,
+    max_output_bytes: Option<u32>,
+) {
+    let mut global = config::global_config();
+    let options = &mut global.default_options;
+    if let Some(output_format) = output_format {
+        options.output_format = output_format;
+    }
+    if let Some(output_width) = output_width {
+        options.output_width = (output_width > 0).then_some(output_width);
+    }
+    if let Some(output_height) = output_height {
+        options.output_height = (output_height > 0).then_some(output_height);
+    }
+    if let Some(max_output_bytes) = max_output_bytes {
+        options.max_output_bytes = (max_output_bytes > 0).then_some(max_output_bytes as usize);
+    }
+    config::configure(global);
+}
+
+/**
+ * 设置FFmpeg的全局日志级别
+ *
+ * 默认只输出错误；排查单个文件时更适合使用按次捕获日志的接口，避免日志刷屏
+ *
+ * @param level - 日志级别
+ */
+#[wasm_bindgen(js_name = setLogLevel)]
+pub fn set_log_level(level: LogLevel) {
+    let mut global = config::global_config();
+    global.log_level = Some(level);
+    config::configure(global);
+}
+
+/**
+ * 设置线性内存增长的阈值和回调
+ *
+ * 抽帧、拼图、动画编码和音频提取分配大块内存之前预估增长之后的大小，即将越过某个阈值时调用 `handler.onGrow`，
+ * 返回 false 或抛出异常时这次调用失败，错误代码为 8；当前大小已经超过的阈值不再触发
+ *
+ * @param thresholds - 阈值（字节）
+ * @param handler - 回调对象，传入 undefined 时移除回调
+ */
+#[wasm_bindgen(js_name = setMemoryGrowthHandler)]
+pub fn set_memory_growth_handler(thresholds: &[u32], handler: Option<MemoryGrowthHandler>) {
+    match handler {
+        Some(handler) => {
+            let thresholds: Vec<usize> = thresholds.iter().map(|&t| t as usize).collect();
+            memory_watch::set_memory_growth_hook(&thresholds, move |growth| {
+                let allowed = handler.on_grow(
+                    growth.current_bytes as f64,
+                    growth.projected_bytes as f64,
+                    growth.threshold as f64,
+                );
+                // 回调抛出异常时记录日志并拒绝，与返回 false 相同
+                allowed.unwrap_or_else(|e| {
+                    log_error(&VideoError::new(
+                        VideoErrorCode::MemoryLimit,
+                        Some(format!("内存增长回调抛出异常: {:?}", e)),
+                    ));
+                    false
+                })
+            });
+        }
+        None => memory_watch::clear_memory_growth_hook(),
+    }
+}
+
+/**
+ * 开启或关闭输出缓冲区复用
+ *
+ * 开启后抽帧结果的缓冲区来自复用池，用完结果后调用 `result.recycle()` 或 `result.free()` 归还，
+ * 减少长时间运行的页面中的内存分配和碎片
+ *
+ * @param enabled - 是否开启
+ */
+#[wasm_bindgen(js_name = setResultPooling)]
+pub fn set_result_pooling(enabled: bool) {
+    crate::buffer_pool::set_enabled(enabled);
+}
+
+/**
+ * 获取构建信息
+ *
+ * 返回链接的FFmpeg版本、可用的解码器和解复用器以及编译时启用的特性，
+ * 用于确认已部署的WASM二进制实际具备哪些能力
+ *
+ * @returns 构建信息对象
+ */
+#[wasm_bindgen(js_name = getBuildInfo)]
+pub fn get_build_info() -> BuildInfo {
+    crate::build_info::get_build_info()
+}
+
 /**
  * 检查FFmpeg和WASM环境是否正常
  *
- * 这个函数用于前端检查WASM环境和FFmpeg是否正确初始化
+ * 执行一次启动自检，只返回是否通过；需要失败原因时使用 `runSelfTest`
  *
  * @returns 如果一切正常则返回true
  */
 #[wasm_bindgen(js_name = checkEnvironment)]
 pub fn check_environment() -> bool {
-    // 初始化FFmpeg测试环境是否正常
-    crate::ffmpeg_init::initialize();
-    true
+    crate::self_test::run_self_test().passed
+}
+
+/**
+ * 执行启动自检
+ *
+ * 解码内置的测试图像并验证颜色转换，返回每一项检查的结果，
+ * 建议在应用启动时调用，让集成问题尽早暴露
+ *
+ * @returns 自检结果对象
+ */
+#[wasm_bindgen(js_name = runSelfTest)]
+pub fn run_self_test() -> SelfTestReport {
+    crate::self_test::run_self_test()
 }
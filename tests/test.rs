@@ -6,6 +6,11 @@ mod tests {
     use video_capture_wasm::error::VideoErrorCode;
     use video_capture_wasm::video_processor;
     use video_capture_wasm::ffmpeg_init;
+    use video_capture_wasm::validation;
+    use video_capture_wasm::transform::{Rotation, Transform};
+    use video_capture_wasm::job::ExtractionJob;
+    use video_capture_wasm::blurhash;
+    use video_capture_wasm::options::{ExtractOptions, Position};
     use std::fs::File;
     use std::io::Write;
     use std::path::Path;
@@ -38,19 +43,17 @@ mod tests {
         assert!(result.is_ok(), "帧提取失败: {:?}", result.err());
 
         // 提取帧数据并验证
-        let frame_data = result.unwrap();
+        let frame = result.unwrap();
+        let frame_data = frame.data;
         assert!(!frame_data.is_empty(), "提取的帧数据不应为空");
 
-        // 从数据中推测图片尺寸
-        let (width, height) = guess_image_dimensions(&frame_data);
-        println!("Guessed image dimensions: {}x{}", width, height);
-
         // 验证尺寸是否合理
-        assert!(width > 0 && height > 0, "图片尺寸推测失败");
+        let (width, height) = (frame.width, frame.height);
+        assert!(width > 0 && height > 0, "帧尺寸无效");
         assert_eq!(
             width * height * 3,
             frame_data.len() as u32,
-            "数据长度与推测尺寸不匹配"
+            "数据长度与帧尺寸不匹配"
         );
 
         // 同时保存PPM和PNG格式图片
@@ -126,6 +129,196 @@ mod tests {
         assert!(result.is_err());
     }
 
+    // 测试输入校验层对异常参数的处理
+    #[test]
+    fn test_input_validation() {
+        // 空数据和非法时间点应被拒绝
+        assert!(validation::validate_buffer(&[]).is_err());
+        assert!(validation::validate_time(f64::NAN).is_err());
+        assert!(validation::validate_time(-1.0).is_err());
+        assert!(validation::validate_time(0.0).is_ok());
+        assert!(validation::validate_percent(150.0).is_err());
+        assert!(validation::validate_percent(25.0).is_ok());
+
+        // 尺寸必须为正且不超过上限
+        assert!(validation::validate_dimensions(1920, 1080).is_ok());
+        assert!(validation::validate_dimensions(0, 1080).is_err());
+        assert!(validation::validate_dimensions(100_000, 10).is_err());
+
+        // 内存输入为空时直接返回 InvalidInput，不会触发解码
+        let result = video_processor::extract_frame_from_memory(&[], 0.0);
+        assert_eq!(result.err().map(|e| e.code), Some(VideoErrorCode::InvalidInput));
+    }
+
+    // 测试输出帧的旋转和翻转
+    #[test]
+    fn test_transform() {
+        // 2x1 的灰度图：[1, 2]
+        let data = [1u8, 2];
+
+        let rotate = Transform { rotation: Rotation::Rotate90, ..Default::default() };
+        assert_eq!(rotate.apply(&data, 2, 1, 1), (vec![1, 2], 1, 2));
+
+        let flip = Transform { flip_horizontal: true, ..Default::default() };
+        assert_eq!(flip.apply(&data, 2, 1, 1), (vec![2, 1], 2, 1));
+
+        // 先水平翻转再旋转270度
+        let both = Transform { rotation: Rotation::Rotate270, flip_horizontal: true, ..Default::default() };
+        assert_eq!(both.apply(&data, 2, 1, 1), (vec![1, 2], 1, 2));
+    }
+
+    // 测试抽帧任务的保存和恢复
+    #[test]
+    fn test_job_round_trip() {
+        let options = ExtractOptions {
+            fflags: Some("+genpts".to_string()),
+            output_width: Some(160),
+            transform: Transform { rotation: Rotation::Rotate180, ..Default::default() },
+            ..Default::default()
+        };
+
+        let mut job = ExtractionJob::new("input.mp4", vec![Position::Seconds(1.5), Position::Percent(50.0)], options);
+        job.skip();
+
+        let data = job.to_bytes().unwrap();
+        let restored = ExtractionJob::from_bytes(&data).unwrap();
+        assert_eq!(restored.input, job.input);
+        assert_eq!(restored.positions, job.positions);
+        assert_eq!(restored.next_position(), Some(Position::Percent(50.0)));
+        assert_eq!(restored.options.fflags.as_deref(), Some("+genpts"));
+        assert_eq!(restored.options.output_width, Some(160));
+        assert_eq!(restored.options.transform.rotation, Rotation::Rotate180);
+
+        // 截断的数据不能恢复
+        assert!(ExtractionJob::from_bytes(&data[..data.len() - 1]).is_err());
+    }
+
+    // 测试BlurHash编码
+    #[test]
+    fn test_blurhash() {
+        // 纯黑图像的哈希与参考实现一致：所有交流分量为0
+        let black = vec![0u8; 8 * 6 * 3];
+        assert_eq!(blurhash::encode(&black, 8, 6, 4, 3).unwrap(), "L00000fQfQfQfQfQfQfQfQfQfQfQ");
+
+        // 分量数量超出范围、数据长度与尺寸不符时返回错误
+        assert!(blurhash::encode(&black, 8, 6, 10, 3).is_err());
+        assert!(blurhash::encode(&black, 8, 5, 4, 3).is_err());
+    }
+
+    // 测试ThumbHash编码，期望值来自 evanw/thumbhash 的参考JavaScript实现
+    #[test]
+    fn test_thumbhash() {
+        use video_capture_wasm::thumbhash;
+
+        // 纯黑的横向图像：所有交流分量为0
+        let black: Vec<u8> = (0..8 * 6).flat_map(|_| [0u8, 0, 0, 255]).collect();
+        assert_eq!(
+            thumbhash::encode(&black, 8, 6).unwrap(),
+            [0, 8, 2, 5, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        // 纯色图像的头部：平均颜色和宽高比的打包方式（交流分量只有浮点误差，不做比较）
+        let red: Vec<u8> = (0..8 * 6).flat_map(|_| [200u8, 40, 40, 255]).collect();
+        assert_eq!(thumbhash::encode(&red, 8, 6).unwrap()[..5], [87, 58, 3, 5, 128]);
+
+        // 左半透明、右半不透明的蓝色：带透明度的头部和透明度通道
+        let alpha: Vec<u8> = (0..16).flat_map(|i| [0u8, 0, 255, if i % 4 < 2 { 0 } else { 255 }]).collect();
+        assert_eq!(
+            thumbhash::encode(&alpha, 4, 4).unwrap(),
+            [21, 0, 130, 5, 0, 88, 43, 32, 136, 136, 132, 8, 39, 231, 137, 15, 0, 0, 112, 123, 136, 136, 135, 120, 120]
+        );
+
+        // 尺寸超出范围、数据长度不符时返回错误
+        assert!(thumbhash::encode(&black, 101, 1).is_err());
+        assert!(thumbhash::encode(&black, 8, 5).is_err());
+    }
+
+    // 测试由像素计算调色板
+    #[test]
+    fn test_palette() {
+        use video_capture_wasm::palette::palette_from_pixels;
+
+        // 三个红色像素和一个蓝色像素
+        let pixels = [255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 0, 255];
+        let palette = palette_from_pixels(&pixels, 2);
+        assert_eq!(palette.average, [191, 0, 64]);
+        assert_eq!(palette.colors, [255, 0, 0, 0, 0, 255]);
+        assert_eq!(palette.weights, [0.75, 0.25]);
+
+        // 颜色种类少于要求的数量时只返回实际的颜色
+        assert_eq!(palette_from_pixels(&pixels, 5), palette);
+
+        // 只要一种颜色时为加权平均
+        let single = palette_from_pixels(&pixels, 1);
+        assert_eq!(single.colors, [191, 0, 64]);
+        assert_eq!(single.weights, [1.0]);
+    }
+
+    // 测试RGB24像素的直方图统计
+    #[test]
+    fn test_histogram() {
+        use video_capture_wasm::histogram::FrameHistogram;
+
+        // 黑、白、红、绿各一个像素
+        let pixels = [0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 255, 0];
+        let histogram = FrameHistogram::from_rgb24(&pixels);
+        assert_eq!(histogram.pixels, 4);
+        assert_eq!((histogram.red[0], histogram.red[255]), (2, 2));
+        assert_eq!((histogram.green[0], histogram.green[255]), (2, 2));
+        assert_eq!((histogram.blue[0], histogram.blue[255]), (3, 1));
+
+        // BT.709亮度：红为54，绿为182
+        let levels: Vec<usize> = (0..256).filter(|&level| histogram.luma[level] > 0).collect();
+        assert_eq!(levels, [0, 54, 182, 255]);
+        assert_eq!(histogram.mean_luma(), 122.75);
+        assert_eq!(histogram.shadow_ratio(54), 0.5);
+        assert_eq!(histogram.highlight_ratio(200), 0.25);
+
+        // 不足一个像素的尾部字节不计入
+        assert_eq!(FrameHistogram::from_rgb24(&pixels[..5]).pixels, 1);
+    }
+
+    // 测试沙箱子进程结果的序列化，被中途终止的子进程输出不能当作成功结果
+    #[test]
+    #[cfg(all(feature = "sandbox", unix))]
+    fn test_sandbox_response() {
+        use video_capture_wasm::sandbox::{decode_response, encode_response};
+        use video_capture_wasm::video_processor::{FrameInfo, RgbFrame};
+
+        let frame = RgbFrame {
+            width: 2,
+            height: 2,
+            data: (0..12).collect(),
+            info: FrameInfo { storage_width: 2, storage_height: 2, display_width: 2, display_height: 2 },
+            warnings: Vec::new(),
+        };
+        let response = encode_response(&Ok(frame.clone()));
+        assert_eq!(decode_response(&response).unwrap(), frame);
+
+        // 只写出了一部分像素
+        let truncated = decode_response(&response[..response.len() - 1]);
+        assert_eq!(truncated.err().map(|e| e.code), Some(VideoErrorCode::DecoderFailed));
+    }
+
+    // 测试输出尺寸预估：旋转90度的流按旋转后的画面计算要求的输出宽度
+    #[test]
+    fn test_estimate_rotated_output() {
+        use video_capture_wasm::video_processor::{FrameInfo, OutputEstimate};
+
+        let info = FrameInfo { storage_width: 1920, storage_height: 1080, display_width: 1920, display_height: 1080 };
+        let options = ExtractOptions { output_width: Some(160), ..Default::default() };
+        let estimate = OutputEstimate::new(&info, Rotation::Rotate90, &options).unwrap();
+        assert_eq!((estimate.width, estimate.height, estimate.bytes), (160, 284, 160 * 284 * 3));
+
+        // 调用方再旋转90度时恢复横向画面
+        let options = ExtractOptions {
+            transform: Transform { rotation: Rotation::Rotate90, ..Default::default() },
+            ..options
+        };
+        let estimate = OutputEstimate::new(&info, Rotation::Rotate90, &options).unwrap();
+        assert_eq!((estimate.width, estimate.height), (284, 160));
+    }
+
       // 辅助函数：获取测试资源目录路径
       fn get_test_resources_path(filename: &str) -> String {
         // 集成测试中资源的路径应从项目根目录开始
@@ -207,25 +400,4 @@ mod tests {
 
         Ok(())
     }
-
-    // 辅助函数：从原始数据推测图片尺寸
-    // 这个函数基于数据大小和RGB格式（每像素3字节）推测图片尺寸
-    fn guess_image_dimensions(data: &[u8]) -> (u32, u32) {
-        // 假设图片是16:9宽高比的常见分辨率
-        // 根据数据大小和每像素3字节计算像素数
-        let total_pixels = data.len() / 3;
-        let width_options = [1280, 1920, 640, 320]; // 常见宽度
-
-        for &width in &width_options {
-            let height = total_pixels as u32 / width;
-            // 检查这个尺寸是否合理
-            if width * height * 3 == data.len() as u32 {
-                return (width, height);
-            }
-        }
-
-        // 如果上面的常见尺寸都不匹配，尝试计算一个接近正方形的尺寸
-        let sqrt_pixels = (total_pixels as f64).sqrt() as u32;
-        (sqrt_pixels, total_pixels as u32 / sqrt_pixels)
-    }
 }